@@ -0,0 +1,227 @@
+//! Derive macros for `query-druid`.
+//!
+//! Use them through the `derive` feature of `query-druid`, which re-exports
+//! [`DruidRow`](macro@DruidRow) as `query_druid::row::DruidRow`.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote};
+use syn::{parse_macro_input, Data, DeriveInput, Fields, LitStr};
+
+/// Aggregators taking only an output name and a source column.
+const FIELD_AGGREGATORS: &[&str] = &[
+    "long_sum",
+    "double_sum",
+    "float_sum",
+    "long_min",
+    "double_min",
+    "float_min",
+    "long_max",
+    "double_max",
+    "float_max",
+    "double_mean",
+    "long_first",
+    "double_first",
+    "float_first",
+    "long_last",
+    "double_last",
+    "float_last",
+    "long_any",
+    "double_any",
+    "float_any",
+];
+
+/// Aggregators that also take a maximum string size.
+const STRING_AGGREGATORS: &[&str] = &["string_first", "string_last", "string_any"];
+
+/// Derive `query_druid::row::DruidRow` for a struct with named fields.
+///
+/// See the `query_druid::row` module for the supported attributes.
+#[proc_macro_derive(DruidRow, attributes(druid))]
+pub fn derive_druid_row(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+enum Kind {
+    Dimension(String),
+    Count,
+    Aggregator(String, String),
+    StringAggregator(String, String),
+}
+
+struct Column {
+    ident: syn::Ident,
+    name: String,
+    kind: Kind,
+    skip: bool,
+}
+
+fn parse_field(field: &syn::Field) -> syn::Result<Column> {
+    let ident = field.ident.clone().unwrap();
+    let mut name = ident.to_string();
+    let mut kind = None;
+    let mut source = None;
+    let mut skip = false;
+    for attr in field.attrs.iter().filter(|a| a.path().is_ident("druid")) {
+        attr.parse_nested_meta(|meta| {
+            let key = meta
+                .path
+                .get_ident()
+                .map(|i| i.to_string())
+                .unwrap_or_default();
+            let mut set_kind = |k: Kind| {
+                if kind.replace(k).is_some() {
+                    Err(meta.error("a field can have only one aggregator"))
+                } else {
+                    Ok(())
+                }
+            };
+            match key.as_str() {
+                "rename" => name = meta.value()?.parse::<LitStr>()?.value(),
+                "skip" => skip = true,
+                "dimension" => {
+                    if meta.input.peek(syn::Token![=]) {
+                        source = Some(meta.value()?.parse::<LitStr>()?.value());
+                    }
+                }
+                "count" => set_kind(Kind::Count)?,
+                k if FIELD_AGGREGATORS.contains(&k) => {
+                    let field = meta.value()?.parse::<LitStr>()?.value();
+                    set_kind(Kind::Aggregator(key.clone(), field))?
+                }
+                k if STRING_AGGREGATORS.contains(&k) => {
+                    let field = meta.value()?.parse::<LitStr>()?.value();
+                    set_kind(Kind::StringAggregator(key.clone(), field))?
+                }
+                _ => return Err(meta.error("unknown druid attribute")),
+            }
+            Ok(())
+        })?;
+    }
+    let kind = match (kind, source) {
+        (Some(_), Some(_)) => {
+            return Err(syn::Error::new_spanned(
+                field,
+                "a field can't be both a dimension and an aggregator",
+            ))
+        }
+        (Some(kind), None) => kind,
+        (None, source) => Kind::Dimension(source.unwrap_or_else(|| name.clone())),
+    };
+    Ok(Column {
+        ident,
+        name,
+        kind,
+        skip,
+    })
+}
+
+fn expand(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    &input,
+                    "DruidRow needs a struct with named fields",
+                ))
+            }
+        },
+        _ => {
+            return Err(syn::Error::new_spanned(
+                &input,
+                "DruidRow can only be derived for structs",
+            ))
+        }
+    };
+    let columns = fields
+        .iter()
+        .map(parse_field)
+        .collect::<syn::Result<Vec<_>>>()?;
+    let used: Vec<&Column> = columns.iter().filter(|c| !c.skip).collect();
+
+    let names = used.iter().map(|c| &c.name);
+    let dimensions = used.iter().filter_map(|c| match &c.kind {
+        Kind::Dimension(source) => {
+            let name = &c.name;
+            Some(quote! {
+                ::query_druid::components::dimension_specs::DimensionSpec::default(
+                    #source.into(), #name.into(), ::std::option::Option::None,
+                )
+            })
+        }
+        _ => None,
+    });
+    let scan_sources = used.iter().filter_map(|c| {
+        let name = &c.name;
+        match &c.kind {
+            Kind::Dimension(source)
+            | Kind::Aggregator(_, source)
+            | Kind::StringAggregator(_, source) => Some(quote! {
+                (::std::string::String::from(#name), ::std::string::String::from(#source))
+            }),
+            Kind::Count => None,
+        }
+    });
+    let aggregations = used.iter().filter_map(|c| {
+        let name = &c.name;
+        let path = quote!(::query_druid::components::aggregations::Aggregator);
+        match &c.kind {
+            Kind::Dimension(_) => None,
+            Kind::Count => Some(quote!(#path::count(#name.into()))),
+            Kind::Aggregator(ctor, field) => {
+                let ctor = format_ident!("{}", ctor);
+                Some(quote!(#path::#ctor(#name.into(), #field.into())))
+            }
+            Kind::StringAggregator(ctor, field) => {
+                let ctor = format_ident!("{}", ctor);
+                Some(quote!(#path::#ctor(#name.into(), #field.into(), ::std::option::Option::None)))
+            }
+        }
+    });
+    let inits = columns.iter().map(|c| {
+        let ident = &c.ident;
+        let name = &c.name;
+        if c.skip {
+            quote!(#ident: ::std::default::Default::default())
+        } else {
+            quote!(#ident: ::query_druid::row::column(row, #name)?)
+        }
+    });
+
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::query_druid::row::DruidRow for #ident #ty_generics #where_clause {
+            fn columns() -> ::std::vec::Vec<::std::string::String> {
+                ::std::vec![#(::std::string::String::from(#names)),*]
+            }
+
+            fn scan_sources() -> ::std::vec::Vec<(::std::string::String, ::std::string::String)> {
+                ::std::vec![#(#scan_sources),*]
+            }
+
+            fn dimensions() -> ::std::vec::Vec<::query_druid::components::dimension_specs::DimensionSpec> {
+                ::std::vec![#(#dimensions),*]
+            }
+
+            fn aggregations() -> ::std::vec::Vec<::query_druid::components::aggregations::Aggregator> {
+                ::std::vec![#(#aggregations),*]
+            }
+
+            fn from_row(
+                row: &::std::collections::HashMap<
+                    ::std::string::String,
+                    ::query_druid::components::druid_types::DruidNativeType,
+                >,
+            ) -> ::std::result::Result<Self, ::query_druid::row::RowError> {
+                ::std::result::Result::Ok(Self {
+                    #(#inits),*
+                })
+            }
+        }
+    })
+}
@@ -0,0 +1,103 @@
+use std::collections::HashMap;
+
+use query_druid::{
+    components::{
+        aggregations::Aggregator, dimension_specs::DimensionSpec, druid_types::DruidNativeType,
+    },
+    queries::response::ScanResult,
+    row::DruidRow,
+};
+
+#[derive(Debug, DruidRow)]
+struct PageEdits {
+    page: String,
+    #[druid(rename = "user", dimension = "user_name")]
+    user_name: Option<String>,
+    #[druid(long_sum = "added")]
+    added: i64,
+    #[druid(count)]
+    edits: u64,
+    #[druid(string_last = "comment")]
+    last_comment: String,
+    #[druid(skip)]
+    note: String,
+}
+
+#[test]
+fn generates_query_parts() {
+    assert_eq!(
+        PageEdits::columns(),
+        vec!["page", "user", "added", "edits", "last_comment"]
+    );
+
+    let dimensions = PageEdits::dimensions();
+    assert_eq!(dimensions.len(), 2);
+    assert!(matches!(
+        &dimensions[1],
+        DimensionSpec::Default { dimension, output_name, .. } if dimension == "user_name" && output_name == "user"
+    ));
+
+    let aggregations = PageEdits::aggregations();
+    assert!(matches!(
+        &aggregations[0],
+        Aggregator::LongSum { name, field_name } if name == "added" && field_name == "added"
+    ));
+    assert!(matches!(&aggregations[1], Aggregator::Count { name } if name == "edits"));
+    assert!(matches!(
+        &aggregations[2],
+        Aggregator::StringLast { name, field_name, .. } if name == "last_comment" && field_name == "comment"
+    ));
+}
+
+#[test]
+fn decodes_rows() {
+    let row = HashMap::from([
+        ("page".to_string(), DruidNativeType::string("Rust")),
+        ("added".to_string(), DruidNativeType::long(42)),
+        ("edits".to_string(), DruidNativeType::long(3)),
+        ("last_comment".to_string(), DruidNativeType::string("typo")),
+    ]);
+    let edits = PageEdits::from_row(&row).unwrap();
+    assert_eq!(edits.page, "Rust");
+    assert_eq!(edits.user_name, None);
+    assert_eq!(edits.added, 42);
+    assert_eq!(edits.edits, 3);
+    assert_eq!(edits.last_comment, "typo");
+    assert_eq!(edits.note, "");
+}
+
+#[derive(Debug, DruidRow)]
+struct PageChange {
+    page: String,
+    #[druid(rename = "user", dimension = "user_name")]
+    user_name: Option<String>,
+    #[druid(long_sum = "added")]
+    added: i64,
+    #[druid(string_last = "comment")]
+    last_comment: String,
+}
+
+#[test]
+fn scans_source_columns() {
+    assert_eq!(
+        PageChange::scan_columns(),
+        vec!["page", "user_name", "added", "comment"]
+    );
+    assert_eq!(PageEdits::scan_columns().len(), 4);
+
+    let scan = ScanResult::CompactedList {
+        segment_id: "segment".into(),
+        columns: PageChange::scan_columns(),
+        events: vec![vec![
+            DruidNativeType::string("Rust"),
+            DruidNativeType::string("alice"),
+            DruidNativeType::long(1),
+            DruidNativeType::string("fix"),
+        ]],
+    };
+    let rows = PageChange::from_scan(&scan).unwrap();
+    assert_eq!(rows[0].page, "Rust");
+    assert_eq!(rows[0].user_name.as_deref(), Some("alice"));
+    assert_eq!(rows[0].added, 1);
+    assert_eq!(rows[0].last_comment, "fix");
+}
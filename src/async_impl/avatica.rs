@@ -0,0 +1,298 @@
+use std::{
+    sync::atomic::{AtomicU64, Ordering},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use super::client::{Client, Error};
+use crate::{
+    components::druid_types::DruidSqlType,
+    queries::avatica::{
+        AvaticaParameter, AvaticaRequest, AvaticaResponse, ColumnMetaData, Frame,
+        ResultSetResponse, StatementHandle, TypedValue,
+    },
+};
+
+/// Rows requested per frame when fetching results.
+const FRAME_SIZE: i32 = 1000;
+
+static CONNECTION_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// A connection to Druid's Avatica JSON endpoint, `/druid/v2/sql/avatica/`.
+///
+/// Avatica connections and statements live on a single broker. The router
+/// picks the broker from the `connectionId` of each request, so every request
+/// made through a connection carries its id. When several routers sit behind
+/// a load balancer, that balancer must also route on the connection id or
+/// keep the client sticky to one router.
+pub struct AvaticaConnection<'a> {
+    client: &'a Client,
+    url: String,
+    connection_id: String,
+}
+
+impl Client {
+    /// Open an Avatica connection through the SQL endpoint.
+    pub async fn avatica_connection(&self) -> Result<AvaticaConnection<'_>, Error> {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_nanos())
+            .unwrap_or_default();
+        let counter = CONNECTION_COUNTER.fetch_add(1, Ordering::Relaxed);
+        let connection = AvaticaConnection {
+            client: self,
+            url: self.sql_url("avatica/")?,
+            connection_id: format!("query-druid-{nanos:x}-{counter:x}"),
+        };
+        connection
+            .send(&AvaticaRequest::OpenConnection {
+                connection_id: connection.connection_id.clone(),
+            })
+            .await?;
+        Ok(connection)
+    }
+}
+
+impl<'a> AvaticaConnection<'a> {
+    pub fn id(&self) -> &str {
+        &self.connection_id
+    }
+
+    /// Send a request on this connection.
+    pub async fn send(&self, request: &AvaticaRequest) -> Result<AvaticaResponse, Error> {
+        let resp = self
+            .client
+            .http()
+            .post(&self.url)
+            .json(request)
+            .send()
+            .await?;
+        let status = resp.status();
+        let body = resp.bytes().await?;
+        match serde_json::from_slice(&body) {
+            Ok(AvaticaResponse::Error(e)) => Err(Error::Avatica(e)),
+            Ok(r) => Ok(r),
+            Err(_) if !status.is_success() => Err(Error::Response {
+                status: status.as_u16(),
+                body: String::from_utf8_lossy(&body).into_owned(),
+            }),
+            Err(e) => Err(Error::ResponseDecode(e.to_string())),
+        }
+    }
+
+    pub async fn create_statement(&self) -> Result<AvaticaStatement<'_, 'a>, Error> {
+        match self
+            .send(&AvaticaRequest::CreateStatement {
+                connection_id: self.connection_id.clone(),
+            })
+            .await?
+        {
+            AvaticaResponse::CreateStatement { statement_id, .. } => Ok(AvaticaStatement {
+                connection: self,
+                statement_id,
+            }),
+            r => Err(unexpected(r)),
+        }
+    }
+
+    /// Prepare a statement with `?` placeholders for parameters.
+    pub async fn prepare(&self, sql: &str) -> Result<PreparedStatement<'_, 'a>, Error> {
+        match self
+            .send(&AvaticaRequest::Prepare {
+                connection_id: self.connection_id.clone(),
+                sql: sql.into(),
+                max_row_count: -1,
+            })
+            .await?
+        {
+            AvaticaResponse::Prepare { statement } => Ok(PreparedStatement {
+                connection: self,
+                handle: statement,
+            }),
+            r => Err(unexpected(r)),
+        }
+    }
+
+    pub async fn close(self) -> Result<(), Error> {
+        self.send(&AvaticaRequest::CloseConnection {
+            connection_id: self.connection_id.clone(),
+        })
+        .await?;
+        Ok(())
+    }
+
+    async fn close_statement(&self, statement_id: u32) -> Result<(), Error> {
+        self.send(&AvaticaRequest::CloseStatement {
+            connection_id: self.connection_id.clone(),
+            statement_id,
+        })
+        .await?;
+        Ok(())
+    }
+
+    fn result_set(&self, response: AvaticaResponse) -> Result<AvaticaResultSet<'_, 'a>, Error> {
+        let result = match response {
+            AvaticaResponse::ExecuteResults {
+                missing_statement: true,
+                ..
+            } => {
+                return Err(Error::Client(
+                    "statement is missing on the broker".to_string(),
+                ))
+            }
+            AvaticaResponse::ExecuteResults { results, .. } => results.into_iter().next(),
+            r => return Err(unexpected(r)),
+        };
+        let ResultSetResponse {
+            statement_id,
+            signature,
+            first_frame,
+            ..
+        } = result.ok_or_else(|| Error::ResponseDecode("no result set".to_string()))?;
+        Ok(AvaticaResultSet {
+            connection: self,
+            statement_id,
+            columns: signature.map(|s| s.columns).unwrap_or_default(),
+            pending: first_frame,
+            next_offset: 0,
+            done: false,
+        })
+    }
+}
+
+/// A statement for running SQL without parameters.
+pub struct AvaticaStatement<'c, 'a> {
+    connection: &'c AvaticaConnection<'a>,
+    statement_id: u32,
+}
+
+impl<'c, 'a> AvaticaStatement<'c, 'a> {
+    pub fn id(&self) -> u32 {
+        self.statement_id
+    }
+
+    pub async fn execute(&self, sql: &str) -> Result<AvaticaResultSet<'c, 'a>, Error> {
+        let response = self
+            .connection
+            .send(&AvaticaRequest::PrepareAndExecute {
+                connection_id: self.connection.connection_id.clone(),
+                statement_id: self.statement_id,
+                sql: sql.into(),
+                max_rows_total: -1,
+                max_rows_in_first_frame: FRAME_SIZE,
+            })
+            .await?;
+        self.connection.result_set(response)
+    }
+
+    pub async fn close(self) -> Result<(), Error> {
+        self.connection.close_statement(self.statement_id).await
+    }
+}
+
+/// A prepared statement, which knows its result columns and parameter types
+/// before it is executed.
+pub struct PreparedStatement<'c, 'a> {
+    connection: &'c AvaticaConnection<'a>,
+    handle: StatementHandle,
+}
+
+impl<'c, 'a> PreparedStatement<'c, 'a> {
+    pub fn id(&self) -> u32 {
+        self.handle.id
+    }
+
+    pub fn columns(&self) -> &[ColumnMetaData] {
+        self.handle
+            .signature
+            .as_ref()
+            .map(|s| s.columns.as_slice())
+            .unwrap_or_default()
+    }
+
+    pub fn parameters(&self) -> &[AvaticaParameter] {
+        self.handle
+            .signature
+            .as_ref()
+            .map(|s| s.parameters.as_slice())
+            .unwrap_or_default()
+    }
+
+    /// Execute the statement with one value per `?` placeholder. It can be
+    /// executed again once the previous results have been read.
+    pub async fn execute(
+        &self,
+        parameters: &[DruidSqlType],
+    ) -> Result<AvaticaResultSet<'c, 'a>, Error> {
+        let response = self
+            .connection
+            .send(&AvaticaRequest::Execute {
+                statement_handle: self.handle.clone(),
+                parameter_values: parameters.iter().map(TypedValue::from).collect(),
+                first_frame_max_size: FRAME_SIZE,
+            })
+            .await?;
+        let mut result_set = self.connection.result_set(response)?;
+        if result_set.columns.is_empty() {
+            result_set.columns = self.columns().to_vec();
+        }
+        Ok(result_set)
+    }
+
+    pub async fn close(self) -> Result<(), Error> {
+        self.connection.close_statement(self.handle.id).await
+    }
+}
+
+/// Results of an executed statement, fetched frame by frame.
+pub struct AvaticaResultSet<'c, 'a> {
+    connection: &'c AvaticaConnection<'a>,
+    statement_id: u32,
+    columns: Vec<ColumnMetaData>,
+    pending: Option<Frame>,
+    next_offset: u64,
+    done: bool,
+}
+
+impl<'c, 'a> AvaticaResultSet<'c, 'a> {
+    pub fn columns(&self) -> &[ColumnMetaData] {
+        &self.columns
+    }
+
+    /// Get the next frame of rows, or `None` once all rows were returned.
+    pub async fn next_frame(&mut self) -> Result<Option<Frame>, Error> {
+        if self.done {
+            return Ok(None);
+        }
+        let frame = match self.pending.take() {
+            Some(frame) => frame,
+            None => match self
+                .connection
+                .send(&AvaticaRequest::Fetch {
+                    connection_id: self.connection.connection_id.clone(),
+                    statement_id: self.statement_id,
+                    offset: self.next_offset,
+                    frame_max_size: FRAME_SIZE,
+                })
+                .await?
+            {
+                AvaticaResponse::Fetch {
+                    missing_statement: true,
+                    ..
+                } => {
+                    return Err(Error::Client(
+                        "statement is missing on the broker".to_string(),
+                    ))
+                }
+                AvaticaResponse::Fetch { frame, .. } => frame,
+                r => return Err(unexpected(r)),
+            },
+        };
+        self.next_offset = frame.offset + frame.rows.len() as u64;
+        self.done = frame.done;
+        Ok(Some(frame))
+    }
+}
+
+fn unexpected(response: AvaticaResponse) -> Error {
+    Error::ResponseDecode(format!("unexpected avatica response {response:?}"))
+}
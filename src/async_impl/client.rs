@@ -1,28 +1,37 @@
-use std::collections::HashMap;
+use reqwest::Url;
+use serde::{de::DeserializeOwned, Serialize};
+
+use super::schema::SchemaCache;
 
 use crate::{
-    components::druid_types::DruidNativeType,
     queries::{
+        avatica::AvaticaError,
         datasource_metadata::DataSourceMetadata,
+        explain::{ExplainResult, ExplainRow},
         groupby::GroupBy,
         response::{
-            DataSourceMetadataResult, GroupByResult, QueryError, ScanResult, SearchResult,
-            SegmentMetadataResult, SqlResult, TimeBoundaryResult, TimeseriesResult, TopNResult,
+            DataSourceMetadataResult, GroupByResult, QueryError, ScanCompactedList, ScanList,
+            ScanResult, SearchResult, SegmentMetadataResult, TimeBoundaryResult,
+            TimeseriesResult, TopNResult,
         },
         scan::Scan,
         search::Search,
         segment_metadata::SegmentMetadata,
         sql::{ResultFormat, Sql},
+        sql_result::SqlResultSet,
         time_boundary::TimeBoundary,
         timeseries::Timeseries,
         topn::TopN,
+        validation::ValidationError,
     },
 };
 
 /// An HTTP connector to Druid.
 ///
 /// It can hold two different URLs for using either the native queries or the
-/// SQL query.
+/// SQL query. A third, management URL points at the router (or the
+/// coordinator/overlord) and is used for the ingestion and cluster management
+/// APIs.
 ///
 /// Submit a query to Druid and get a parsed response.
 ///
@@ -51,6 +60,9 @@ pub struct Client {
     inner: reqwest::Client,
     native_endpoint: Option<String>,
     sql_endpoint: Option<String>,
+    management_endpoint: Option<String>,
+    pub(super) schema_cache: SchemaCache,
+    validate_queries: bool,
 }
 
 /// An error originating from this library.
@@ -64,6 +76,16 @@ pub enum Error {
     ResponseDecode(String),
     #[error("error response from druid {0}")]
     QueryError(QueryError),
+    #[error("druid responded with status {status}: {body}")]
+    Response { status: u16, body: String },
+    #[error("task {id} failed: {message}")]
+    TaskFailed { id: String, message: String },
+    #[error("task {id} did not complete in time")]
+    TaskTimeout { id: String },
+    #[error("{0}")]
+    Avatica(AvaticaError),
+    #[error(transparent)]
+    Validation(#[from] ValidationError),
 }
 
 impl Client {
@@ -74,6 +96,9 @@ impl Client {
                 inner,
                 native_endpoint: Some(native_endpoint),
                 sql_endpoint: Some(sql_endpoint),
+                management_endpoint: None,
+                schema_cache: SchemaCache::default(),
+                validate_queries: false,
             })
         } else {
             Err(Error::Client("could not create a client".to_string()))
@@ -87,6 +112,9 @@ impl Client {
                 inner,
                 native_endpoint: Some(native_endpoint),
                 sql_endpoint: None,
+                management_endpoint: None,
+                schema_cache: SchemaCache::default(),
+                validate_queries: false,
             })
         } else {
             Err(Error::Client("could not create a client".to_string()))
@@ -100,12 +128,46 @@ impl Client {
                 inner,
                 native_endpoint: None,
                 sql_endpoint: Some(sql_endpoint),
+                management_endpoint: None,
+                schema_cache: SchemaCache::default(),
+                validate_queries: false,
             })
         } else {
             Err(Error::Client("could not create a client".to_string()))
         }
     }
 
+    /// Create a new `Client` for the ingestion and cluster management APIs.
+    ///
+    /// The endpoint is the base URL of the router, for example
+    /// `http://localhost:8888/`.
+    pub fn management_client(management_endpoint: String) -> Result<Self, Error> {
+        if let Ok(inner) = reqwest::Client::builder().gzip(true).build() {
+            Ok(Self {
+                inner,
+                native_endpoint: None,
+                sql_endpoint: None,
+                management_endpoint: Some(management_endpoint),
+                schema_cache: SchemaCache::default(),
+                validate_queries: false,
+            })
+        } else {
+            Err(Error::Client("could not create a client".to_string()))
+        }
+    }
+
+    /// Set the base URL used for the ingestion and cluster management APIs.
+    pub fn set_management_endpoint(&mut self, management_endpoint: String) {
+        self.management_endpoint = Some(management_endpoint);
+    }
+
+    /// Check native queries with their `validate` method before sending them,
+    /// failing with [`Error::Validation`] instead of a Druid error. Off by
+    /// default.
+    pub fn set_query_validation(&mut self, validate: bool) {
+        self.validate_queries = validate;
+    }
+
     /// Change the internal `reqwest::Client`.
     ///
     /// See also [`Self::get_default_builder`].
@@ -118,6 +180,16 @@ impl Client {
         reqwest::Client::builder().gzip(true)
     }
 
+    fn validate<F>(&self, validate: F) -> Result<(), Error>
+    where
+        F: FnOnce() -> Result<(), ValidationError>,
+    {
+        if self.validate_queries {
+            validate()?;
+        }
+        Ok(())
+    }
+
     fn is_native(&self) -> Result<(), Error> {
         self.native_endpoint
             .as_ref()
@@ -132,6 +204,102 @@ impl Client {
             .map(|_| ())
     }
 
+    /// Build the full URL of a management API `path` like
+    /// `druid/indexer/v1/task`.
+    pub(super) fn management_url(&self, path: &str) -> Result<String, Error> {
+        let base = self
+            .management_endpoint
+            .as_ref()
+            .ok_or_else(|| Error::Client("not a management client".to_string()))?;
+        Ok(format!(
+            "{}/{}",
+            base.trim_end_matches('/'),
+            path.trim_start_matches('/')
+        ))
+    }
+
+    /// Build the URL of a management API `path` followed by `segments`, like
+    /// a task id, percent-encoding each segment so that it can't change the
+    /// path.
+    pub(super) fn management_segments_url(
+        &self,
+        path: &str,
+        segments: &[&str],
+    ) -> Result<Url, Error> {
+        let mut url = Url::parse(&self.management_url(path)?)
+            .map_err(|e| Error::Client(format!("invalid management endpoint: {e}")))?;
+        url.path_segments_mut()
+            .map_err(|_| Error::Client("invalid management endpoint".to_string()))?
+            .pop_if_empty()
+            .extend(segments);
+        Ok(url)
+    }
+
+    /// Build the full URL of a `path` under the SQL endpoint, like `task`
+    /// for `/druid/v2/sql/task`.
+    pub(super) fn sql_url(&self, path: &str) -> Result<String, Error> {
+        self.is_sql()?;
+        let base = self.sql_endpoint.as_ref().unwrap();
+        Ok(format!(
+            "{}/{}",
+            base.trim_end_matches('/'),
+            path.trim_start_matches('/')
+        ))
+    }
+
+    /// Build the URL of a `path` under the SQL endpoint followed by
+    /// `segments`, like a query id, percent-encoding each segment.
+    pub(super) fn sql_segments_url(&self, path: &str, segments: &[&str]) -> Result<Url, Error> {
+        let mut url = Url::parse(&self.sql_url(path)?)
+            .map_err(|e| Error::Client(format!("invalid SQL endpoint: {e}")))?;
+        url.path_segments_mut()
+            .map_err(|_| Error::Client("invalid SQL endpoint".to_string()))?
+            .pop_if_empty()
+            .extend(segments);
+        Ok(url)
+    }
+
+    pub(super) fn http(&self) -> &reqwest::Client {
+        &self.inner
+    }
+
+    /// Decode a JSON response, turning Druid error payloads and unsuccessful
+    /// statuses into an [`Error`].
+    pub(super) async fn decode_json<T: DeserializeOwned>(
+        resp: reqwest::Response,
+    ) -> Result<T, Error> {
+        let status = resp.status();
+        let body = resp.bytes().await?;
+        let decode_err = match serde_json::from_slice(&body) {
+            Ok(r) if status.is_success() => return Ok(r),
+            Ok(_) => None,
+            Err(e) => Some(e),
+        };
+        if let Ok(e) = serde_json::from_slice::<QueryError>(&body) {
+            return Err(Error::QueryError(e));
+        }
+        match decode_err {
+            Some(e) if status.is_success() => Err(Error::ResponseDecode(e.to_string())),
+            _ => Err(Error::Response {
+                status: status.as_u16(),
+                body: String::from_utf8_lossy(&body).into_owned(),
+            }),
+        }
+    }
+
+    /// Check that a response without a meaningful body was successful.
+    pub(super) async fn expect_success(resp: reqwest::Response) -> Result<(), Error> {
+        let status = resp.status();
+        if status.is_success() {
+            Ok(())
+        } else {
+            Err(Error::Response {
+                status: status.as_u16(),
+                body: resp.text().await.unwrap_or_default(),
+            })
+        }
+    }
+
     pub async fn datasource_metadata(
         &self,
         q: DataSourceMetadata,
@@ -157,6 +325,7 @@ impl Client {
 
     pub async fn groupby(&self, q: GroupBy) -> Result<Vec<GroupByResult>, Error> {
         self.is_native()?;
+        self.validate(|| q.validate())?;
         let resp = self
             .inner
             .post(self.native_endpoint.as_ref().unwrap())
@@ -177,6 +346,7 @@ impl Client {
 
     pub async fn scan(&self, q: Scan) -> Result<Vec<ScanResult>, Error> {
         self.is_native()?;
+        self.validate(|| q.validate())?;
         let resp = self
             .inner
             .post(self.native_endpoint.as_ref().unwrap())
@@ -197,6 +367,7 @@ impl Client {
 
     pub async fn search(&self, q: Search) -> Result<Vec<SearchResult>, Error> {
         self.is_native()?;
+        self.validate(|| q.validate())?;
         let resp = self
             .inner
             .post(self.native_endpoint.as_ref().unwrap())
@@ -260,6 +431,7 @@ impl Client {
 
     pub async fn timeseries(&self, q: Timeseries) -> Result<Vec<TimeseriesResult>, Error> {
         self.is_native()?;
+        self.validate(|| q.validate())?;
         let resp = self
             .inner
             .post(self.native_endpoint.as_ref().unwrap())
@@ -280,6 +452,7 @@ impl Client {
 
     pub async fn topn(&self, q: TopN) -> Result<Vec<TopNResult>, Error> {
         self.is_native()?;
+        self.validate(|| q.validate())?;
         let resp = self
             .inner
             .post(self.native_endpoint.as_ref().unwrap())
@@ -298,14 +471,94 @@ impl Client {
         }
     }
 
-    pub async fn sql(&self, q: Sql) -> Result<Vec<SqlResult>, Error> {
+    /// Like [`Self::groupby`], with each event deserialized straight into
+    /// `T` instead of a map.
+    pub async fn groupby_as<T: DeserializeOwned>(
+        &self,
+        q: GroupBy,
+    ) -> Result<Vec<GroupByResult<T>>, Error> {
+        self.validate(|| q.validate())?;
+        self.native_as(&q).await
+    }
+
+    /// Like [`Self::scan`], with each event deserialized straight into `T`.
+    ///
+    /// With the `compactedList` result format, each row is zipped with the
+    /// column names before deserializing `T`, and comes back in a
+    /// [`ScanResult::List`].
+    pub async fn scan_as<T: DeserializeOwned>(&self, q: Scan) -> Result<Vec<ScanResult<T>>, Error> {
+        self.validate(|| q.validate())?;
+        if q.is_compacted_list() {
+            let batches: Vec<ScanCompactedList> = self.native_as(&q).await?;
+            batches
+                .into_iter()
+                .map(|batch| {
+                    batch
+                        .into_list()
+                        .map_err(|e| Error::ResponseDecode(e.to_string()))
+                })
+                .collect()
+        } else {
+            let batches: Vec<ScanList<T>> = self.native_as(&q).await?;
+            Ok(batches.into_iter().map(ScanResult::from).collect())
+        }
+    }
+
+    /// Like [`Self::timeseries`], with each result deserialized straight into
+    /// `T`.
+    pub async fn timeseries_as<T: DeserializeOwned>(
+        &self,
+        q: Timeseries,
+    ) -> Result<Vec<TimeseriesResult<T>>, Error> {
+        self.validate(|| q.validate())?;
+        self.native_as(&q).await
+    }
+
+    /// Like [`Self::topn`], with each ranked row deserialized straight into
+    /// `T`.
+    pub async fn topn_as<T: DeserializeOwned>(&self, q: TopN) -> Result<Vec<TopNResult<T>>, Error> {
+        self.validate(|| q.validate())?;
+        self.native_as(&q).await
+    }
+
+    async fn native_as<Q: Serialize, R: DeserializeOwned>(&self, q: &Q) -> Result<R, Error> {
+        self.is_native()?;
+        let resp = self
+            .inner
+            .post(self.native_endpoint.as_ref().unwrap())
+            .json(q)
+            .send()
+            .await?;
+        Self::decode_json(resp).await
+    }
+
+    /// Run a SQL query and decode its rows with the column types.
+    ///
+    /// The `header`, `typesHeader` and `sqlTypesHeader` options are turned
+    /// on unless the query sets them, so that the result carries the column
+    /// schema. With `header` turned off, the type headers are left alone as
+    /// Druid rejects them without it. Without the types, values are decoded
+    /// by their JSON type, or kept as strings in the CSV format.
+    pub async fn sql(&self, mut q: Sql) -> Result<SqlResultSet, Error> {
         self.is_sql()?;
+        SqlResultSet::request_schema(&mut q);
         let resp = self
             .inner
             .post(self.sql_endpoint.as_ref().unwrap())
             .json(&q)
             .send()
             .await?;
+        let status = resp.status();
+        if !status.is_success() {
+            let body = resp.text().await.unwrap_or_default();
+            return Err(match serde_json::from_str::<QueryError>(&body) {
+                Ok(e) => Error::QueryError(e),
+                Err(_) => Error::Response {
+                    status: status.as_u16(),
+                    body,
+                },
+            });
+        }
         let text = match resp.text().await {
             Ok(s) => s,
             Err(_) => {
@@ -314,49 +567,86 @@ impl Client {
                 ))
             }
         };
-        match q.result_format {
-            None | Some(ResultFormat::Object) | Some(ResultFormat::Array) => {
-                match serde_json::from_str(&text) {
-                    Ok(r) => Ok(r),
-                    Err(_) => match serde_json::from_str::<QueryError>(&text) {
-                        Ok(e) => Err(Error::QueryError(e)),
-                        Err(_) => Err(Error::ResponseDecode(
-                            "response is not valid JSON".to_string(),
-                        )),
-                    },
-                }
-            }
-            Some(ResultFormat::ObjectLines) => {
-                let maybe_objects: Result<Vec<HashMap<String, DruidNativeType>>, _> =
-                    text.trim().lines().map(serde_json::from_str).collect();
-                match maybe_objects {
-                    Ok(v) => Ok(v.into_iter().map(SqlResult::Object).collect()),
-                    Err(_) => Err(Error::ResponseDecode(
-                        "part of the response is not valid JSON".to_string(),
-                    )),
-                }
-            }
-            Some(ResultFormat::ArrayLines) => {
-                let maybe_arrays: Result<Vec<Vec<DruidNativeType>>, _> =
-                    text.trim().lines().map(serde_json::from_str).collect();
-                match maybe_arrays {
-                    Ok(v) => Ok(v.into_iter().map(SqlResult::Array).collect()),
-                    Err(_) => Err(Error::ResponseDecode(
-                        "part of the response is not valid JSON".to_string(),
-                    )),
-                }
-            }
-            Some(ResultFormat::Csv) => Ok(text
-                .lines()
-                .map(|line| SqlResult::Csv(line.to_string()))
-                .collect()),
-        }
+        SqlResultSet::parse(&text, &q).map_err(Error::ResponseDecode)
+    }
+
+    /// Run a SQL query in the object result format and decode each row
+    /// directly into `T`.
+    ///
+    /// ```no_run
+    /// # use std::error::Error;
+    /// use query_druid::prelude::{Client, Sql};
+    /// use serde::Deserialize;
+    ///
+    /// #[derive(Deserialize)]
+    /// struct PageEdits {
+    ///     page: String,
+    ///     edits: i64,
+    /// }
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn Error>> {
+    /// let client = Client::sql_client("http://localhost:8888/druid/v2/sql/".to_string())?;
+    /// let query = Sql::new("SELECT page, COUNT(*) AS edits FROM wikipedia GROUP BY page");
+    /// let rows: Vec<PageEdits> = client.sql_as(query).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn sql_as<T: DeserializeOwned>(&self, mut q: Sql) -> Result<Vec<T>, Error> {
+        self.is_sql()?;
+        q.result_format = Some(ResultFormat::Object);
+        q.header = None;
+        q.types_header = None;
+        q.sql_types_header = None;
+        let resp = self
+            .inner
+            .post(self.sql_endpoint.as_ref().unwrap())
+            .json(&q)
+            .send()
+            .await?;
+        Self::decode_json(resp).await
+    }
+
+    /// Run `EXPLAIN PLAN FOR` on a SQL query and parse the native queries it
+    /// is planned into.
+    ///
+    /// The `useNativeQueryExplain` context parameter is always enabled, and
+    /// the result format and header settings of the query are ignored.
+    pub async fn explain(&self, mut q: Sql) -> Result<ExplainResult, Error> {
+        self.is_sql()?;
+        q.query = format!("EXPLAIN PLAN FOR {}", q.query);
+        q.result_format = Some(ResultFormat::Object);
+        q.header = None;
+        q.types_header = None;
+        q.sql_types_header = None;
+        q.context = Some(
+            q.context
+                .take()
+                .unwrap_or_default()
+                .use_native_query_explain(true),
+        );
+        let resp = self
+            .inner
+            .post(self.sql_endpoint.as_ref().unwrap())
+            .json(&q)
+            .send()
+            .await?;
+        let rows: Vec<ExplainRow> = Self::decode_json(resp).await?;
+        let row = rows
+            .into_iter()
+            .next()
+            .ok_or_else(|| Error::ResponseDecode("explain returned no rows".to_string()))?;
+        ExplainResult::try_from(row).map_err(|e| Error::ResponseDecode(e.to_string()))
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::Client;
+    use super::{Client, Error};
+    use crate::{
+        components::{data_sources::DataSource, granularities::Granularity},
+        queries::timeseries::Timeseries,
+    };
 
     #[test]
     fn new_test() {
@@ -369,8 +659,58 @@ mod tests {
             inner: reqwest::Client::new(),
             native_endpoint: Some("http://localhost:8888/druid/v2".to_string()),
             sql_endpoint: Some("http://localhost:8888/druid/v2/sql".to_string()),
+            management_endpoint: None,
+            schema_cache: Default::default(),
+            validate_queries: false,
         };
         assert_eq!(new_client.native_endpoint, client.native_endpoint);
         assert_eq!(new_client.sql_endpoint, client.sql_endpoint);
     }
+
+    #[test]
+    fn management_url_joins_paths() {
+        let client = Client::management_client("http://localhost:8888/".to_string())
+            .expect("couldn't create a client");
+        assert_eq!(
+            client.management_url("/druid/indexer/v1/task").unwrap(),
+            "http://localhost:8888/druid/indexer/v1/task"
+        );
+        assert!(
+            Client::native_client("http://localhost:8888/druid/v2/".to_string())
+                .unwrap()
+                .management_url("druid/indexer/v1/task")
+                .is_err()
+        );
+        assert_eq!(
+            client
+                .management_segments_url("druid/indexer/v1/task", &["a/b c?", "status"])
+                .unwrap()
+                .as_str(),
+            "http://localhost:8888/druid/indexer/v1/task/a%2Fb%20c%3F/status"
+        );
+        assert_eq!(
+            Client::sql_client("http://localhost:8888/druid/v2/sql/".to_string())
+                .unwrap()
+                .sql_segments_url("statements", &["q/1", "results"])
+                .unwrap()
+                .as_str(),
+            "http://localhost:8888/druid/v2/sql/statements/q%2F1/results"
+        );
+    }
+
+    #[tokio::test]
+    async fn validates_before_sending() {
+        let mut client = Client::native_client("http://localhost:1/druid/v2".to_string())
+            .expect("couldn't create a client");
+        client.set_query_validation(true);
+        let query = Timeseries::new(
+            DataSource::table("wikipedia".into()),
+            &[],
+            Granularity::simple("all".into()),
+        );
+        assert!(matches!(
+            client.timeseries(query).await,
+            Err(Error::Validation(e)) if e.problems[0].path == "intervals"
+        ));
+    }
 }
@@ -0,0 +1,169 @@
+use std::time::Duration;
+
+use serde::Serialize;
+
+use super::client::{Client, Error};
+use crate::ingestion::{
+    supervisors::{SupervisorId, SupervisorSpec, SupervisorStatus},
+    tasks::{
+        IndexParallelIoConfig, IndexParallelTask, IngestionMode, InputFormat, InputSource,
+        RowSchema, RowStats, TaskReports, TaskState, TaskStatus, TaskStatusResponse, TaskSubmitted,
+    },
+};
+
+/// How often [`Client::ingest_rows`] checks on its task.
+const TASK_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+impl Client {
+    /// Submit a task to the overlord and return its id.
+    pub async fn submit_task<T: Serialize>(&self, task: &T) -> Result<String, Error> {
+        let resp = self
+            .http()
+            .post(self.management_url("druid/indexer/v1/task")?)
+            .json(task)
+            .send()
+            .await?;
+        let submitted: TaskSubmitted = Self::decode_json(resp).await?;
+        Ok(submitted.task)
+    }
+
+    pub async fn task_status(&self, task_id: &str) -> Result<TaskStatus, Error> {
+        let url = self.management_segments_url("druid/indexer/v1/task", &[task_id, "status"])?;
+        let resp = self.http().get(url).send().await?;
+        let status: TaskStatusResponse = Self::decode_json(resp).await?;
+        Ok(status.status)
+    }
+
+    pub async fn task_reports(&self, task_id: &str) -> Result<TaskReports, Error> {
+        let url = self.management_segments_url("druid/indexer/v1/task", &[task_id, "reports"])?;
+        let resp = self.http().get(url).send().await?;
+        Self::decode_json(resp).await
+    }
+
+    /// Poll the status of a task every `poll_interval` until it completes,
+    /// successfully or not.
+    ///
+    /// Gives up with [`Error::TaskTimeout`] once `timeout` has passed. The
+    /// task itself keeps running.
+    pub async fn wait_for_task(
+        &self,
+        task_id: &str,
+        poll_interval: Duration,
+        timeout: Duration,
+    ) -> Result<TaskStatus, Error> {
+        let deadline = tokio::time::Instant::now() + timeout;
+        loop {
+            let status = self.task_status(task_id).await?;
+            if status.status_code.is_complete() {
+                return Ok(status);
+            }
+            if tokio::time::Instant::now() + poll_interval > deadline {
+                return Err(Error::TaskTimeout {
+                    id: task_id.to_string(),
+                });
+            }
+            tokio::time::sleep(poll_interval).await;
+        }
+    }
+
+    /// Ingest `rows` into `datasource` with an `index_parallel` task using an
+    /// `inline` input source.
+    ///
+    /// The rows are serialized to newline-delimited JSON, so their fields must
+    /// match the columns named in `schema`. The call waits for the task to
+    /// finish, for at most `timeout` as in [`Client::wait_for_task`], and
+    /// returns the row statistics of its segment building phase.
+    pub async fn ingest_rows<T: Serialize>(
+        &self,
+        datasource: &str,
+        rows: &[T],
+        schema: RowSchema,
+        timeout: Duration,
+    ) -> Result<RowStats, Error> {
+        let mut data = String::new();
+        for row in rows {
+            let line = serde_json::to_string(row)
+                .map_err(|e| Error::Client(format!("could not serialize row: {e}")))?;
+            data.push_str(&line);
+            data.push('\n');
+        }
+        let io_config = IndexParallelIoConfig::new(InputSource::inline(data))
+            .input_format(InputFormat::json())
+            .append_to_existing(schema.mode == IngestionMode::Append);
+        let task = IndexParallelTask::new(schema.into_data_schema(datasource.into()), io_config);
+        let task_id = self.submit_task(&task).await?;
+        let status = self
+            .wait_for_task(&task_id, TASK_POLL_INTERVAL, timeout)
+            .await?;
+        if status.status_code == TaskState::Failed {
+            return Err(Error::TaskFailed {
+                id: task_id,
+                message: status.error_msg.unwrap_or_default(),
+            });
+        }
+        self.task_reports(&task_id)
+            .await?
+            .ingestion_stats_and_errors
+            .and_then(|report| report.payload.row_stats)
+            .and_then(|phases| phases.build_segments)
+            .ok_or_else(|| Error::ResponseDecode("task report has no row stats".to_string()))
+    }
+}
+
+impl Client {
+    /// Create a supervisor, or update it if one already exists for the
+    /// datasource, and return its id.
+    pub async fn submit_supervisor(&self, spec: &SupervisorSpec) -> Result<String, Error> {
+        let resp = self
+            .http()
+            .post(self.management_url("druid/indexer/v1/supervisor")?)
+            .json(spec)
+            .send()
+            .await?;
+        let supervisor: SupervisorId = Self::decode_json(resp).await?;
+        Ok(supervisor.id)
+    }
+
+    /// List the ids of all supervisors.
+    pub async fn supervisors(&self) -> Result<Vec<String>, Error> {
+        let resp = self
+            .http()
+            .get(self.management_url("druid/indexer/v1/supervisor")?)
+            .send()
+            .await?;
+        Self::decode_json(resp).await
+    }
+
+    pub async fn supervisor_status(&self, supervisor_id: &str) -> Result<SupervisorStatus, Error> {
+        let url = self
+            .management_segments_url("druid/indexer/v1/supervisor", &[supervisor_id, "status"])?;
+        let resp = self.http().get(url).send().await?;
+        Self::decode_json(resp).await
+    }
+
+    pub async fn suspend_supervisor(&self, supervisor_id: &str) -> Result<(), Error> {
+        self.supervisor_action(supervisor_id, "suspend").await
+    }
+
+    pub async fn resume_supervisor(&self, supervisor_id: &str) -> Result<(), Error> {
+        self.supervisor_action(supervisor_id, "resume").await
+    }
+
+    /// Clear the stored offsets so the supervisor restarts reading from the
+    /// earliest or latest offsets of the stream.
+    pub async fn reset_supervisor(&self, supervisor_id: &str) -> Result<(), Error> {
+        self.supervisor_action(supervisor_id, "reset").await
+    }
+
+    pub async fn terminate_supervisor(&self, supervisor_id: &str) -> Result<(), Error> {
+        self.supervisor_action(supervisor_id, "terminate").await
+    }
+
+    async fn supervisor_action(&self, supervisor_id: &str, action: &str) -> Result<(), Error> {
+        let url =
+            self.management_segments_url("druid/indexer/v1/supervisor", &[supervisor_id, action])?;
+        let resp = self.http().post(url).send().await?;
+        Self::decode_json::<serde_json::Value>(resp).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,155 @@
+use std::collections::HashMap;
+
+use reqwest::Url;
+
+use super::client::{Client, Error};
+use crate::management::{
+    compaction::{CompactionConfig, CompactionStatus, CompactionStatusResponse},
+    lookups::{LookupSpec, LookupStatus, NodeLookupsState},
+};
+
+impl Client {
+    /// Create the empty lookup config, needed once on a new cluster before any
+    /// lookup can be added.
+    pub async fn initialize_lookups(&self) -> Result<(), Error> {
+        let resp = self
+            .http()
+            .post(self.management_url("druid/coordinator/v1/lookups/config")?)
+            .json(&HashMap::<String, ()>::new())
+            .send()
+            .await?;
+        Self::expect_success(resp).await
+    }
+
+    /// List the tiers that have lookups configured.
+    pub async fn lookup_tiers(&self) -> Result<Vec<String>, Error> {
+        let resp = self
+            .http()
+            .get(self.management_url("druid/coordinator/v1/lookups/config")?)
+            .send()
+            .await?;
+        Self::decode_json(resp).await
+    }
+
+    /// List the names of the lookups configured in a tier.
+    pub async fn lookups(&self, tier: &str) -> Result<Vec<String>, Error> {
+        let url = self.management_segments_url("druid/coordinator/v1/lookups/config", &[tier])?;
+        let resp = self.http().get(url).send().await?;
+        Self::decode_json(resp).await
+    }
+
+    pub async fn lookup(&self, tier: &str, lookup_id: &str) -> Result<LookupSpec, Error> {
+        let url = self
+            .management_segments_url("druid/coordinator/v1/lookups/config", &[tier, lookup_id])?;
+        let resp = self.http().get(url).send().await?;
+        Self::decode_json(resp).await
+    }
+
+    /// Create a lookup, or update it if it exists. Updates must carry a
+    /// higher version than the current one.
+    pub async fn set_lookup(
+        &self,
+        tier: &str,
+        lookup_id: &str,
+        spec: &LookupSpec,
+    ) -> Result<(), Error> {
+        let url = self
+            .management_segments_url("druid/coordinator/v1/lookups/config", &[tier, lookup_id])?;
+        let resp = self.http().post(url).json(spec).send().await?;
+        Self::expect_success(resp).await
+    }
+
+    pub async fn delete_lookup(&self, tier: &str, lookup_id: &str) -> Result<(), Error> {
+        let url = self
+            .management_segments_url("druid/coordinator/v1/lookups/config", &[tier, lookup_id])?;
+        let resp = self.http().delete(url).send().await?;
+        Self::expect_success(resp).await
+    }
+
+    /// Whether a lookup is loaded on all nodes of its tier, with the nodes
+    /// still pending if not.
+    pub async fn lookup_status(&self, tier: &str, lookup_id: &str) -> Result<LookupStatus, Error> {
+        let url = self
+            .management_segments_url("druid/coordinator/v1/lookups/status", &[tier, lookup_id])?;
+        let resp = self
+            .http()
+            .get(url)
+            .query(&[("detailed", "true")])
+            .send()
+            .await?;
+        Self::decode_json(resp).await
+    }
+
+    /// The lookups loaded, being loaded and being dropped on each node of a
+    /// tier, keyed by `host:port`.
+    pub async fn lookup_node_status(
+        &self,
+        tier: &str,
+    ) -> Result<HashMap<String, NodeLookupsState>, Error> {
+        let url =
+            self.management_segments_url("druid/coordinator/v1/lookups/nodeStatus", &[tier])?;
+        let resp = self.http().get(url).send().await?;
+        Self::decode_json(resp).await
+    }
+
+    /// Read the key/value pairs of a lookup.
+    ///
+    /// Introspection is served by the nodes that load the lookup, so
+    /// `node_url` must point at a broker or historical, like
+    /// `http://localhost:8082`, rather than the coordinator.
+    pub async fn introspect_lookup(
+        &self,
+        node_url: &str,
+        lookup_id: &str,
+    ) -> Result<HashMap<String, String>, Error> {
+        let mut url = Url::parse(node_url)
+            .map_err(|e| Error::Client(format!("invalid node url {node_url}: {e}")))?;
+        url.path_segments_mut()
+            .map_err(|_| Error::Client(format!("invalid node url {node_url}")))?
+            .pop_if_empty()
+            .extend(["druid", "v1", "lookups", "introspect", lookup_id]);
+        let resp = self.http().get(url).send().await?;
+        Self::decode_json(resp).await
+    }
+
+    pub async fn compaction_config(&self, datasource: &str) -> Result<CompactionConfig, Error> {
+        let url =
+            self.management_segments_url("druid/coordinator/v1/config/compaction", &[datasource])?;
+        let resp = self.http().get(url).send().await?;
+        Self::decode_json(resp).await
+    }
+
+    /// Create or replace the auto-compaction config of `config.data_source`.
+    pub async fn set_compaction_config(&self, config: &CompactionConfig) -> Result<(), Error> {
+        let resp = self
+            .http()
+            .post(self.management_url("druid/coordinator/v1/config/compaction")?)
+            .json(config)
+            .send()
+            .await?;
+        Self::expect_success(resp).await
+    }
+
+    /// Disable auto-compaction of a datasource.
+    pub async fn delete_compaction_config(&self, datasource: &str) -> Result<(), Error> {
+        let url =
+            self.management_segments_url("druid/coordinator/v1/config/compaction", &[datasource])?;
+        let resp = self.http().delete(url).send().await?;
+        Self::expect_success(resp).await
+    }
+
+    /// Auto-compaction progress of every datasource, or only of `datasource`.
+    pub async fn compaction_status(
+        &self,
+        datasource: Option<&str>,
+    ) -> Result<Vec<CompactionStatus>, Error> {
+        let mut req = self
+            .http()
+            .get(self.management_url("druid/coordinator/v1/compaction/status")?);
+        if let Some(datasource) = datasource {
+            req = req.query(&[("dataSource", datasource)]);
+        }
+        let resp: CompactionStatusResponse = Self::decode_json(req.send().await?).await?;
+        Ok(resp.latest_status)
+    }
+}
@@ -1 +1,8 @@
+pub mod avatica;
 pub mod client;
+mod ingestion;
+mod management;
+mod schema;
+mod system_tables;
+pub mod sql_task;
+pub mod statement;
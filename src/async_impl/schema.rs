@@ -0,0 +1,133 @@
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use super::client::{Client, Error};
+use crate::{
+    components::{data_sources::DataSource, druid_types::DruidSqlType, intervals::Interval},
+    queries::{
+        information_schema::{ColumnDescriptor, TableInfo, TableSchema},
+        segment_metadata::{AnalysisType, SegmentMetadata},
+        sql::Sql,
+    },
+};
+
+const DEFAULT_SCHEMA_TTL: Duration = Duration::from_secs(300);
+
+/// Table schemas fetched by [`Client::table_schema`], keyed by table name and
+/// whether they include the native analysis.
+pub(super) struct SchemaCache {
+    ttl: Duration,
+    entries: Mutex<HashMap<(String, bool), (Instant, TableSchema)>>,
+}
+
+impl Default for SchemaCache {
+    fn default() -> Self {
+        Self {
+            ttl: DEFAULT_SCHEMA_TTL,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+impl SchemaCache {
+    fn get(&self, table: &str, with_analysis: bool) -> Option<TableSchema> {
+        let entries = self.entries.lock().unwrap();
+        entries
+            .get(&(table.to_string(), with_analysis))
+            .filter(|(fetched, _)| fetched.elapsed() < self.ttl)
+            .map(|(_, schema)| schema.clone())
+    }
+
+    fn insert(&self, with_analysis: bool, schema: TableSchema) {
+        if self.ttl.is_zero() {
+            return;
+        }
+        let mut entries = self.entries.lock().unwrap();
+        entries.insert(
+            (schema.table.clone(), with_analysis),
+            (Instant::now(), schema),
+        );
+    }
+}
+
+impl Client {
+    /// Change how long table schemas are cached, five minutes by default. A
+    /// zero duration disables caching.
+    pub fn set_schema_cache_ttl(&mut self, ttl: Duration) {
+        self.schema_cache.ttl = ttl;
+        self.clear_schema_cache();
+    }
+
+    pub fn clear_schema_cache(&self) {
+        self.schema_cache.entries.lock().unwrap().clear();
+    }
+
+    /// List the datasources in the `druid` schema of `INFORMATION_SCHEMA.TABLES`.
+    pub async fn list_tables(&self) -> Result<Vec<TableInfo>, Error> {
+        self.sql_as(Sql::new(
+            "SELECT TABLE_SCHEMA, TABLE_NAME, TABLE_TYPE FROM INFORMATION_SCHEMA.TABLES \
+             WHERE TABLE_SCHEMA = 'druid' ORDER BY TABLE_NAME",
+        ))
+        .await
+    }
+
+    /// Get the columns of a datasource from `INFORMATION_SCHEMA.COLUMNS`.
+    pub async fn table_schema(&self, datasource: &str) -> Result<TableSchema, Error> {
+        if let Some(schema) = self.schema_cache.get(datasource, false) {
+            return Ok(schema);
+        }
+        let schema = self.fetch_table_schema(datasource).await?;
+        self.schema_cache.insert(false, schema.clone());
+        Ok(schema)
+    }
+
+    /// Like [`Self::table_schema`], with each column merged with its
+    /// cardinality, size and min/max analysis over all segments of the
+    /// datasource.
+    ///
+    /// This needs both the SQL and the native endpoint.
+    pub async fn table_schema_with_analysis(&self, datasource: &str) -> Result<TableSchema, Error> {
+        if let Some(schema) = self.schema_cache.get(datasource, true) {
+            return Ok(schema);
+        }
+        let mut schema = self.fetch_table_schema(datasource).await?;
+        let query = SegmentMetadata::new(DataSource::table(datasource.into()))
+            .intervals(&[Interval {
+                start: "1000-01-01".into(),
+                end: "3000-01-01".into(),
+            }])
+            .merge(true)
+            .analysis_types(&[
+                AnalysisType::Cardinality,
+                AnalysisType::Size,
+                AnalysisType::Minmax,
+            ]);
+        if let Some(mut analyzed) = self.segment_metadata(query).await?.into_iter().next() {
+            for column in &mut schema.columns {
+                column.analysis = analyzed.columns.remove(&column.name);
+            }
+        }
+        self.schema_cache.insert(true, schema.clone());
+        Ok(schema)
+    }
+
+    async fn fetch_table_schema(&self, datasource: &str) -> Result<TableSchema, Error> {
+        let query = Sql::new(
+            "SELECT COLUMN_NAME, DATA_TYPE, IS_NULLABLE, ORDINAL_POSITION \
+             FROM INFORMATION_SCHEMA.COLUMNS \
+             WHERE TABLE_SCHEMA = 'druid' AND TABLE_NAME = ? ORDER BY ORDINAL_POSITION",
+        )
+        .parameters(&[DruidSqlType::varchar(datasource)]);
+        let columns: Vec<ColumnDescriptor> = self.sql_as(query).await?;
+        if columns.is_empty() {
+            return Err(Error::Client(format!("table {datasource} does not exist")));
+        }
+        Ok(TableSchema {
+            table: datasource.into(),
+            columns,
+        })
+    }
+}
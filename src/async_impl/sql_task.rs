@@ -0,0 +1,85 @@
+use std::time::Duration;
+
+use super::client::{Client, Error};
+use crate::{
+    ingestion::{
+        msq::{MultiStageQueryReport, SqlTaskSubmitted},
+        tasks::TaskStatus,
+    },
+    queries::sql::Sql,
+};
+
+/// A handle to a multi-stage query task started with [`Client::sql_task`].
+pub struct SqlTask<'a> {
+    client: &'a Client,
+    task_id: String,
+}
+
+impl Client {
+    /// Submit a SQL statement, such as an `INSERT`, `REPLACE` or `SELECT`,
+    /// to the multi-stage query task engine at `/druid/v2/sql/task`.
+    ///
+    /// This needs both the SQL endpoint and the management endpoint, the
+    /// latter for following the task.
+    pub async fn sql_task(&self, q: Sql) -> Result<SqlTask<'_>, Error> {
+        let resp = self
+            .http()
+            .post(self.sql_url("task")?)
+            .json(&q)
+            .send()
+            .await?;
+        let submitted: SqlTaskSubmitted = Self::decode_json(resp).await?;
+        Ok(SqlTask {
+            client: self,
+            task_id: submitted.task_id,
+        })
+    }
+
+    /// Ask the overlord to stop a task.
+    pub async fn shutdown_task(&self, task_id: &str) -> Result<(), Error> {
+        let url = self.management_segments_url("druid/indexer/v1/task", &[task_id, "shutdown"])?;
+        let resp = self.http().post(url).send().await?;
+        Self::decode_json::<serde_json::Value>(resp).await?;
+        Ok(())
+    }
+}
+
+impl<'a> SqlTask<'a> {
+    pub fn id(&self) -> &str {
+        &self.task_id
+    }
+
+    pub async fn status(&self) -> Result<TaskStatus, Error> {
+        self.client.task_status(&self.task_id).await
+    }
+
+    /// Get the current report with stage progress, counters and warnings.
+    ///
+    /// Live reports are only available once the controller task has started.
+    pub async fn report(&self) -> Result<MultiStageQueryReport, Error> {
+        self.client
+            .task_reports(&self.task_id)
+            .await?
+            .multi_stage_query
+            .ok_or_else(|| {
+                Error::ResponseDecode("task has no multi-stage query report".to_string())
+            })
+    }
+
+    /// Wait for the task to complete, for at most `timeout`, and return its
+    /// final report.
+    pub async fn wait(
+        &self,
+        poll_interval: Duration,
+        timeout: Duration,
+    ) -> Result<MultiStageQueryReport, Error> {
+        self.client
+            .wait_for_task(&self.task_id, poll_interval, timeout)
+            .await?;
+        self.report().await
+    }
+
+    pub async fn cancel(&self) -> Result<(), Error> {
+        self.client.shutdown_task(&self.task_id).await
+    }
+}
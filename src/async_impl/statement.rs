@@ -0,0 +1,158 @@
+use std::time::Duration;
+
+use super::client::{Client, Error};
+use crate::queries::{
+    response::SqlResult,
+    sql::Sql,
+    statements::{StatementColumn, StatementInfo, StatementState},
+};
+
+/// A handle to an asynchronous query started with
+/// [`Client::submit_statement`].
+pub struct Statement<'a> {
+    client: &'a Client,
+    info: StatementInfo,
+}
+
+impl Client {
+    /// Submit a query to the asynchronous SQL statements API at
+    /// `/druid/v2/sql/statements`.
+    ///
+    /// The `executionMode` context parameter is set to `ASYNC` unless already
+    /// present. Set `selectDestination` to `durableStorage` in the context for
+    /// results too large for the task report.
+    pub async fn submit_statement(&self, mut q: Sql) -> Result<Statement<'_>, Error> {
+        let mut context = q.context.take().unwrap_or_default();
+        if context.execution_mode.is_none() {
+            context.execution_mode = Some("ASYNC".to_string());
+        }
+        q.context = Some(context);
+        let resp = self
+            .http()
+            .post(self.sql_url("statements")?)
+            .json(&q)
+            .send()
+            .await?;
+        let info = Self::decode_json(resp).await?;
+        Ok(Statement { client: self, info })
+    }
+}
+
+impl<'a> Statement<'a> {
+    pub fn id(&self) -> &str {
+        &self.info.query_id
+    }
+
+    /// The state as of the last submission, refresh or wait.
+    pub fn state(&self) -> StatementState {
+        self.info.state
+    }
+
+    pub fn schema(&self) -> &[StatementColumn] {
+        &self.info.schema
+    }
+
+    pub fn info(&self) -> &StatementInfo {
+        &self.info
+    }
+
+    /// Fetch the current status of the statement.
+    pub async fn refresh(&mut self) -> Result<&StatementInfo, Error> {
+        let resp = self
+            .client
+            .http()
+            .get(
+                self.client
+                    .sql_segments_url("statements", &[&self.info.query_id])?,
+            )
+            .send()
+            .await?;
+        self.info = Client::decode_json(resp).await?;
+        Ok(&self.info)
+    }
+
+    /// Poll the statement every `poll_interval` until it succeeds or fails.
+    ///
+    /// Gives up with [`Error::TaskTimeout`] once `timeout` has passed, like
+    /// [`Client::wait_for_task`]. The statement itself keeps running.
+    pub async fn wait(
+        &mut self,
+        poll_interval: Duration,
+        timeout: Duration,
+    ) -> Result<&StatementInfo, Error> {
+        let deadline = tokio::time::Instant::now() + timeout;
+        while !self.info.state.is_complete() {
+            if tokio::time::Instant::now() + poll_interval > deadline {
+                return Err(Error::TaskTimeout {
+                    id: self.info.query_id.clone(),
+                });
+            }
+            tokio::time::sleep(poll_interval).await;
+            self.refresh().await?;
+        }
+        Ok(&self.info)
+    }
+
+    /// Fetch a single page of results.
+    pub async fn page(&self, page: u64) -> Result<Vec<SqlResult>, Error> {
+        let resp = self
+            .client
+            .http()
+            .get(
+                self.client
+                    .sql_segments_url("statements", &[&self.info.query_id, "results"])?,
+            )
+            .query(&[
+                ("page", page.to_string().as_str()),
+                ("resultFormat", "object"),
+            ])
+            .send()
+            .await?;
+        Client::decode_json(resp).await
+    }
+
+    /// Iterate over the result pages of a successful statement.
+    pub fn pages(&self) -> StatementPages<'_, 'a> {
+        let pages = self
+            .info
+            .result
+            .as_ref()
+            .map(|r| r.pages.iter().map(|p| p.id).collect())
+            .unwrap_or_default();
+        StatementPages {
+            statement: self,
+            pages,
+            next: 0,
+        }
+    }
+
+    /// Cancel the statement with a `DELETE` request.
+    pub async fn cancel(&self) -> Result<(), Error> {
+        let resp = self
+            .client
+            .http()
+            .delete(
+                self.client
+                    .sql_segments_url("statements", &[&self.info.query_id])?,
+            )
+            .send()
+            .await?;
+        Client::expect_success(resp).await
+    }
+}
+
+/// Result pages of a [`Statement`], fetched one at a time.
+pub struct StatementPages<'s, 'a> {
+    statement: &'s Statement<'a>,
+    pages: Vec<u64>,
+    next: usize,
+}
+
+impl<'s, 'a> StatementPages<'s, 'a> {
+    /// Fetch the next page, or `None` once all pages were returned.
+    pub async fn next_page(&mut self) -> Option<Result<Vec<SqlResult>, Error>> {
+        let page = *self.pages.get(self.next)?;
+        self.next += 1;
+        Some(self.statement.page(page).await)
+    }
+}
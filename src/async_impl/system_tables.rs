@@ -0,0 +1,20 @@
+use super::client::{Client, Error};
+use crate::system_tables::SystemTableQuery;
+
+impl Client {
+    /// Query one of Druid's system tables and decode the rows.
+    ///
+    /// ```no_run
+    /// # async fn run(client: query_druid::prelude::Client) -> Result<(), query_druid::prelude::Error> {
+    /// use query_druid::system_tables::segments;
+    ///
+    /// let unavailable = client
+    ///     .system_table(&segments().datasource("wikipedia").is_published().is_unavailable())
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn system_table<Q: SystemTableQuery>(&self, query: &Q) -> Result<Vec<Q::Row>, Error> {
+        self.sql_as(query.to_sql()).await
+    }
+}
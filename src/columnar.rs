@@ -0,0 +1,317 @@
+//! Row to column conversion shared by the Arrow and Polars integrations.
+
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+
+use crate::{
+    components::druid_types::DruidNativeType,
+    queries::response::{GroupByResult, ScanResult, SqlResult, TimeseriesResult, TopNResult},
+};
+
+/// Name of the column holding the result timestamp.
+pub(crate) const TIMESTAMP: &str = "timestamp";
+const TIME: &str = "__time";
+
+/// The column types values are converted into.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ColumnKind {
+    Int64,
+    Float64,
+    Utf8,
+    StringList,
+    Timestamp,
+    /// Only from declared types, never inferred.
+    #[cfg(feature = "polars")]
+    Boolean,
+}
+
+pub(crate) struct Column<'r> {
+    pub(crate) name: &'r str,
+    pub(crate) values: Vec<Option<&'r DruidNativeType>>,
+}
+
+impl Column<'_> {
+    /// The type of the column, inferred from its name and values.
+    ///
+    /// JSON numbers with a fraction decode into `Float` first, so floats and
+    /// doubles are both widened to doubles.
+    pub(crate) fn kind(&self) -> ColumnKind {
+        if self.name == TIME {
+            return ColumnKind::Timestamp;
+        }
+        let (mut long, mut float, mut string, mut array) = (false, false, false, false);
+        for value in self.values.iter().flatten() {
+            match value {
+                DruidNativeType::Long(_) => long = true,
+                DruidNativeType::Float(_) | DruidNativeType::Double(_) => float = true,
+                DruidNativeType::String(_) => string = true,
+                DruidNativeType::Array(_) => array = true,
+            }
+        }
+        if array {
+            ColumnKind::StringList
+        } else if string {
+            ColumnKind::Utf8
+        } else if float {
+            ColumnKind::Float64
+        } else if long {
+            ColumnKind::Int64
+        } else {
+            ColumnKind::Utf8
+        }
+    }
+}
+
+/// Rows collected column by column.
+#[derive(Default)]
+pub(crate) struct Columns<'r> {
+    timestamps: Option<Vec<Option<i64>>>,
+    names: Vec<&'r str>,
+    positions: HashMap<&'r str, usize>,
+    values: Vec<Vec<Option<&'r DruidNativeType>>>,
+    rows: usize,
+    ordered: bool,
+}
+
+impl<'r> Columns<'r> {
+    /// Keep columns in the order of `names` rather than sorting them by name.
+    fn with_order<I: IntoIterator<Item = &'r str>>(names: I) -> Self {
+        let mut columns = Self {
+            ordered: true,
+            ..Default::default()
+        };
+        for name in names {
+            columns.position(name);
+        }
+        columns
+    }
+
+    fn position(&mut self, name: &'r str) -> usize {
+        if let Some(&position) = self.positions.get(name) {
+            return position;
+        }
+        self.names.push(name);
+        self.values.push(vec![None; self.rows]);
+        self.positions.insert(name, self.names.len() - 1);
+        self.names.len() - 1
+    }
+
+    fn push<I>(&mut self, timestamp: Option<&DateTime<Utc>>, fields: I)
+    where
+        I: IntoIterator<Item = (&'r str, &'r DruidNativeType)>,
+    {
+        if let Some(timestamp) = timestamp {
+            self.timestamps
+                .get_or_insert_with(|| vec![None; self.rows])
+                .push(Some(timestamp.timestamp_millis()));
+        } else if let Some(timestamps) = &mut self.timestamps {
+            timestamps.push(None);
+        }
+        for (name, value) in fields {
+            let position = self.position(name);
+            self.values[position].push(Some(value));
+        }
+        self.rows += 1;
+        for column in &mut self.values {
+            column.resize(self.rows, None);
+        }
+    }
+
+    /// The result timestamps in milliseconds, if the results have them, and
+    /// the other columns.
+    pub(crate) fn finish(self) -> (Option<Vec<Option<i64>>>, Vec<Column<'r>>) {
+        let mut columns: Vec<Column> = self
+            .names
+            .into_iter()
+            .zip(self.values)
+            .map(|(name, values)| Column { name, values })
+            .collect();
+        if !self.ordered {
+            columns.sort_by_key(|column| column.name);
+        }
+        (self.timestamps, columns)
+    }
+}
+
+/// Results that can be laid out as columns.
+pub(crate) trait ToColumns {
+    fn to_columns(&self) -> Result<Columns<'_>, &'static str>;
+}
+
+impl ToColumns for [ScanResult] {
+    fn to_columns(&self) -> Result<Columns<'_>, &'static str> {
+        let mut columns = match self.first() {
+            Some(ScanResult::List { columns, .. } | ScanResult::CompactedList { columns, .. }) => {
+                Columns::with_order(columns.iter().map(String::as_str))
+            }
+            None => Columns::default(),
+        };
+        for result in self {
+            match result {
+                ScanResult::List { events, .. } => {
+                    for event in events {
+                        columns.push(None, event.iter().map(|(k, v)| (k.as_str(), v)));
+                    }
+                }
+                ScanResult::CompactedList {
+                    columns: names,
+                    events,
+                    ..
+                } => {
+                    for event in events {
+                        columns.push(None, names.iter().map(String::as_str).zip(event));
+                    }
+                }
+            }
+        }
+        Ok(columns)
+    }
+}
+
+impl ToColumns for [GroupByResult] {
+    fn to_columns(&self) -> Result<Columns<'_>, &'static str> {
+        let mut columns = Columns::default();
+        for result in self {
+            columns.push(
+                Some(&result.timestamp),
+                result.event.iter().map(|(k, v)| (k.as_str(), v)),
+            );
+        }
+        Ok(columns)
+    }
+}
+
+impl ToColumns for [TimeseriesResult] {
+    fn to_columns(&self) -> Result<Columns<'_>, &'static str> {
+        let mut columns = Columns::default();
+        for result in self {
+            columns.push(
+                result.timestamp.as_ref(),
+                result.result.iter().map(|(k, v)| (k.as_str(), v)),
+            );
+        }
+        Ok(columns)
+    }
+}
+
+/// Flattened to one row per ranked value, with the bucket timestamp repeated.
+impl ToColumns for [TopNResult] {
+    fn to_columns(&self) -> Result<Columns<'_>, &'static str> {
+        let mut columns = Columns::default();
+        for result in self {
+            for row in &result.result {
+                columns.push(
+                    Some(&result.timestamp),
+                    row.iter().map(|(k, v)| (k.as_str(), v)),
+                );
+            }
+        }
+        Ok(columns)
+    }
+}
+
+/// Array results must start with the header row, queried with `header: true`.
+/// Object results keep the order of their header row, if they have one. CSV
+/// results can't be converted.
+impl ToColumns for [SqlResult] {
+    fn to_columns(&self) -> Result<Columns<'_>, &'static str> {
+        match self.first() {
+            Some(SqlResult::Array(header)) => {
+                let names = header
+                    .iter()
+                    .map(|name| name.as_str().ok_or("array results need a header row"))
+                    .collect::<Result<Vec<_>, _>>()?;
+                let mut columns = Columns::with_order(names.iter().copied());
+                for result in &self[1..] {
+                    match result {
+                        SqlResult::Array(row) => columns.push(None, names.iter().copied().zip(row)),
+                        _ => return Err("results have mixed formats"),
+                    }
+                }
+                Ok(columns)
+            }
+            Some(SqlResult::Csv(_)) => Err("CSV results can't be converted to columns"),
+            first => {
+                let (mut columns, rows) = match first {
+                    Some(SqlResult::Header(names)) => (
+                        Columns::with_order(names.iter().map(String::as_str)),
+                        &self[1..],
+                    ),
+                    _ => (Columns::default(), self),
+                };
+                for result in rows {
+                    match result {
+                        SqlResult::Object(row) => {
+                            columns.push(None, row.iter().map(|(k, v)| (k.as_str(), v)))
+                        }
+                        SqlResult::Header(_) => return Err("the header row must come first"),
+                        _ => return Err("results have mixed formats"),
+                    }
+                }
+                Ok(columns)
+            }
+        }
+    }
+}
+
+/// The value as a long, `None` for floats with a fraction or out of range
+/// rather than truncating them.
+pub(crate) fn as_i64(value: &DruidNativeType) -> Option<i64> {
+    match value {
+        DruidNativeType::Long(l) => Some(*l),
+        DruidNativeType::Float(f) => integral(f64::from(*f)),
+        DruidNativeType::Double(d) => integral(*d),
+        DruidNativeType::String(s) => s.parse().ok(),
+        DruidNativeType::Array(_) => None,
+    }
+}
+
+fn integral(d: f64) -> Option<i64> {
+    // i64::MAX rounds up to 2^63 as a double, which is out of range.
+    (d.fract() == 0.0 && d >= i64::MIN as f64 && d < i64::MAX as f64).then_some(d as i64)
+}
+
+pub(crate) fn as_f64(value: &DruidNativeType) -> Option<f64> {
+    match value {
+        DruidNativeType::Long(l) => Some(*l as f64),
+        DruidNativeType::String(s) => s.parse().ok(),
+        other => other.as_f64(),
+    }
+}
+
+#[cfg(any(feature = "arrow", feature = "polars"))]
+pub(crate) fn as_bool(value: &DruidNativeType) -> Option<bool> {
+    match value {
+        DruidNativeType::String(s) => s.parse().ok(),
+        other => as_i64(other).map(|l| l != 0),
+    }
+}
+
+/// Milliseconds since the epoch of a millisecond or RFC 3339 timestamp.
+pub(crate) fn as_millis(value: &DruidNativeType) -> Option<i64> {
+    match value {
+        DruidNativeType::String(s) => DateTime::parse_from_rfc3339(s)
+            .ok()
+            .map(|t| t.timestamp_millis()),
+        other => as_i64(other),
+    }
+}
+
+pub(crate) fn as_strings(value: &DruidNativeType) -> Option<Vec<&str>> {
+    match value {
+        DruidNativeType::Array(items) => Some(items.iter().map(String::as_str).collect()),
+        DruidNativeType::String(s) => Some(vec![s]),
+        _ => None,
+    }
+}
+
+pub(crate) fn to_string(value: &DruidNativeType) -> String {
+    match value {
+        DruidNativeType::String(s) => s.clone(),
+        DruidNativeType::Long(l) => l.to_string(),
+        DruidNativeType::Float(f) => f.to_string(),
+        DruidNativeType::Double(d) => d.to_string(),
+        DruidNativeType::Array(items) => items.join(","),
+    }
+}
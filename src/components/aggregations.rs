@@ -4,7 +4,7 @@ use super::{dimension_specs::DimensionSpec, filters::Filter};
 
 /// Native query aggregations.
 #[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(tag = "type", rename_all = "camelCase")]
+#[serde(tag = "type", rename_all = "camelCase", rename_all_fields = "camelCase")]
 pub enum Aggregator {
     Count {
         name: String,
@@ -316,4 +316,38 @@ impl Aggregator {
             round,
         }
     }
+
+    /// The output name of the aggregator.
+    pub fn name(&self) -> &str {
+        match self {
+            Self::Filtered { aggregator, .. } => aggregator.name(),
+            Self::Count { name }
+            | Self::LongSum { name, .. }
+            | Self::DoubleSum { name, .. }
+            | Self::FloatSum { name, .. }
+            | Self::LongMin { name, .. }
+            | Self::DoubleMin { name, .. }
+            | Self::FloatMin { name, .. }
+            | Self::LongMax { name, .. }
+            | Self::DoubleMax { name, .. }
+            | Self::FloatMax { name, .. }
+            | Self::DoubleMean { name, .. }
+            | Self::LongFirst { name, .. }
+            | Self::DoubleFirst { name, .. }
+            | Self::FloatFirst { name, .. }
+            | Self::StringFirst { name, .. }
+            | Self::LongLast { name, .. }
+            | Self::DoubleLast { name, .. }
+            | Self::FloatLast { name, .. }
+            | Self::StringLast { name, .. }
+            | Self::LongAny { name, .. }
+            | Self::DoubleAny { name, .. }
+            | Self::FloatAny { name, .. }
+            | Self::StringAny { name, .. }
+            | Self::Javascript { name, .. }
+            | Self::Grouping { name, .. }
+            | Self::Cardinality { name, .. }
+            | Self::HyperUnique { name, .. } => name,
+        }
+    }
 }
@@ -52,6 +52,14 @@ pub struct Context {
     pub use_approximate_count_distinct: Option<bool>,
     pub use_grouping_set_for_exact_distinct: Option<bool>,
     pub use_approximate_top_n: Option<bool>,
+    pub max_num_tasks: Option<u64>,
+    pub finalize_aggregations: Option<bool>,
+    pub rows_per_segment: Option<u64>,
+    pub durable_shuffle_storage: Option<bool>,
+    pub task_assignment: Option<String>,
+    pub execution_mode: Option<String>,
+    pub select_destination: Option<String>,
+    pub use_native_query_explain: Option<bool>,
 }
 
 impl Context {
@@ -263,4 +271,54 @@ impl Context {
         self.use_approximate_top_n = Some(flag);
         self
     }
+
+    /// Set the `maxNumTasks` multi-stage query parameter.
+    pub fn max_num_tasks(mut self, mnt: u64) -> Self {
+        self.max_num_tasks = Some(mnt);
+        self
+    }
+
+    /// Set the `finalizeAggregations` multi-stage query parameter.
+    pub fn finalize_aggregations(mut self, flag: bool) -> Self {
+        self.finalize_aggregations = Some(flag);
+        self
+    }
+
+    /// Set the `rowsPerSegment` multi-stage query parameter.
+    pub fn rows_per_segment(mut self, rps: u64) -> Self {
+        self.rows_per_segment = Some(rps);
+        self
+    }
+
+    /// Set the `durableShuffleStorage` multi-stage query parameter.
+    pub fn durable_shuffle_storage(mut self, flag: bool) -> Self {
+        self.durable_shuffle_storage = Some(flag);
+        self
+    }
+
+    /// Set the `taskAssignment` multi-stage query parameter, either `max` or
+    /// `auto`.
+    pub fn task_assignment(mut self, ta: String) -> Self {
+        self.task_assignment = Some(ta);
+        self
+    }
+
+    /// Set the `executionMode` parameter of the SQL statements API.
+    pub fn execution_mode(mut self, em: String) -> Self {
+        self.execution_mode = Some(em);
+        self
+    }
+
+    /// Set the `selectDestination` parameter, either `taskReport` or
+    /// `durableStorage`.
+    pub fn select_destination(mut self, sd: String) -> Self {
+        self.select_destination = Some(sd);
+        self
+    }
+
+    /// Set the `useNativeQueryExplain` parameter.
+    pub fn use_native_query_explain(mut self, flag: bool) -> Self {
+        self.use_native_query_explain = Some(flag);
+        self
+    }
 }
@@ -4,7 +4,7 @@ use super::druid_types::DruidNativeType;
 use crate::queries::Query;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(tag = "type", rename_all = "camelCase")]
+#[serde(tag = "type", rename_all = "camelCase", rename_all_fields = "camelCase")]
 pub enum DataSource {
     Table {
         name: String,
@@ -19,7 +19,8 @@ pub enum DataSource {
         column_names: Vec<String>,
         rows: Vec<Vec<DruidNativeType>>,
     },
-    Query(Box<Query>),
+    /// A subquery, sent as `{"type": "query", "query": {...}}`.
+    Query(#[serde(with = "subquery")] Box<Query>),
     Join {
         left: Box<DataSource>,  // must not be union
         right: Box<DataSource>, // must not be table, union or join
@@ -36,7 +37,7 @@ impl DataSource {
             DataSource::Lookup { .. } => "lookup",
             DataSource::Union { .. } => "union",
             DataSource::Inline { .. } => "inline",
-            DataSource::Query { .. } => "query",
+            DataSource::Query(_) => "query",
             DataSource::Join { .. } => "join",
         }
         .into()
@@ -105,6 +106,36 @@ impl DataSource {
     }
 }
 
+/// Nests the subquery under `query`, next to the `type` tag.
+mod subquery {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    use crate::queries::Query;
+
+    #[derive(Serialize)]
+    struct Nested<'a> {
+        query: &'a Query,
+    }
+
+    #[derive(Deserialize)]
+    struct Owned {
+        query: Box<Query>,
+    }
+
+    pub(super) fn serialize<S: Serializer>(
+        query: &Query,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        Nested { query }.serialize(serializer)
+    }
+
+    pub(super) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Box<Query>, D::Error> {
+        Owned::deserialize(deserializer).map(|owned| owned.query)
+    }
+}
+
 #[derive(Debug, Clone, thiserror::Error)]
 pub enum JoinDataSourceError {
     #[error("invalid {which} join source {datasource_type}")]
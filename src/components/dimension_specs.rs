@@ -1,4 +1,4 @@
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
 use super::{
     druid_types::DruidNativeType,
@@ -7,7 +7,7 @@ use super::{
 };
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(tag = "type", rename_all = "camelCase")]
+#[serde(tag = "type", rename_all = "camelCase", rename_all_fields = "camelCase", remote = "Self")]
 pub enum DimensionSpec {
     Default {
         dimension: String,
@@ -44,6 +44,44 @@ pub enum DimensionSpec {
     },
 }
 
+// A default spec keeping the column name is written as the bare name, the
+// only form the selector, in and regex filters take.
+impl Serialize for DimensionSpec {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match self {
+            DimensionSpec::Default {
+                dimension,
+                output_name,
+                output_type: None,
+            } if dimension == output_name => serializer.serialize_str(dimension),
+            _ => DimensionSpec::serialize(self, serializer),
+        }
+    }
+}
+
+// A bare column name is shorthand for the default dimension spec.
+impl<'de> Deserialize<'de> for DimensionSpec {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Repr {
+            Name(String),
+            #[serde(with = "DimensionSpec")]
+            Spec(DimensionSpec),
+        }
+        match Repr::deserialize(deserializer)? {
+            Repr::Name(name) => Ok(DimensionSpec::default(name.clone(), name, None)),
+            Repr::Spec(spec) => Ok(spec),
+        }
+    }
+}
+
 impl DimensionSpec {
     pub fn default(dimension: String, output_name: String, output_type: Option<String>) -> Self {
         Self::Default {
@@ -126,6 +164,18 @@ impl DimensionSpec {
             }),
         }
     }
+
+    /// The name of the dimension in the results.
+    pub fn output_name(&self) -> &str {
+        match self {
+            Self::Default { output_name, .. }
+            | Self::Extraction { output_name, .. }
+            | Self::Lookup { output_name, .. } => output_name,
+            Self::ListFiltered { delegate, .. }
+            | Self::RegexFiltered { delegate, .. }
+            | Self::PrefixFiltered { delegate, .. } => delegate.output_name(),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -71,7 +71,7 @@ impl DruidNativeType {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(tag = "type", content = "value", rename_all = "UPPERCASE")]
 pub enum DruidSqlType {
     Tinyint(i64),
@@ -142,3 +142,32 @@ impl DruidSqlType {
         Self::Date(d.into())
     }
 }
+
+macro_rules! sql_type_from {
+    ($($t:ty => $variant:ident),* $(,)?) => {
+        $(
+            impl From<$t> for DruidSqlType {
+                fn from(value: $t) -> Self {
+                    Self::$variant(value.into())
+                }
+            }
+        )*
+    };
+}
+
+sql_type_from!(
+    i8 => Tinyint,
+    i16 => Smallint,
+    i32 => Integer,
+    i64 => Bigint,
+    u8 => Smallint,
+    u16 => Integer,
+    u32 => Bigint,
+    bool => Boolean,
+    f32 => Float,
+    f64 => Double,
+    char => Char,
+    &str => Varchar,
+    String => Varchar,
+    DateTime<Utc> => Timestamp,
+);
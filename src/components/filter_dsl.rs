@@ -0,0 +1,282 @@
+//! A shorthand for building [`Filter`]s.
+//!
+//! Columns are compared with the methods of [`col`], and the resulting
+//! filters combined with `&`, `|` and `!`:
+//!
+//! ```
+//! use query_druid::components::filter_dsl::col;
+//!
+//! let filter = col("page").eq("Rust") & col("added").between(1, 10)
+//!     | !col("user").like("bot%");
+//! ```
+//!
+//! Comparisons with numbers use the numeric ordering, and comparisons with
+//! strings Druid's default lexicographic ordering.
+
+use std::ops::{BitAnd, BitOr, Not};
+
+use super::{
+    dimension_specs::DimensionSpec, filters::Filter, intervals::Interval, ordering::Ordering,
+};
+
+/// A column to build filters on.
+#[derive(Debug, Clone)]
+pub struct Column {
+    name: String,
+}
+
+/// Start a filter on the column `name`.
+pub fn col(name: &str) -> Column {
+    Column { name: name.into() }
+}
+
+/// A filter on a Druid expression, like `"added > deleted"`.
+pub fn expression(expression: &str) -> Filter {
+    Filter::expression(expression.into())
+}
+
+/// A value a column is compared with.
+#[derive(Debug, Clone)]
+pub enum FilterValue {
+    String(String),
+    Number(String),
+}
+
+impl FilterValue {
+    fn into_string(self) -> String {
+        match self {
+            Self::String(s) | Self::Number(s) => s,
+        }
+    }
+
+    fn is_number(&self) -> bool {
+        matches!(self, Self::Number(_))
+    }
+}
+
+impl From<&str> for FilterValue {
+    fn from(value: &str) -> Self {
+        Self::String(value.into())
+    }
+}
+
+impl From<String> for FilterValue {
+    fn from(value: String) -> Self {
+        Self::String(value)
+    }
+}
+
+macro_rules! number_value {
+    ($($t:ty),*) => {
+        $(
+            impl From<$t> for FilterValue {
+                fn from(value: $t) -> Self {
+                    Self::Number(value.to_string())
+                }
+            }
+        )*
+    };
+}
+
+number_value!(i32, i64, u32, u64, usize, f32, f64);
+
+impl Column {
+    fn dimension(&self) -> DimensionSpec {
+        DimensionSpec::default(self.name.clone(), self.name.clone(), None)
+    }
+
+    /// A `selector` filter.
+    pub fn eq<V: Into<FilterValue>>(&self, value: V) -> Filter {
+        Filter::selector(self.dimension(), value.into().into_string(), None)
+    }
+
+    /// A negated `selector` filter.
+    pub fn ne<V: Into<FilterValue>>(&self, value: V) -> Filter {
+        !self.eq(value)
+    }
+
+    /// An `in` filter.
+    pub fn is_in<I, V>(&self, values: I) -> Filter
+    where
+        I: IntoIterator<Item = V>,
+        V: Into<FilterValue>,
+    {
+        Filter::in_filter(
+            self.dimension(),
+            values
+                .into_iter()
+                .map(|value| value.into().into_string())
+                .collect(),
+        )
+    }
+
+    /// A `bound` filter for values greater than `value`.
+    pub fn gt<V: Into<FilterValue>>(&self, value: V) -> Filter {
+        self.bound(Some((value.into(), true)), None)
+    }
+
+    /// A `bound` filter for values greater than or equal to `value`.
+    pub fn ge<V: Into<FilterValue>>(&self, value: V) -> Filter {
+        self.bound(Some((value.into(), false)), None)
+    }
+
+    /// A `bound` filter for values less than `value`.
+    pub fn lt<V: Into<FilterValue>>(&self, value: V) -> Filter {
+        self.bound(None, Some((value.into(), true)))
+    }
+
+    /// A `bound` filter for values less than or equal to `value`.
+    pub fn le<V: Into<FilterValue>>(&self, value: V) -> Filter {
+        self.bound(None, Some((value.into(), false)))
+    }
+
+    /// A `bound` filter for values from `lower` to `upper`, both included.
+    pub fn between<L, U>(&self, lower: L, upper: U) -> Filter
+    where
+        L: Into<FilterValue>,
+        U: Into<FilterValue>,
+    {
+        self.bound(Some((lower.into(), false)), Some((upper.into(), false)))
+    }
+
+    /// The bounds are each a value and whether it is strict.
+    fn bound(
+        &self,
+        lower: Option<(FilterValue, bool)>,
+        upper: Option<(FilterValue, bool)>,
+    ) -> Filter {
+        let numeric = lower
+            .iter()
+            .chain(&upper)
+            .any(|(value, _)| value.is_number());
+        let (lower, lower_strict) = lower.unzip();
+        let (upper, upper_strict) = upper.unzip();
+        Filter::bound(
+            self.name.clone(),
+            lower.map(FilterValue::into_string),
+            upper.map(FilterValue::into_string),
+            lower_strict,
+            upper_strict,
+            numeric.then_some(Ordering::Numeric),
+            None,
+        )
+    }
+
+    /// A `like` filter with a SQL LIKE `pattern`.
+    pub fn like(&self, pattern: &str) -> Filter {
+        Filter::like(self.name.clone(), pattern.into(), None, None)
+    }
+
+    /// A `regex` filter.
+    pub fn regex(&self, pattern: &str) -> Filter {
+        Filter::regex(self.dimension(), pattern.into(), None)
+    }
+
+    /// An `interval` filter, usually on `__time`.
+    pub fn in_intervals(&self, intervals: &[Interval]) -> Filter {
+        Filter::interval(self.name.clone(), intervals.to_vec(), None)
+    }
+}
+
+/// An `and` of both filters, extending `self` if it is an `and` already.
+impl BitAnd for Filter {
+    type Output = Filter;
+
+    fn bitand(self, rhs: Self) -> Self::Output {
+        match self {
+            Filter::And { mut fields } => {
+                fields.push(rhs);
+                Filter::and(fields)
+            }
+            lhs => Filter::and(vec![lhs, rhs]),
+        }
+    }
+}
+
+/// An `or` of both filters, extending `self` if it is an `or` already.
+impl BitOr for Filter {
+    type Output = Filter;
+
+    fn bitor(self, rhs: Self) -> Self::Output {
+        match self {
+            Filter::Or { mut fields } => {
+                fields.push(rhs);
+                Filter::or(fields)
+            }
+            lhs => Filter::or(vec![lhs, rhs]),
+        }
+    }
+}
+
+impl Not for Filter {
+    type Output = Filter;
+
+    fn not(self) -> Self::Output {
+        Filter::not(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::{col, expression};
+
+    #[test]
+    fn builds_filters() {
+        let filter =
+            col("page").eq("Rust") & col("added").between(1, 10) | !col("user").like("bot%");
+        assert_eq!(
+            serde_json::to_value(&filter).unwrap(),
+            json!({
+                "type": "or",
+                "fields": [
+                    {
+                        "type": "and",
+                        "fields": [
+                            {
+                                "type": "selector",
+                                "dimension": "page",
+                                "value": "Rust",
+                                "extractionFn": null
+                            },
+                            {
+                                "type": "bound",
+                                "dimension": "added",
+                                "lower": "1",
+                                "upper": "10",
+                                "lowerStrict": false,
+                                "upperStrict": false,
+                                "ordering": "numeric",
+                                "extractionFn": null
+                            }
+                        ]
+                    },
+                    {
+                        "type": "not",
+                        "field": {
+                            "type": "like",
+                            "dimension": "user",
+                            "pattern": "bot%",
+                            "escape": null,
+                            "extractionFn": null
+                        }
+                    }
+                ]
+            })
+        );
+    }
+
+    #[test]
+    fn chains_operators() {
+        let filter = col("a").gt("m") & col("b").is_in([1, 2]) & expression("a > b");
+        let value = serde_json::to_value(&filter).unwrap();
+        assert_eq!(value["fields"].as_array().unwrap().len(), 3);
+        assert_eq!(value["fields"][0]["lowerStrict"], json!(true));
+        assert_eq!(value["fields"][0]["ordering"], json!(null));
+        assert_eq!(value["fields"][1]["dimension"], json!("b"));
+        assert_eq!(value["fields"][1]["values"], json!(["1", "2"]));
+        let regex = serde_json::to_value(col("page").regex("^R")).unwrap();
+        assert_eq!(regex["dimension"], json!("page"));
+    }
+}
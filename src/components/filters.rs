@@ -6,7 +6,7 @@ use super::{
 };
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(tag = "type", rename_all = "camelCase")]
+#[serde(tag = "type", rename_all = "camelCase", rename_all_fields = "camelCase")]
 pub enum Filter {
     Selector {
         dimension: DimensionSpec,
@@ -80,7 +80,7 @@ pub enum Filter {
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(tag = "type", rename_all = "camelCase")]
+#[serde(tag = "type", rename_all = "camelCase", rename_all_fields = "camelCase")]
 pub enum Bound {
     Rectangular {
         min_coords: Vec<f64>,
@@ -133,6 +133,7 @@ impl Filter {
         Self::Or { fields }
     }
 
+    #[allow(clippy::should_implement_trait)]
     pub fn not(field: Filter) -> Self {
         Self::Not {
             field: Box::new(field),
@@ -238,6 +239,263 @@ impl Filter {
     }
 }
 
+/// Simplification and normal forms of filter trees.
+///
+/// `Not(True)` is used as the always false filter, as Druid has no `false`
+/// filter.
+impl Filter {
+    /// Rewrite the filter into an equivalent, smaller one:
+    ///
+    /// - nested `And`s and `Or`s are flattened and duplicate fields removed;
+    /// - `True` fields are dropped from `And`s, and an `Or` with a `True`
+    ///   field becomes `True`, and the same for always false fields;
+    /// - `And`s and `Or`s with a single field are replaced by it;
+    /// - double negations are removed, and a negated `And` or `Or` whose
+    ///   fields are all negated is turned into an `Or` or `And` of the
+    ///   un-negated fields;
+    /// - `Selector`s and `In`s on the same dimension within an `Or` are
+    ///   merged into a single `In`.
+    pub fn simplify(self) -> Self {
+        match self {
+            Self::And { fields } => Self::junction(true, fields),
+            Self::Or { fields } => Self::junction(false, fields),
+            Self::Not { field } => match field.simplify() {
+                Self::Not { field } => *field,
+                Self::And { fields } if fields.iter().all(Self::is_not) => {
+                    Self::junction(false, fields.into_iter().map(Self::negate).collect())
+                }
+                Self::Or { fields } if fields.iter().all(Self::is_not) => {
+                    Self::junction(true, fields.into_iter().map(Self::negate).collect())
+                }
+                field => Self::not(field),
+            },
+            other => other,
+        }
+    }
+
+    /// Rewrite the filter as an `And` of `Or`s of leaf filters, with
+    /// negations only directly around leaves.
+    ///
+    /// The result can be exponentially larger than the filter.
+    pub fn to_cnf(self) -> Self {
+        self.normal_form(true)
+    }
+
+    /// Rewrite the filter as an `Or` of `And`s of leaf filters, with
+    /// negations only directly around leaves.
+    ///
+    /// The result can be exponentially larger than the filter.
+    pub fn to_dnf(self) -> Self {
+        self.normal_form(false)
+    }
+
+    fn false_filter() -> Self {
+        Self::not(Self::True)
+    }
+
+    fn is_true(&self) -> bool {
+        matches!(self, Self::True)
+    }
+
+    fn is_false(&self) -> bool {
+        matches!(self, Self::Not { field } if field.is_true())
+    }
+
+    fn is_not(&self) -> bool {
+        matches!(self, Self::Not { .. })
+    }
+
+    /// Remove or add a negation.
+    fn negate(self) -> Self {
+        match self {
+            Self::Not { field } => *field,
+            other => Self::not(other),
+        }
+    }
+
+    /// Take the fields of an `And` when `and` is true or of an `Or`
+    /// otherwise.
+    fn take_fields(&mut self, and: bool) -> Option<Vec<Self>> {
+        match self {
+            Self::And { fields } if and => Some(std::mem::take(fields)),
+            Self::Or { fields } if !and => Some(std::mem::take(fields)),
+            _ => None,
+        }
+    }
+
+    /// Simplify an `And` when `and` is true or an `Or` otherwise.
+    fn junction(and: bool, fields: Vec<Self>) -> Self {
+        // The field that decides the whole junction: false for an `And` and
+        // true for an `Or`.
+        let absorbing = |filter: &Self| {
+            if and {
+                filter.is_false()
+            } else {
+                filter.is_true()
+            }
+        };
+        let mut flat: Vec<Self> = Vec::new();
+        let mut seen = Vec::new();
+        let mut pending: Vec<Self> = fields.into_iter().rev().collect();
+        while let Some(field) = pending.pop() {
+            let mut field = field.simplify();
+            match field.take_fields(and) {
+                Some(nested) => pending.extend(nested.into_iter().rev()),
+                None if absorbing(&field) => return field,
+                // The identity of the junction: true for an `And` and false
+                // for an `Or`.
+                None if field.is_true() || field.is_false() => {}
+                None => {
+                    let key = serde_json::to_value(&field).ok();
+                    if key.is_none() || !seen.contains(&key) {
+                        seen.push(key);
+                        flat.push(field);
+                    }
+                }
+            }
+        }
+        if !and {
+            flat = Self::merge_selectors(flat);
+        }
+        match flat.len() {
+            0 if and => Self::True,
+            0 => Self::false_filter(),
+            1 => flat.pop().unwrap(),
+            _ if and => Self::and(flat),
+            _ => Self::or(flat),
+        }
+    }
+
+    /// Merge the `Selector`s without extraction functions and `In`s of an
+    /// `Or` on the same dimension into one `In` in place of the first of them.
+    fn merge_selectors(fields: Vec<Self>) -> Vec<Self> {
+        let mut merged: Vec<Self> = Vec::with_capacity(fields.len());
+        let mut dimensions: Vec<(serde_json::Value, usize)> = Vec::new();
+        for field in fields {
+            let (dimension, values) = match field {
+                Self::Selector {
+                    dimension,
+                    value,
+                    extraction_fn: None,
+                } => (dimension, vec![value]),
+                Self::In { dimension, values } => (dimension, values),
+                other => {
+                    merged.push(other);
+                    continue;
+                }
+            };
+            let key = serde_json::to_value(&dimension).unwrap_or_default();
+            match dimensions.iter().find(|(k, _)| *k == key) {
+                Some(&(_, index)) => {
+                    let existing = std::mem::replace(&mut merged[index], Self::True);
+                    let (dimension, mut all) = match existing {
+                        Self::Selector {
+                            dimension, value, ..
+                        } => (dimension, vec![value]),
+                        Self::In { dimension, values } => (dimension, values),
+                        _ => unreachable!("only selectors and ins are merged"),
+                    };
+                    for value in values {
+                        if !all.contains(&value) {
+                            all.push(value);
+                        }
+                    }
+                    merged[index] = Self::in_filter(dimension, all);
+                }
+                None => {
+                    dimensions.push((key, merged.len()));
+                    merged.push(match values.len() {
+                        1 => Self::selector(dimension, values.into_iter().next().unwrap(), None),
+                        _ => Self::in_filter(dimension, values),
+                    });
+                }
+            }
+        }
+        merged
+    }
+
+    /// Push negations down to the leaves with De Morgan's laws.
+    fn negation_normal_form(self, negated: bool) -> Self {
+        match self {
+            Self::Not { field } => field.negation_normal_form(!negated),
+            Self::And { fields } => {
+                let fields = fields
+                    .into_iter()
+                    .map(|field| field.negation_normal_form(negated))
+                    .collect();
+                if negated {
+                    Self::or(fields)
+                } else {
+                    Self::and(fields)
+                }
+            }
+            Self::Or { fields } => {
+                let fields = fields
+                    .into_iter()
+                    .map(|field| field.negation_normal_form(negated))
+                    .collect();
+                if negated {
+                    Self::and(fields)
+                } else {
+                    Self::or(fields)
+                }
+            }
+            leaf if negated => Self::not(leaf),
+            leaf => leaf,
+        }
+    }
+
+    /// An `And` of `Or`s when `and` is true or an `Or` of `And`s otherwise.
+    fn normal_form(self, and: bool) -> Self {
+        let groups = self.negation_normal_form(false).simplify().groups(and);
+        let groups = groups
+            .into_iter()
+            .map(|group| {
+                if and {
+                    Self::or(group)
+                } else {
+                    Self::and(group)
+                }
+            })
+            .collect();
+        let outer = if and {
+            Self::and(groups)
+        } else {
+            Self::or(groups)
+        };
+        outer.simplify()
+    }
+
+    /// The groups of leaves of the normal form with the outer junction `And`
+    /// when `and` is true and `Or` otherwise.
+    fn groups(mut self, and: bool) -> Vec<Vec<Self>> {
+        if let Some(fields) = self.take_fields(and) {
+            return fields
+                .into_iter()
+                .flat_map(|field| field.groups(and))
+                .collect();
+        }
+        match self.take_fields(!and) {
+            Some(fields) => {
+                let mut product = vec![Vec::new()];
+                for field in fields {
+                    let groups = field.groups(and);
+                    product = product
+                        .iter()
+                        .flat_map(|left| {
+                            groups.iter().map(move |right| {
+                                left.iter().chain(right).cloned().collect::<Vec<_>>()
+                            })
+                        })
+                        .collect();
+                }
+                product
+            }
+            None => vec![vec![self]],
+        }
+    }
+}
+
 impl Bound {
     pub fn rectangular(min_coords: Vec<f64>, max_coords: Vec<f64>) -> Self {
         Self::Rectangular {
@@ -254,3 +512,82 @@ impl Bound {
         Self::Polygon { abscissa, ordinate }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::Filter;
+    use crate::components::dimension_specs::DimensionSpec;
+
+    fn dimension(name: &str) -> DimensionSpec {
+        DimensionSpec::default(name.into(), name.into(), None)
+    }
+
+    fn selector(name: &str, value: &str) -> Filter {
+        Filter::selector(dimension(name), value.into(), None)
+    }
+
+    fn json(filter: &Filter) -> serde_json::Value {
+        serde_json::to_value(filter).unwrap()
+    }
+
+    #[test]
+    fn simplifies_filters() {
+        let filter = Filter::and(vec![
+            Filter::True,
+            Filter::and(vec![Filter::or(vec![
+                selector("page", "Rust"),
+                Filter::or(vec![selector("page", "Go"), selector("user", "bob")]),
+                Filter::in_filter(dimension("page"), vec!["Go".into(), "C".into()]),
+            ])]),
+            Filter::not(Filter::not(selector("user", "bob"))),
+        ]);
+        let expected = Filter::and(vec![
+            Filter::or(vec![
+                Filter::in_filter(
+                    dimension("page"),
+                    vec!["Rust".into(), "Go".into(), "C".into()],
+                ),
+                selector("user", "bob"),
+            ]),
+            selector("user", "bob"),
+        ]);
+        assert_eq!(json(&filter.simplify()), json(&expected));
+
+        let filter = Filter::not(Filter::and(vec![
+            Filter::not(selector("page", "Rust")),
+            Filter::not(selector("page", "Go")),
+        ]));
+        let expected = Filter::in_filter(dimension("page"), vec!["Rust".into(), "Go".into()]);
+        assert_eq!(json(&filter.simplify()), json(&expected));
+
+        let filter = Filter::or(vec![selector("page", "Rust"), Filter::True]);
+        assert!(matches!(filter.simplify(), Filter::True));
+        let filter = Filter::and(vec![selector("page", "Rust"), Filter::not(Filter::True)]);
+        assert_eq!(json(&filter.simplify()), json(&Filter::not(Filter::True)));
+    }
+
+    #[test]
+    fn converts_to_normal_forms() {
+        let (a, b, c) = (selector("a", "1"), selector("b", "1"), selector("c", "1"));
+        // a | (b & !c)
+        let filter = Filter::or(vec![
+            a.clone(),
+            Filter::and(vec![b.clone(), Filter::not(c.clone())]),
+        ]);
+        let cnf = Filter::and(vec![
+            Filter::or(vec![a.clone(), b.clone()]),
+            Filter::or(vec![a.clone(), Filter::not(c.clone())]),
+        ]);
+        assert_eq!(json(&filter.clone().to_cnf()), json(&cnf));
+        assert_eq!(json(&filter.clone().to_dnf()), json(&filter));
+
+        // !(a | b) & c
+        let filter = Filter::and(vec![
+            Filter::not(Filter::or(vec![a.clone(), b.clone()])),
+            c.clone(),
+        ]);
+        let dnf = Filter::and(vec![Filter::not(a), Filter::not(b), c]);
+        assert_eq!(json(&filter.clone().to_dnf()), json(&dnf));
+        assert_eq!(json(&filter.to_cnf()), json(&dnf));
+    }
+}
@@ -54,82 +54,52 @@ impl Serialize for Granularity {
     }
 }
 
+// Besides the plain string form, Druid writes simple granularities as
+// `{"type": "all"}`, and may send `null` for the optional fields.
 impl<'de> Deserialize<'de> for Granularity {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
         D: Deserializer<'de>,
     {
-        let s: &str = Deserialize::deserialize(deserializer)?;
-        let val: Value = serde_json::to_value(s).map_err(de::Error::custom)?;
+        let val = Value::deserialize(deserializer)?;
         match val {
             Value::String(s) => Ok(Self::Simple(s)),
-            Value::Object(map) => match map.get("type") {
-                None => Err(de::Error::custom(r#"expected field "type" not found"#)),
-                Some(val) => match val {
-                    Value::String(s) if s == "duration" => {
-                        let duration = map.get("duration").ok_or_else(|| {
-                            de::Error::custom(r#"expected field "duration" not found"#)
-                        })?;
-                        let origin = if let Some(val) = map.get("origin") {
-                            Some(
-                                val.as_str()
-                                    .ok_or_else(|| {
-                                        de::Error::custom(r#"field "origin" is not a string"#)
-                                    })?
-                                    .to_string(),
-                            )
-                        } else {
-                            None
-                        };
+            Value::Object(map) => {
+                let optional_str = |field: &str| match map.get(field) {
+                    None | Some(Value::Null) => Ok(None),
+                    Some(Value::String(s)) => Ok(Some(s.clone())),
+                    Some(_) => Err(de::Error::custom(format!(
+                        r#"field "{field}" is not a string"#
+                    ))),
+                };
+                match map.get("type").and_then(Value::as_str) {
+                    None => Err(de::Error::custom(r#"expected field "type" not found"#)),
+                    Some("duration") => {
+                        let duration = map
+                            .get("duration")
+                            .ok_or_else(|| {
+                                de::Error::custom(r#"expected field "duration" not found"#)
+                            })?
+                            .as_u64()
+                            .ok_or_else(|| de::Error::custom(r#"field "duration" is not u64"#))?;
                         Ok(Granularity::Duration {
-                            duration: duration.as_u64().ok_or_else(|| {
-                                de::Error::custom(r#"field "duration" is not u64"#)
-                            })?,
-                            origin,
+                            duration,
+                            origin: optional_str("origin")?,
                         })
                     }
-                    Value::String(s) if s == "period" => {
-                        let period = map.get("period").ok_or_else(|| {
-                            de::Error::custom(r#"expected field "period not found"#)
+                    Some("period") => {
+                        let period = optional_str("period")?.ok_or_else(|| {
+                            de::Error::custom(r#"expected field "period" not found"#)
                         })?;
-                        let origin = if let Some(val) = map.get("origin") {
-                            Some(
-                                val.as_str()
-                                    .ok_or_else(|| {
-                                        de::Error::custom(r#"field "origin" is not a string"#)
-                                    })?
-                                    .to_string(),
-                            )
-                        } else {
-                            None
-                        };
-                        let time_zone = if let Some(val) = map.get("timeZone") {
-                            Some(
-                                val.as_str()
-                                    .ok_or_else(|| {
-                                        de::Error::custom(r#"field "timeZone" is not a string"#)
-                                    })?
-                                    .to_string(),
-                            )
-                        } else {
-                            None
-                        };
                         Ok(Granularity::Period {
-                            period: period
-                                .as_str()
-                                .ok_or_else(|| {
-                                    de::Error::custom(r#"field "period" is not a string"#)
-                                })?
-                                .to_string(),
-                            origin,
-                            time_zone,
+                            period,
+                            origin: optional_str("origin")?,
+                            time_zone: optional_str("timeZone")?,
                         })
                     }
-                    _ => Err(de::Error::custom(
-                        r#"field "type" does not have a recognized value"#,
-                    )),
-                },
-            },
+                    Some(simple) => simple.parse().map_err(de::Error::custom),
+                }
+            }
             _ => Err(de::Error::custom(
                 r#"value does not have a recognized form"#,
             )),
@@ -2,8 +2,9 @@ use serde::{Serialize, Deserialize};
 
 use super::{druid_types::DruidNativeType, filters::Filter};
 
+#[allow(clippy::large_enum_variant)]
 #[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(tag = "type", rename_all = "camelCase")]
+#[serde(tag = "type", rename_all = "camelCase", rename_all_fields = "camelCase")]
 pub enum HavingSpec {
     Filter {
         filter: Filter,
@@ -64,6 +65,7 @@ impl HavingSpec {
         Self::Or { having_specs }
     }
 
+    #[allow(clippy::should_implement_trait)]
     pub fn not(having_spec: HavingSpec) -> Self {
         Self::Not {
             having_spec: Box::new(having_spec),
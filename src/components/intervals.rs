@@ -1,4 +1,4 @@
-use std::str::FromStr;
+use std::{fmt, str::FromStr};
 
 use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
 
@@ -28,12 +28,9 @@ impl Interval {
     }
 }
 
-impl ToString for Interval {
-    fn to_string(&self) -> String {
-        let mut s = self.start.clone();
-        s.push('/');
-        s.push_str(&self.end);
-        s
+impl fmt::Display for Interval {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.start, self.end)
     }
 }
 
@@ -67,7 +64,7 @@ impl<'de> Deserialize<'de> for Interval {
     where
         D: Deserializer<'de>,
     {
-        let s: &str = Deserialize::deserialize(deserializer)?;
+        let s: String = Deserialize::deserialize(deserializer)?;
         let mut parts = s.split('/');
         let start = if let Some(start) = parts.next() {
             start.to_string()
@@ -83,13 +80,77 @@ impl<'de> Deserialize<'de> for Interval {
     }
 }
 
+/// Deserialize a list of intervals either from a plain array or from the
+/// `{"type": "intervals", "intervals": [...]}` segment spec Druid writes.
+pub(crate) fn deserialize_intervals<'de, D>(deserializer: D) -> Result<Vec<Interval>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Repr {
+        List(Vec<Interval>),
+        Spec { intervals: Vec<Interval> },
+    }
+    match Repr::deserialize(deserializer)? {
+        Repr::List(intervals) | Repr::Spec { intervals } => Ok(intervals),
+    }
+}
+
+pub(crate) fn deserialize_optional_intervals<'de, D>(
+    deserializer: D,
+) -> Result<Option<Vec<Interval>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    struct Wrapper(#[serde(deserialize_with = "deserialize_intervals")] Vec<Interval>);
+    Ok(Option::<Wrapper>::deserialize(deserializer)?.map(|w| w.0))
+}
+
 #[derive(Debug, Clone, thiserror::Error)]
 #[error("invalid ISO 8601 interval {0}")]
 pub struct InvalidISO8601(String);
 
+#[derive(Debug, Clone, thiserror::Error)]
+#[error("invalid ISO 8601 period {0}")]
+pub struct InvalidPeriod(String);
+
+/// Check that `period` is an ISO 8601 duration like `P1D` or `PT6H30M`.
+pub(crate) fn validate_period(period: &str) -> Result<(), InvalidPeriod> {
+    let invalid = || InvalidPeriod(period.to_string());
+    let rest = period.strip_prefix('P').ok_or_else(invalid)?;
+    let (date, time) = match rest.split_once('T') {
+        Some((date, time)) if !time.is_empty() => (date, Some(time)),
+        Some(_) => return Err(invalid()),
+        None => (rest, None),
+    };
+    let valid = |part: &str, units: &str, fraction: bool| {
+        let mut units = units.chars();
+        let mut number = String::new();
+        for c in part.chars() {
+            if c.is_ascii_digit() || (fraction && c == '.' && !number.is_empty()) {
+                number.push(c);
+            } else if number.is_empty() || number.ends_with('.') || !units.any(|u| u == c) {
+                return false;
+            } else {
+                number.clear();
+            }
+        }
+        number.is_empty()
+    };
+    if (date.is_empty() && time.is_none())
+        || !valid(date, "YMWD", false)
+        || time.is_some_and(|time| !valid(time, "HMS", true))
+    {
+        return Err(invalid());
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
-    use super::{Interval, InvalidISO8601};
+    use super::{validate_period, Interval, InvalidISO8601};
 
     #[test]
     fn new_both_period_fails() -> Result<(), String> {
@@ -112,4 +173,16 @@ mod tests {
             "2021-01-01/2021-01-02T22:00:03".to_string()
         );
     }
+
+    #[test]
+    fn validates_periods() {
+        for period in ["P1D", "PT6H", "P1Y2M3DT4H5M6.5S", "P2W"] {
+            assert!(validate_period(period).is_ok(), "{period}");
+        }
+        for period in [
+            "", "P", "PT", "1D", "P1H", "PT1D", "PD", "P1D2", "PT1.S", "P1DM",
+        ] {
+            assert!(validate_period(period).is_err(), "{period}");
+        }
+    }
 }
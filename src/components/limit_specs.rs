@@ -1,10 +1,12 @@
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
 use super::ordering::Ordering;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(tag = "type", rename_all = "camelCase")]
+#[serde(tag = "type", rename_all = "camelCase", rename_all_fields = "camelCase", remote = "Self")]
 pub enum LimitSpec {
+    #[serde(rename = "NoopLimitSpec")]
+    Noop,
     Default {
         limit: Option<usize>,
         offset: Option<usize>,
@@ -17,7 +19,54 @@ pub enum LimitSpec {
     },
 }
 
+impl Serialize for LimitSpec {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        LimitSpec::serialize(self, serializer)
+    }
+}
+
+// Druid writes the order by columns of the default limit spec without a
+// `type`.
+impl<'de> Deserialize<'de> for LimitSpec {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Repr {
+            #[serde(with = "LimitSpec")]
+            Spec(LimitSpec),
+            #[serde(rename_all = "camelCase")]
+            Column {
+                dimension: String,
+                direction: Option<String>,
+                dimension_order: Option<Ordering>,
+            },
+        }
+        match Repr::deserialize(deserializer)? {
+            Repr::Spec(spec) => Ok(spec),
+            Repr::Column {
+                dimension,
+                direction,
+                dimension_order,
+            } => Ok(LimitSpec::OrderByColumnSpec {
+                dimension,
+                direction: direction.unwrap_or_else(|| "ascending".into()),
+                dimension_order,
+            }),
+        }
+    }
+}
+
 impl LimitSpec {
+    pub fn noop() -> Self {
+        Self::Noop {}
+    }
+
     pub fn default(
         limit: Option<usize>,
         offset: Option<usize>,
@@ -5,7 +5,7 @@ use serde::{Serialize, Deserialize};
 use super::druid_types::DruidNativeType;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(tag = "type", rename_all = "camelCase")]
+#[serde(tag = "type", rename_all = "camelCase", rename_all_fields = "camelCase")]
 pub enum Lookup {
     Map {
         map: HashMap<String, DruidNativeType>,
@@ -4,6 +4,7 @@ pub mod data_sources;
 pub mod dimension_specs;
 pub mod druid_types;
 pub mod extraction_functions;
+pub mod filter_dsl;
 pub mod filters;
 pub mod granularities;
 pub mod having_specs;
@@ -1,7 +1,7 @@
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(rename_all = "lowercase")]
+#[serde(rename_all = "lowercase", remote = "Self")]
 pub enum Ordering {
     Alphanumeric,
     Lexicographic,
@@ -9,3 +9,34 @@ pub enum Ordering {
     Strlen,
     Version,
 }
+
+impl Serialize for Ordering {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        Ordering::serialize(self, serializer)
+    }
+}
+
+// Druid itself writes orderings in the `{"type": "numeric"}` form.
+impl<'de> Deserialize<'de> for Ordering {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Repr {
+            #[serde(with = "Ordering")]
+            Name(Ordering),
+            Typed {
+                #[serde(rename = "type", with = "Ordering")]
+                ordering: Ordering,
+            },
+        }
+        match Repr::deserialize(deserializer)? {
+            Repr::Name(ordering) | Repr::Typed { ordering } => Ok(ordering),
+        }
+    }
+}
@@ -3,7 +3,7 @@ use serde::{Deserialize, Serialize};
 use super::{druid_types::DruidNativeType, ordering::Ordering};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(tag = "type", rename_all = "camelCase")]
+#[serde(tag = "type", rename_all = "camelCase", rename_all_fields = "camelCase")]
 pub enum PostAggregator {
     Arithmetic {
         name: String,
@@ -48,6 +48,12 @@ pub enum PostAggregator {
         name: String,
         field_name: String,
     },
+    Expression {
+        name: String,
+        expression: String,
+        ordering: Option<String>,
+        output_type: Option<String>,
+    },
 }
 
 impl PostAggregator {
@@ -112,6 +118,37 @@ impl PostAggregator {
     pub fn hyper_unique_cardinality(name: String, field_name: String) -> Self {
         Self::HyperUniqueCardinality { name, field_name }
     }
+
+    pub fn expression(
+        name: String,
+        expression: String,
+        ordering: Option<String>,
+        output_type: Option<String>,
+    ) -> Self {
+        Self::Expression {
+            name,
+            expression,
+            ordering,
+            output_type,
+        }
+    }
+
+    /// The output name of the post-aggregator.
+    pub fn name(&self) -> &str {
+        match self {
+            Self::Arithmetic { name, .. }
+            | Self::FieldAccess { name, .. }
+            | Self::FinalizingFieldAccess { name, .. }
+            | Self::Constant { name, .. }
+            | Self::LongGreatest { name, .. }
+            | Self::DoubleGreatest { name, .. }
+            | Self::LongLeast { name, .. }
+            | Self::DoubleLeast { name, .. }
+            | Self::Javascript { name, .. }
+            | Self::HyperUniqueCardinality { name, .. }
+            | Self::Expression { name, .. } => name,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy, thiserror::Error)]
@@ -3,7 +3,7 @@ use serde::{Deserialize, Serialize};
 use super::druid_types::DruidNativeType;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(tag = "type", rename_all = "camelCase")]
+#[serde(tag = "type", rename_all = "camelCase", rename_all_fields = "camelCase")]
 pub enum SearchQuerySpec {
     InsensitiveContains {
         value: DruidNativeType, // must be String
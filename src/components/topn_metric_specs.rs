@@ -3,7 +3,7 @@ use serde::{Deserialize, Serialize};
 use super::ordering::Ordering;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(tag = "type", rename_all = "camelCase")]
+#[serde(tag = "type", rename_all = "camelCase", rename_all_fields = "camelCase")]
 pub enum TopNMetricSpec {
     Numeric {
         metric: String,
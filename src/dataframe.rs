@@ -0,0 +1,214 @@
+//! Conversion of query results into Polars [`DataFrame`]s, behind the
+//! `polars` feature.
+//!
+//! Column dtypes are inferred from the values. Multi-value dimensions become
+//! lists of strings, and the result timestamp becomes a `timestamp` datetime
+//! column, as does `__time`. Datetimes are in UTC without a time zone set.
+//! TopN results are flattened to one row per ranked value, with the bucket
+//! timestamp repeated.
+
+use std::collections::HashMap;
+
+use polars::prelude::{
+    DataFrame, DataType, IntoColumn, IntoSeries, ListChunked, NamedFrom, PolarsError, PolarsResult,
+    Series, TimeUnit,
+};
+
+use crate::{
+    columnar::{
+        as_bool, as_f64, as_i64, as_millis, as_strings, to_string, ColumnKind, ToColumns, TIMESTAMP,
+    },
+    queries::{
+        response::{GroupByResult, ScanResult, SqlResult, TimeseriesResult, TopNResult},
+        sql_result::{ColumnSchema, SqlResultSet},
+    },
+};
+
+/// Conversion of a set of results into a single [`DataFrame`].
+pub trait ToDataFrame {
+    fn to_dataframe(&self) -> PolarsResult<DataFrame>;
+}
+
+macro_rules! impl_to_dataframe {
+    ($($result:ty),*) => {
+        $(
+            impl ToDataFrame for [$result] {
+                fn to_dataframe(&self) -> PolarsResult<DataFrame> {
+                    dataframe(self, &HashMap::new())
+                }
+            }
+        )*
+    };
+}
+
+impl_to_dataframe!(
+    ScanResult,
+    GroupByResult,
+    TimeseriesResult,
+    TopNResult,
+    SqlResult
+);
+
+/// Columns keep their order, and take their dtypes from the SQL or native
+/// types in the schema where known.
+impl ToDataFrame for SqlResultSet {
+    fn to_dataframe(&self) -> PolarsResult<DataFrame> {
+        let kinds = self
+            .columns
+            .iter()
+            .filter_map(|column| Some((column.name.as_str(), column_kind(column)?)))
+            .collect();
+        dataframe(self.to_sql_results().as_slice(), &kinds)
+    }
+}
+
+/// The kind of a column of a SQL result, from its SQL type or else its
+/// native type.
+fn column_kind(column: &ColumnSchema) -> Option<ColumnKind> {
+    let kind = |druid_type: &str| match druid_type {
+        "LONG" | "BIGINT" | "INTEGER" | "SMALLINT" | "TINYINT" => Some(ColumnKind::Int64),
+        "FLOAT" | "DOUBLE" | "REAL" | "DECIMAL" => Some(ColumnKind::Float64),
+        "STRING" | "VARCHAR" | "CHAR" => Some(ColumnKind::Utf8),
+        "BOOLEAN" => Some(ColumnKind::Boolean),
+        "TIMESTAMP" | "DATE" => Some(ColumnKind::Timestamp),
+        "ARRAY<STRING>" | "VARCHAR ARRAY" => Some(ColumnKind::StringList),
+        _ => None,
+    };
+    column
+        .sql_type
+        .as_deref()
+        .and_then(kind)
+        .or_else(|| column.druid_type.as_deref().and_then(kind))
+}
+
+fn datetime(name: &str, millis: Vec<Option<i64>>) -> PolarsResult<Series> {
+    Series::new(name.into(), millis).cast(&DataType::Datetime(TimeUnit::Milliseconds, None))
+}
+
+fn dataframe<R: ToColumns + ?Sized>(
+    results: &R,
+    kinds: &HashMap<&str, ColumnKind>,
+) -> PolarsResult<DataFrame> {
+    let columns = results
+        .to_columns()
+        .map_err(|e| PolarsError::ComputeError(e.into()))?;
+    let (timestamps, columns) = columns.finish();
+    let mut series = Vec::new();
+    if let Some(timestamps) = timestamps {
+        series.push(datetime(TIMESTAMP, timestamps)?);
+    }
+    for column in columns {
+        let values = column.values.iter();
+        let kind = kinds
+            .get(column.name)
+            .copied()
+            .unwrap_or_else(|| column.kind());
+        let s = match kind {
+            ColumnKind::Int64 => Series::new(
+                column.name.into(),
+                values.map(|v| v.and_then(as_i64)).collect::<Vec<_>>(),
+            ),
+            ColumnKind::Float64 => Series::new(
+                column.name.into(),
+                values.map(|v| v.and_then(as_f64)).collect::<Vec<_>>(),
+            ),
+            ColumnKind::Utf8 => Series::new(
+                column.name.into(),
+                values.map(|v| v.map(to_string)).collect::<Vec<_>>(),
+            ),
+            ColumnKind::Boolean => Series::new(
+                column.name.into(),
+                values.map(|v| v.and_then(as_bool)).collect::<Vec<_>>(),
+            ),
+            ColumnKind::Timestamp => {
+                datetime(column.name, values.map(|v| v.and_then(as_millis)).collect())?
+            }
+            ColumnKind::StringList => {
+                let mut list: ListChunked = values
+                    .map(|v| {
+                        v.and_then(as_strings)
+                            .map(|items| Series::new("".into(), items))
+                    })
+                    .collect();
+                list.rename(column.name.into());
+                list.into_series()
+            }
+        };
+        series.push(s);
+    }
+    DataFrame::new(series.into_iter().map(IntoColumn::into_column).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use polars::prelude::{DataType, TimeUnit};
+
+    use super::ToDataFrame;
+    use crate::queries::{
+        response::{ScanResult, TopNResult},
+        sql::Sql,
+        sql_result::SqlResultSet,
+    };
+
+    #[test]
+    fn flattens_topn() {
+        let results: Vec<TopNResult> = serde_json::from_str(
+            r#"[{"timestamp": "2015-09-12T00:00:00.000Z", "result": [
+                    {"page": "Rust", "edits": 3}, {"page": "Go", "edits": 2}]},
+                {"timestamp": "2015-09-13T00:00:00.000Z", "result": [
+                    {"page": "Zig", "edits": 1.5}]}]"#,
+        )
+        .unwrap();
+        let df = results.to_dataframe().unwrap();
+        assert_eq!(df.shape(), (3, 3));
+        assert_eq!(
+            df.column("timestamp").unwrap().dtype(),
+            &DataType::Datetime(TimeUnit::Milliseconds, None)
+        );
+        assert_eq!(df.column("edits").unwrap().dtype(), &DataType::Float64);
+        assert_eq!(df.column("page").unwrap().dtype(), &DataType::String);
+    }
+
+    #[test]
+    fn converts_multi_value_dimensions() {
+        let results: Vec<ScanResult> = serde_json::from_str(
+            r#"[{"segmentId": "s", "columns": ["__time", "tags"], "events": [
+                {"__time": 1442016000000, "tags": ["a", "b"]}, {"__time": 1442016001000, "tags": "c"}]}]"#,
+        )
+        .unwrap();
+        let df = results.to_dataframe().unwrap();
+        assert_eq!(
+            df.column("tags").unwrap().dtype(),
+            &DataType::List(Box::new(DataType::String))
+        );
+        assert!(matches!(
+            df.column("__time").unwrap().dtype(),
+            DataType::Datetime(TimeUnit::Milliseconds, _)
+        ));
+    }
+
+    #[test]
+    fn converts_sql_result_sets_with_their_types() {
+        let query = Sql::new("SELECT page, __time, added, robot FROM wikipedia")
+            .header(true)
+            .types_header(true)
+            .sql_types_header(true);
+        let body = r#"[
+            {"page": {"type": "STRING", "sqlType": "VARCHAR"},
+             "__time": {"type": "LONG", "sqlType": "TIMESTAMP"},
+             "added": {"type": "LONG", "sqlType": "BIGINT"},
+             "robot": {"type": "LONG", "sqlType": "BOOLEAN"}},
+            {"page": "Rust", "__time": "2015-09-12T00:46:58.000Z", "added": null, "robot": true}]"#;
+        let df = SqlResultSet::parse(body, &query)
+            .unwrap()
+            .to_dataframe()
+            .unwrap();
+        assert_eq!(df.get_column_names(), ["page", "__time", "added", "robot"]);
+        assert_eq!(df.column("added").unwrap().dtype(), &DataType::Int64);
+        assert_eq!(df.column("robot").unwrap().dtype(), &DataType::Boolean);
+        assert!(matches!(
+            df.column("__time").unwrap().dtype(),
+            DataType::Datetime(TimeUnit::Milliseconds, _)
+        ));
+    }
+}
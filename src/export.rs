@@ -0,0 +1,678 @@
+//! Writing query results as CSV, newline-delimited JSON or, with the
+//! `parquet` feature, Parquet.
+//!
+//! Rows are written to the writer one at a time. Columns keep the order Druid
+//! gives them in: the column list of scan results, which follows
+//! `Scan::columns`, the header row of SQL results or the columns of a
+//! [`SqlResultSet`]. Other results have their columns sorted by name, unless
+//! an order is given with [`Exporter::columns`]. Results with a timestamp per
+//! row, like groupBy and topN results, get a leading `timestamp` column.
+//!
+//! ```
+//! use query_druid::{export::Exporter, queries::response::GroupByResult};
+//!
+//! let results: Vec<GroupByResult> = serde_json::from_str(
+//!     r#"[{"version": "v1", "timestamp": "2015-09-12T00:00:00.000Z", "event": {"page": "Rust", "edits": 3}}]"#,
+//! )
+//! .unwrap();
+//! let mut csv = Vec::new();
+//! Exporter::new(results.as_slice()).csv(&mut csv).unwrap();
+//! assert_eq!(
+//!     String::from_utf8(csv).unwrap(),
+//!     "timestamp,edits,page\n2015-09-12T00:00:00.000Z,3,Rust\n"
+//! );
+//! ```
+
+use std::{
+    borrow::Cow,
+    collections::{BTreeSet, HashMap},
+    io::Write,
+};
+
+use chrono::{DateTime, SecondsFormat, Utc};
+
+use crate::{
+    components::druid_types::DruidNativeType,
+    queries::{
+        response::{GroupByResult, ScanResult, SqlResult, TimeseriesResult, TopNResult},
+        sql_result::{ColumnSchema, SqlResultSet, SqlValue},
+    },
+};
+
+const TIMESTAMP: &str = "timestamp";
+
+#[derive(Debug, thiserror::Error)]
+pub enum ExportError {
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+    #[error("{0}")]
+    Unsupported(&'static str),
+    #[cfg(feature = "parquet")]
+    #[error(transparent)]
+    Arrow(#[from] arrow::error::ArrowError),
+    #[cfg(feature = "parquet")]
+    #[error(transparent)]
+    Parquet(#[from] parquet::errors::ParquetError),
+}
+
+/// Writes a set of results in one of the export formats.
+pub struct Exporter<'r, R: ?Sized> {
+    results: &'r R,
+    columns: Option<Vec<String>>,
+}
+
+impl<'r, R: Exportable + ?Sized> Exporter<'r, R> {
+    pub fn new(results: &'r R) -> Self {
+        Self {
+            results,
+            columns: None,
+        }
+    }
+
+    /// Write only these columns, in this order. The `timestamp` column is
+    /// still written first when the results have one.
+    pub fn columns(mut self, columns: &[String]) -> Self {
+        self.columns = Some(columns.to_vec());
+        self
+    }
+
+    /// Write a header line and one line per row. Missing values are left
+    /// empty and multi-value dimensions are written as JSON arrays.
+    ///
+    /// SQL results in the CSV format are copied as they are.
+    pub fn csv<W: Write>(&self, mut writer: W) -> Result<(), ExportError> {
+        if let Some(lines) = self.results.csv_lines() {
+            if self.columns.is_some() {
+                return Err(ExportError::Unsupported(
+                    "CSV results can't be limited to columns",
+                ));
+            }
+            for line in lines {
+                writeln!(writer, "{line}")?;
+            }
+            return writer.flush().map_err(Into::into);
+        }
+        let columns = self.header();
+        let timestamps = self.results.has_timestamps();
+        let mut fields: Vec<&str> = Vec::new();
+        if timestamps {
+            fields.push(TIMESTAMP);
+        }
+        fields.extend(columns.iter().copied());
+        write_csv_line(&mut writer, fields.iter().map(|field| field.to_string()))?;
+        let mut positions = Positions::default();
+        for row in self.results.rows() {
+            let timestamp =
+                timestamps.then(|| row.timestamp.map(format_timestamp).unwrap_or_default());
+            let positions = positions.of(&row.values);
+            let values = columns.iter().map(|column| {
+                row.values
+                    .get(column, positions)
+                    .map(|value| csv_value(&value))
+                    .unwrap_or_default()
+            });
+            write_csv_line(&mut writer, timestamp.into_iter().chain(values))?;
+        }
+        writer.flush().map_err(Into::into)
+    }
+
+    /// Write one JSON object per line. Missing values are left out of the
+    /// objects.
+    pub fn ndjson<W: Write>(&self, mut writer: W) -> Result<(), ExportError> {
+        if self.results.csv_lines().is_some() {
+            return Err(ExportError::Unsupported(
+                "CSV results can't be written as JSON",
+            ));
+        }
+        let columns = self.header();
+        let mut positions = Positions::default();
+        for row in self.results.rows() {
+            let positions = positions.of(&row.values);
+            writer.write_all(b"{")?;
+            let mut first = true;
+            if let Some(timestamp) = row.timestamp {
+                serde_json::to_writer(&mut writer, TIMESTAMP)?;
+                writer.write_all(b":")?;
+                serde_json::to_writer(&mut writer, &format_timestamp(timestamp))?;
+                first = false;
+            }
+            for column in &columns {
+                if let Some(value) = row.values.get(column, positions) {
+                    if !first {
+                        writer.write_all(b",")?;
+                    }
+                    serde_json::to_writer(&mut writer, column)?;
+                    writer.write_all(b":")?;
+                    serde_json::to_writer(&mut writer, &value)?;
+                    first = false;
+                }
+            }
+            writer.write_all(b"}\n")?;
+        }
+        writer.flush().map_err(Into::into)
+    }
+
+    /// Write a Parquet file with the columns typed like
+    /// [`ToRecordBatch`](crate::record_batch::ToRecordBatch) types them.
+    ///
+    /// Unlike the other formats, all rows are converted before writing.
+    #[cfg(feature = "parquet")]
+    pub fn parquet<W: Write + Send>(&self, writer: W) -> Result<(), ExportError>
+    where
+        R: crate::record_batch::ToRecordBatch,
+    {
+        let mut batch = self.results.to_record_batch()?;
+        if let Some(columns) = &self.columns {
+            let schema = batch.schema();
+            let mut indices = Vec::new();
+            if let Ok(index) = schema.index_of(TIMESTAMP) {
+                if self.results.has_timestamps() {
+                    indices.push(index);
+                }
+            }
+            for column in columns {
+                indices.push(schema.index_of(column)?);
+            }
+            batch = batch.project(&indices)?;
+        }
+        let mut writer = parquet::arrow::ArrowWriter::try_new(writer, batch.schema(), None)?;
+        writer.write(&batch)?;
+        writer.close()?;
+        Ok(())
+    }
+
+    /// The columns to write, besides the timestamp.
+    fn header(&self) -> Vec<&str> {
+        if let Some(columns) = &self.columns {
+            return columns.iter().map(String::as_str).collect();
+        }
+        if let Some(columns) = self.results.declared_columns() {
+            return columns;
+        }
+        let mut names = BTreeSet::new();
+        for row in self.results.rows() {
+            names.extend(row.values.names());
+        }
+        names.into_iter().collect()
+    }
+}
+
+fn format_timestamp(timestamp: &DateTime<Utc>) -> String {
+    timestamp.to_rfc3339_opts(SecondsFormat::Millis, true)
+}
+
+fn csv_value(value: &DruidNativeType) -> String {
+    match value {
+        DruidNativeType::String(s) => s.clone(),
+        DruidNativeType::Long(l) => l.to_string(),
+        DruidNativeType::Float(f) => f.to_string(),
+        DruidNativeType::Double(d) => d.to_string(),
+        DruidNativeType::Array(_) => serde_json::to_string(value).unwrap_or_default(),
+    }
+}
+
+/// Write fields separated by commas, quoting the ones that need it.
+fn write_csv_line<W, I>(writer: &mut W, fields: I) -> Result<(), ExportError>
+where
+    W: Write,
+    I: IntoIterator<Item = String>,
+{
+    for (i, field) in fields.into_iter().enumerate() {
+        if i > 0 {
+            writer.write_all(b",")?;
+        }
+        if field.contains(['"', ',', '\n', '\r']) {
+            write!(writer, "\"{}\"", field.replace('"', "\"\""))?;
+        } else {
+            writer.write_all(field.as_bytes())?;
+        }
+    }
+    writer.write_all(b"\n")?;
+    Ok(())
+}
+
+/// Results that can be exported, implemented for slices of all result types
+/// and for [`SqlResultSet`].
+pub trait Exportable: private::Rows {}
+
+impl<R: private::Rows + ?Sized> Exportable for R {}
+
+mod private {
+    use super::Row;
+
+    pub trait Rows {
+        fn has_timestamps(&self) -> bool;
+
+        /// The column order given by the results themselves.
+        fn declared_columns(&self) -> Option<Vec<&str>> {
+            None
+        }
+
+        /// The lines of results in the CSV format.
+        fn csv_lines(&self) -> Option<Vec<&str>> {
+            None
+        }
+
+        fn rows(&self) -> Box<dyn Iterator<Item = Row<'_>> + '_>;
+    }
+}
+
+/// A result row with the timestamp of its bucket, if it has one.
+#[doc(hidden)]
+pub struct Row<'r> {
+    timestamp: Option<&'r DateTime<Utc>>,
+    values: Values<'r>,
+}
+
+impl<'r> Row<'r> {
+    fn map(
+        timestamp: Option<&'r DateTime<Utc>>,
+        map: &'r HashMap<String, DruidNativeType>,
+    ) -> Self {
+        Self {
+            timestamp,
+            values: Values::Map(map),
+        }
+    }
+
+    fn zipped(names: &'r [String], values: &'r [DruidNativeType]) -> Self {
+        Self {
+            timestamp: None,
+            values: Values::Zipped(names, values),
+        }
+    }
+}
+
+enum Values<'r> {
+    Map(&'r HashMap<String, DruidNativeType>),
+    Zipped(&'r [String], &'r [DruidNativeType]),
+    /// Values of a SQL array result with the header row as names.
+    Header(&'r [DruidNativeType], &'r [DruidNativeType]),
+    /// A row of a [`SqlResultSet`], where nulls are missing values.
+    Sql(&'r [ColumnSchema], &'r [SqlValue]),
+}
+
+impl<'r> Values<'r> {
+    /// The value of column `name`, with `positions` from [`Positions::of`].
+    fn get(
+        &self,
+        name: &str,
+        positions: &HashMap<&str, usize>,
+    ) -> Option<Cow<'r, DruidNativeType>> {
+        let position = || positions.get(name).copied();
+        match *self {
+            Values::Map(map) => map.get(name).map(Cow::Borrowed),
+            Values::Zipped(_, values) | Values::Header(_, values) => {
+                values.get(position()?).map(Cow::Borrowed)
+            }
+            Values::Sql(_, values) => values.get(position()?)?.to_native().map(Cow::Owned),
+        }
+    }
+
+    /// The address of the column names of positional values, which all rows
+    /// of a result share.
+    fn names_id(&self) -> Option<*const u8> {
+        match *self {
+            Values::Map(_) => None,
+            Values::Zipped(names, _) => Some(names.as_ptr().cast()),
+            Values::Header(names, _) => Some(names.as_ptr().cast()),
+            Values::Sql(columns, _) => Some(columns.as_ptr().cast()),
+        }
+    }
+
+    /// The position of each column name of positional values. The first of
+    /// duplicate names wins.
+    fn positions(&self) -> HashMap<&'r str, usize> {
+        let mut positions = HashMap::new();
+        let names: Box<dyn Iterator<Item = (usize, &'r str)>> = match *self {
+            Values::Map(_) => return positions,
+            Values::Zipped(names, _) => Box::new(names.iter().map(String::as_str).enumerate()),
+            Values::Header(names, _) => Box::new(
+                names
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(i, name)| Some((i, name.as_str()?))),
+            ),
+            Values::Sql(columns, _) => Box::new(
+                columns
+                    .iter()
+                    .map(|column| column.name.as_str())
+                    .enumerate(),
+            ),
+        };
+        for (i, name) in names {
+            positions.entry(name).or_insert(i);
+        }
+        positions
+    }
+
+    fn names(&self) -> Vec<&'r str> {
+        match *self {
+            Values::Map(map) => map.keys().map(String::as_str).collect(),
+            Values::Zipped(names, _) => names.iter().map(String::as_str).collect(),
+            Values::Header(names, _) => names.iter().filter_map(DruidNativeType::as_str).collect(),
+            Values::Sql(columns, _) => columns.iter().map(|column| column.name.as_str()).collect(),
+        }
+    }
+}
+
+/// Column positions of positional rows, indexed once for each list of
+/// names rather than searched for every value.
+#[derive(Default)]
+struct Positions<'r> {
+    names_id: Option<*const u8>,
+    positions: HashMap<&'r str, usize>,
+}
+
+impl<'r> Positions<'r> {
+    fn of(&mut self, values: &Values<'r>) -> &HashMap<&'r str, usize> {
+        let names_id = values.names_id();
+        if names_id.is_some() && names_id != self.names_id {
+            self.names_id = names_id;
+            self.positions = values.positions();
+        }
+        &self.positions
+    }
+}
+
+impl private::Rows for [ScanResult] {
+    fn has_timestamps(&self) -> bool {
+        false
+    }
+
+    fn declared_columns(&self) -> Option<Vec<&str>> {
+        match self.first()? {
+            ScanResult::List { columns, .. } | ScanResult::CompactedList { columns, .. } => {
+                Some(columns.iter().map(String::as_str).collect())
+            }
+        }
+    }
+
+    fn rows(&self) -> Box<dyn Iterator<Item = Row<'_>> + '_> {
+        Box::new(
+            self.iter()
+                .flat_map(|result| -> Box<dyn Iterator<Item = Row<'_>>> {
+                    match result {
+                        ScanResult::List { events, .. } => {
+                            Box::new(events.iter().map(|event| Row::map(None, event)))
+                        }
+                        ScanResult::CompactedList {
+                            columns, events, ..
+                        } => Box::new(events.iter().map(|event| Row::zipped(columns, event))),
+                    }
+                }),
+        )
+    }
+}
+
+impl private::Rows for [GroupByResult] {
+    fn has_timestamps(&self) -> bool {
+        true
+    }
+
+    fn rows(&self) -> Box<dyn Iterator<Item = Row<'_>> + '_> {
+        Box::new(
+            self.iter()
+                .map(|result| Row::map(Some(&result.timestamp), &result.event)),
+        )
+    }
+}
+
+impl private::Rows for [TimeseriesResult] {
+    fn has_timestamps(&self) -> bool {
+        self.iter().any(|result| result.timestamp.is_some())
+    }
+
+    fn rows(&self) -> Box<dyn Iterator<Item = Row<'_>> + '_> {
+        Box::new(
+            self.iter()
+                .map(|result| Row::map(result.timestamp.as_ref(), &result.result)),
+        )
+    }
+}
+
+/// Flattened to one row per ranked value, with the bucket timestamp repeated.
+impl private::Rows for [TopNResult] {
+    fn has_timestamps(&self) -> bool {
+        true
+    }
+
+    fn rows(&self) -> Box<dyn Iterator<Item = Row<'_>> + '_> {
+        Box::new(self.iter().flat_map(|result| {
+            result
+                .result
+                .iter()
+                .map(|row| Row::map(Some(&result.timestamp), row))
+        }))
+    }
+}
+
+/// Array results must start with the header row, queried with `header: true`.
+/// Object results keep the order of their header row, if they have one.
+impl private::Rows for [SqlResult] {
+    fn has_timestamps(&self) -> bool {
+        false
+    }
+
+    fn declared_columns(&self) -> Option<Vec<&str>> {
+        match self.first()? {
+            SqlResult::Array(header) => header.iter().map(DruidNativeType::as_str).collect(),
+            SqlResult::Header(names) => Some(names.iter().map(String::as_str).collect()),
+            _ => None,
+        }
+    }
+
+    fn csv_lines(&self) -> Option<Vec<&str>> {
+        if !matches!(self.first(), Some(SqlResult::Csv(_))) {
+            return None;
+        }
+        self.iter()
+            .map(|result| match result {
+                SqlResult::Csv(line) => Some(line.as_str()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn rows(&self) -> Box<dyn Iterator<Item = Row<'_>> + '_> {
+        let header = match self.first() {
+            Some(SqlResult::Array(header)) => Some(header.as_slice()),
+            _ => None,
+        };
+        let skip =
+            usize::from(header.is_some() || matches!(self.first(), Some(SqlResult::Header(_))));
+        Box::new(self[skip..].iter().filter_map(move |result| match result {
+            SqlResult::Object(row) => Some(Row::map(None, row)),
+            SqlResult::Array(row) => header.map(|header| Row {
+                timestamp: None,
+                values: Values::Header(header, row),
+            }),
+            // Only the first row can be the header.
+            SqlResult::Header(_) | SqlResult::Csv(_) => None,
+        }))
+    }
+}
+
+impl private::Rows for SqlResultSet {
+    fn has_timestamps(&self) -> bool {
+        false
+    }
+
+    fn declared_columns(&self) -> Option<Vec<&str>> {
+        Some(
+            self.columns
+                .iter()
+                .map(|column| column.name.as_str())
+                .collect(),
+        )
+    }
+
+    fn rows(&self) -> Box<dyn Iterator<Item = Row<'_>> + '_> {
+        Box::new(self.rows.iter().map(|row| Row {
+            timestamp: None,
+            values: Values::Sql(&self.columns, row),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Exporter;
+    use crate::{
+        components::druid_types::DruidNativeType,
+        queries::{
+            response::{ScanResult, SqlResult, TopNResult},
+            sql::{ResultFormat, Sql},
+            sql_result::SqlResultSet,
+        },
+    };
+
+    #[test]
+    fn writes_scan_results_in_column_order() {
+        let results = [ScanResult::CompactedList {
+            segment_id: "s".into(),
+            columns: vec!["page".into(), "tags".into(), "added".into()],
+            events: vec![
+                vec![
+                    DruidNativeType::string("Rust, \"the\" language"),
+                    DruidNativeType::Array(vec!["a".into(), "b".into()]),
+                    DruidNativeType::long(3),
+                ],
+                vec![
+                    DruidNativeType::string("Go"),
+                    DruidNativeType::string("c"),
+                    DruidNativeType::long(5),
+                ],
+            ],
+        }];
+        let mut csv = Vec::new();
+        Exporter::new(results.as_slice()).csv(&mut csv).unwrap();
+        assert_eq!(
+            String::from_utf8(csv).unwrap(),
+            "page,tags,added\n\"Rust, \"\"the\"\" language\",\"[\"\"a\"\",\"\"b\"\"]\",3\nGo,c,5\n"
+        );
+
+        let mut json = Vec::new();
+        Exporter::new(results.as_slice())
+            .columns(&["added".into(), "page".into()])
+            .ndjson(&mut json)
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(json).unwrap(),
+            "{\"added\":3,\"page\":\"Rust, \\\"the\\\" language\"}\n{\"added\":5,\"page\":\"Go\"}\n"
+        );
+    }
+
+    #[test]
+    fn writes_topn_and_sql_results() {
+        let results: Vec<TopNResult> = serde_json::from_str(
+            r#"[{"timestamp": "2015-09-12T00:00:00.000Z", "result": [
+                {"page": "Rust", "edits": 3}, {"page": "Go"}]}]"#,
+        )
+        .unwrap();
+        let mut csv = Vec::new();
+        Exporter::new(results.as_slice()).csv(&mut csv).unwrap();
+        assert_eq!(
+            String::from_utf8(csv).unwrap(),
+            "timestamp,edits,page\n2015-09-12T00:00:00.000Z,3,Rust\n2015-09-12T00:00:00.000Z,,Go\n"
+        );
+
+        let results = [
+            SqlResult::Array(vec![
+                DruidNativeType::string("user"),
+                DruidNativeType::string("edits"),
+            ]),
+            SqlResult::Array(vec![
+                DruidNativeType::string("bob"),
+                DruidNativeType::long(2),
+            ]),
+        ];
+        let mut json = Vec::new();
+        Exporter::new(results.as_slice()).ndjson(&mut json).unwrap();
+        assert_eq!(
+            String::from_utf8(json).unwrap(),
+            "{\"user\":\"bob\",\"edits\":2}\n"
+        );
+
+        let results = SqlResult::from_object_rows(
+            r#"[{"user": null, "edits": null, "active": null}, {"user": "bob", "edits": 2},
+                {"user": null, "edits": null, "active": null}]"#,
+            true,
+        )
+        .unwrap();
+        let mut csv = Vec::new();
+        Exporter::new(results.as_slice()).csv(&mut csv).unwrap();
+        assert_eq!(
+            String::from_utf8(csv).unwrap(),
+            "user,edits,active\nbob,2,\n,,\n"
+        );
+
+        let results = [SqlResult::Csv("a,b".into()), SqlResult::Csv("1,2".into())];
+        let mut csv = Vec::new();
+        Exporter::new(results.as_slice()).csv(&mut csv).unwrap();
+        assert_eq!(String::from_utf8(csv).unwrap(), "a,b\n1,2\n");
+        assert!(Exporter::new(results.as_slice())
+            .ndjson(Vec::new())
+            .is_err());
+
+        let results: [SqlResult; 0] = [];
+        let mut json = Vec::new();
+        Exporter::new(results.as_slice()).ndjson(&mut json).unwrap();
+        assert!(json.is_empty());
+        let mut csv = Vec::new();
+        Exporter::new(results.as_slice())
+            .columns(&["user".into()])
+            .csv(&mut csv)
+            .unwrap();
+        assert_eq!(String::from_utf8(csv).unwrap(), "user\n");
+    }
+
+    #[test]
+    fn writes_sql_result_sets() {
+        let sql = Sql::new("SELECT user, edits, active, __time FROM edits")
+            .result_format(ResultFormat::Array)
+            .header(true)
+            .types_header(true);
+        let results = SqlResultSet::parse(
+            r#"[["user", "edits", "active", "__time"], ["STRING", "LONG", "LONG", "LONG"],
+                ["bob", 2, null, 1442016000000]]"#,
+            &sql,
+        )
+        .unwrap();
+        let mut csv = Vec::new();
+        Exporter::new(&results).csv(&mut csv).unwrap();
+        assert_eq!(
+            String::from_utf8(csv).unwrap(),
+            "user,edits,active,__time\nbob,2,,1442016000000\n"
+        );
+
+        let mut json = Vec::new();
+        Exporter::new(&results)
+            .columns(&["edits".into(), "active".into()])
+            .ndjson(&mut json)
+            .unwrap();
+        assert_eq!(String::from_utf8(json).unwrap(), "{\"edits\":2}\n");
+    }
+
+    #[cfg(feature = "parquet")]
+    #[test]
+    fn writes_parquet() {
+        let results: Vec<TopNResult> = serde_json::from_str(
+            r#"[{"timestamp": "2015-09-12T00:00:00.000Z", "result": [
+                {"page": "Rust", "edits": 3}, {"page": "Go", "edits": 2}]}]"#,
+        )
+        .unwrap();
+        let mut parquet = Vec::new();
+        Exporter::new(results.as_slice())
+            .columns(&["page".into()])
+            .parquet(&mut parquet)
+            .unwrap();
+        assert!(parquet.starts_with(b"PAR1") && parquet.ends_with(b"PAR1"));
+        assert!(Exporter::new(results.as_slice())
+            .columns(&["user".into()])
+            .parquet(Vec::new())
+            .is_err());
+    }
+}
@@ -0,0 +1,809 @@
+//! Local evaluation of expressions against a single row.
+
+use std::{cmp::Ordering, collections::HashMap, fmt};
+
+use chrono::{DateTime, Datelike, NaiveDate};
+
+use crate::components::druid_types::DruidNativeType;
+
+use super::{lint, BinaryOp, Expr, UnaryOp};
+
+/// The value of an evaluated expression.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Null,
+    Long(i64),
+    Double(f64),
+    String(String),
+    Array(Vec<Value>),
+}
+
+/// Why an expression couldn't be evaluated.
+#[derive(Debug, thiserror::Error)]
+pub enum EvalError {
+    #[error("unknown function {0}")]
+    UnknownFunction(String),
+    #[error("function {function} can't take {found} arguments")]
+    ArgumentCount { function: String, found: usize },
+    #[error("{0} can't be evaluated locally")]
+    Unsupported(String),
+    #[error("division by zero")]
+    DivisionByZero,
+    #[error("{0}")]
+    InvalidArgument(String),
+}
+
+impl Value {
+    /// Druid's notion of truth: numbers greater than zero and the string
+    /// `true`.
+    pub fn is_truthy(&self) -> bool {
+        match self {
+            Self::Long(l) => *l > 0,
+            Self::Double(d) => *d > 0.0,
+            Self::String(s) => s.eq_ignore_ascii_case("true"),
+            Self::Null | Self::Array(_) => false,
+        }
+    }
+
+    pub fn as_long(&self) -> Option<i64> {
+        match self {
+            Self::Long(l) => Some(*l),
+            Self::Double(d) => Some(*d as i64),
+            Self::String(s) => s
+                .trim()
+                .parse()
+                .ok()
+                .or_else(|| s.trim().parse::<f64>().ok().map(|d| d as i64)),
+            Self::Null | Self::Array(_) => None,
+        }
+    }
+
+    pub fn as_double(&self) -> Option<f64> {
+        match self {
+            Self::Long(l) => Some(*l as f64),
+            Self::Double(d) => Some(*d),
+            Self::String(s) => s.trim().parse().ok(),
+            Self::Null | Self::Array(_) => None,
+        }
+    }
+
+    fn from_bool(value: bool) -> Self {
+        Self::Long(value.into())
+    }
+
+    /// A string or number as a number, `Null` if it isn't one.
+    fn to_number(&self) -> Self {
+        match self {
+            Self::Long(_) | Self::Double(_) => self.clone(),
+            Self::String(s) => match s.trim().parse() {
+                Ok(l) => Self::Long(l),
+                Err(_) => s.trim().parse().map_or(Self::Null, Self::Double),
+            },
+            Self::Null | Self::Array(_) => Self::Null,
+        }
+    }
+
+    /// Arrays as is, scalars as single element arrays.
+    fn into_array(self) -> Vec<Value> {
+        match self {
+            Self::Array(items) => items,
+            Self::Null => Vec::new(),
+            other => vec![other],
+        }
+    }
+}
+
+impl From<&DruidNativeType> for Value {
+    fn from(value: &DruidNativeType) -> Self {
+        match value {
+            DruidNativeType::String(s) => Self::String(s.clone()),
+            DruidNativeType::Array(items) => {
+                Self::Array(items.iter().cloned().map(Self::String).collect())
+            }
+            DruidNativeType::Long(l) => Self::Long(*l),
+            DruidNativeType::Float(f) => Self::Double((*f).into()),
+            DruidNativeType::Double(d) => Self::Double(*d),
+        }
+    }
+}
+
+/// Renders values the way Druid casts them to strings.
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Null => f.write_str("null"),
+            Self::Long(l) => write!(f, "{l}"),
+            Self::Double(d) if d.is_nan() => f.write_str("NaN"),
+            Self::Double(d) if d.is_infinite() => {
+                f.write_str(if *d > 0.0 { "Infinity" } else { "-Infinity" })
+            }
+            Self::Double(d) => write!(f, "{d:?}"),
+            Self::String(s) => f.write_str(s),
+            Self::Array(items) => {
+                f.write_str("[")?;
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write!(f, "{item}")?;
+                }
+                f.write_str("]")
+            }
+        }
+    }
+}
+
+/// The columns of a row, overlaid with the parameters of enclosing lambdas.
+pub(super) struct Scope<'a> {
+    row: &'a HashMap<String, DruidNativeType>,
+    bindings: HashMap<String, Value>,
+}
+
+impl<'a> Scope<'a> {
+    pub(super) fn new(row: &'a HashMap<String, DruidNativeType>) -> Self {
+        Self {
+            row,
+            bindings: HashMap::new(),
+        }
+    }
+
+    fn get(&self, name: &str) -> Value {
+        match self.bindings.get(name) {
+            Some(value) => value.clone(),
+            None => self.row.get(name).map_or(Value::Null, Value::from),
+        }
+    }
+
+    fn with(&self, params: &[String], values: Vec<Value>) -> Scope<'a> {
+        let mut bindings = self.bindings.clone();
+        bindings.extend(params.iter().cloned().zip(values));
+        Scope {
+            row: self.row,
+            bindings,
+        }
+    }
+
+    pub(super) fn eval(&self, expr: &Expr) -> Result<Value, EvalError> {
+        match expr {
+            Expr::Null => Ok(Value::Null),
+            Expr::Long(l) => Ok(Value::Long(*l)),
+            Expr::Double(d) => Ok(Value::Double(*d)),
+            Expr::String(s) => Ok(Value::String(s.clone())),
+            Expr::Array(items) => items
+                .iter()
+                .map(|item| self.eval(item))
+                .collect::<Result<_, _>>()
+                .map(Value::Array),
+            Expr::Identifier(name) => Ok(self.get(name)),
+            Expr::Unary { op, expr } => Ok(unary(*op, self.eval(expr)?)),
+            Expr::Binary {
+                op: BinaryOp::And,
+                left,
+                right,
+            } => {
+                let left = self.eval(left)?;
+                if !matches!(left, Value::Null) && !left.is_truthy() {
+                    return Ok(Value::from_bool(false));
+                }
+                match (left, self.eval(right)?) {
+                    (_, right) if !matches!(right, Value::Null) && !right.is_truthy() => {
+                        Ok(Value::from_bool(false))
+                    }
+                    (Value::Null, _) | (_, Value::Null) => Ok(Value::Null),
+                    _ => Ok(Value::from_bool(true)),
+                }
+            }
+            Expr::Binary {
+                op: BinaryOp::Or,
+                left,
+                right,
+            } => {
+                let left = self.eval(left)?;
+                if left.is_truthy() {
+                    return Ok(Value::from_bool(true));
+                }
+                match (left, self.eval(right)?) {
+                    (_, right) if right.is_truthy() => Ok(Value::from_bool(true)),
+                    (Value::Null, _) | (_, Value::Null) => Ok(Value::Null),
+                    _ => Ok(Value::from_bool(false)),
+                }
+            }
+            Expr::Binary { op, left, right } => binary(*op, self.eval(left)?, self.eval(right)?),
+            Expr::Function { name, args } => self.function(name, args),
+            Expr::Lambda { .. } => Err(EvalError::InvalidArgument(
+                "a lambda is only allowed as the first argument of apply functions".into(),
+            )),
+        }
+    }
+
+    fn eval_all(&self, args: &[Expr]) -> Result<Vec<Value>, EvalError> {
+        args.iter().map(|arg| self.eval(arg)).collect()
+    }
+
+    /// Evaluate `lambda` for each element of `array`.
+    fn apply<'e>(
+        &'e self,
+        lambda: &'e Expr,
+        array: &Expr,
+    ) -> Result<impl Iterator<Item = Result<(Value, Value), EvalError>> + 'e, EvalError> {
+        let Expr::Lambda { params, body } = lambda else {
+            return Err(EvalError::InvalidArgument(
+                "apply functions take a lambda as their first argument".into(),
+            ));
+        };
+        let items = self.eval(array)?.into_array();
+        Ok(items.into_iter().map(move |item| {
+            let value = self.with(params, vec![item.clone()]).eval(body)?;
+            Ok((item, value))
+        }))
+    }
+
+    fn function(&self, name: &str, args: &[Expr]) -> Result<Value, EvalError> {
+        let Some((min, max)) = lint::arity(name) else {
+            return Err(EvalError::UnknownFunction(name.into()));
+        };
+        if args.len() < min || max.is_some_and(|max| args.len() > max) {
+            return Err(EvalError::ArgumentCount {
+                function: name.into(),
+                found: args.len(),
+            });
+        }
+        // Druid matches function names case-insensitively.
+        let name = name.to_ascii_lowercase();
+        let name = name.as_str();
+        match name {
+            "if" => {
+                if self.eval(&args[0])?.is_truthy() {
+                    self.eval(&args[1])
+                } else {
+                    self.eval(&args[2])
+                }
+            }
+            "nvl" => match self.eval(&args[0])? {
+                Value::Null => self.eval(&args[1]),
+                value => Ok(value),
+            },
+            "case_searched" => {
+                for pair in args.chunks(2) {
+                    match pair {
+                        [condition, result] => {
+                            if self.eval(condition)?.is_truthy() {
+                                return self.eval(result);
+                            }
+                        }
+                        [otherwise] => return self.eval(otherwise),
+                        _ => unreachable!(),
+                    }
+                }
+                Ok(Value::Null)
+            }
+            "case_simple" => {
+                let value = self.eval(&args[0])?;
+                for pair in args[1..].chunks(2) {
+                    match pair {
+                        [candidate, result] => {
+                            if binary(BinaryOp::Eq, value.clone(), self.eval(candidate)?)?
+                                .is_truthy()
+                            {
+                                return self.eval(result);
+                            }
+                        }
+                        [otherwise] => return self.eval(otherwise),
+                        _ => unreachable!(),
+                    }
+                }
+                Ok(Value::Null)
+            }
+            "map" => self
+                .apply(&args[0], &args[1])?
+                .map(|result| result.map(|(_, value)| value))
+                .collect::<Result<_, _>>()
+                .map(Value::Array),
+            "filter" => {
+                let mut kept = Vec::new();
+                for result in self.apply(&args[0], &args[1])? {
+                    let (item, value) = result?;
+                    if value.is_truthy() {
+                        kept.push(item);
+                    }
+                }
+                Ok(Value::Array(kept))
+            }
+            "any" => {
+                for result in self.apply(&args[0], &args[1])? {
+                    if result?.1.is_truthy() {
+                        return Ok(Value::from_bool(true));
+                    }
+                }
+                Ok(Value::from_bool(false))
+            }
+            "all" => {
+                for result in self.apply(&args[0], &args[1])? {
+                    if !result?.1.is_truthy() {
+                        return Ok(Value::from_bool(false));
+                    }
+                }
+                Ok(Value::from_bool(true))
+            }
+            "fold" => {
+                let Expr::Lambda { params, body } = &args[0] else {
+                    return Err(EvalError::InvalidArgument(
+                        "fold takes a lambda as its first argument".into(),
+                    ));
+                };
+                let mut accumulator = self.eval(&args[2])?;
+                for item in self.eval(&args[1])?.into_array() {
+                    accumulator = self.with(params, vec![item, accumulator]).eval(body)?;
+                }
+                Ok(accumulator)
+            }
+            _ => call(name, self.eval_all(args)?),
+        }
+    }
+}
+
+fn unary(op: UnaryOp, value: Value) -> Value {
+    match (op, value.to_number()) {
+        (_, Value::Null) => Value::Null,
+        (UnaryOp::Neg, Value::Long(l)) => Value::Long(l.wrapping_neg()),
+        (UnaryOp::Neg, Value::Double(d)) => Value::Double(-d),
+        (UnaryOp::Not, number) => Value::from_bool(!number.is_truthy()),
+        (_, _) => unreachable!(),
+    }
+}
+
+fn binary(op: BinaryOp, left: Value, right: Value) -> Result<Value, EvalError> {
+    if matches!(left, Value::Array(_)) || matches!(right, Value::Array(_)) {
+        return Err(EvalError::InvalidArgument(format!(
+            "operator {} doesn't take arrays",
+            op.symbol()
+        )));
+    }
+    if matches!(left, Value::Null) || matches!(right, Value::Null) {
+        return Ok(Value::Null);
+    }
+    if op == BinaryOp::Add
+        && (matches!(left, Value::String(_)) || matches!(right, Value::String(_)))
+    {
+        return Ok(Value::String(format!("{left}{right}")));
+    }
+    if op.is_comparison() {
+        let Some(ordering) = compare(&left, &right) else {
+            return Ok(Value::Null);
+        };
+        return Ok(Value::from_bool(match op {
+            BinaryOp::Lt => ordering == Ordering::Less,
+            BinaryOp::Le => ordering != Ordering::Greater,
+            BinaryOp::Gt => ordering == Ordering::Greater,
+            BinaryOp::Ge => ordering != Ordering::Less,
+            BinaryOp::Eq => ordering == Ordering::Equal,
+            _ => ordering != Ordering::Equal,
+        }));
+    }
+    Ok(match (left.to_number(), right.to_number()) {
+        (Value::Long(l), Value::Long(r)) => match op {
+            BinaryOp::Pow if r >= 0 => Value::Long(l.wrapping_pow(r.min(u32::MAX as i64) as u32)),
+            BinaryOp::Pow => Value::Double((l as f64).powf(r as f64)),
+            BinaryOp::Mul => Value::Long(l.wrapping_mul(r)),
+            BinaryOp::Div | BinaryOp::Mod if r == 0 => return Err(EvalError::DivisionByZero),
+            BinaryOp::Div => Value::Long(l.wrapping_div(r)),
+            BinaryOp::Mod => Value::Long(l.wrapping_rem(r)),
+            BinaryOp::Add => Value::Long(l.wrapping_add(r)),
+            _ => Value::Long(l.wrapping_sub(r)),
+        },
+        (Value::Null, _) | (_, Value::Null) => Value::Null,
+        (l, r) => {
+            let (l, r) = (l.as_double().unwrap(), r.as_double().unwrap());
+            Value::Double(match op {
+                BinaryOp::Pow => l.powf(r),
+                BinaryOp::Mul => l * r,
+                BinaryOp::Div => l / r,
+                BinaryOp::Mod => l % r,
+                BinaryOp::Add => l + r,
+                _ => l - r,
+            })
+        }
+    })
+}
+
+/// Strings compare lexically, everything else numerically.
+fn compare(left: &Value, right: &Value) -> Option<Ordering> {
+    match (left, right) {
+        (Value::String(l), Value::String(r)) => Some(l.cmp(r)),
+        (Value::Long(l), Value::Long(r)) => Some(l.cmp(r)),
+        _ => left
+            .as_double()
+            .zip(right.as_double())
+            .and_then(|(l, r)| l.partial_cmp(&r)),
+    }
+}
+
+/// Call a function that takes evaluated arguments.
+fn call(name: &str, mut args: Vec<Value>) -> Result<Value, EvalError> {
+    let string = |value: &Value| match value {
+        Value::Null => None,
+        value => Some(value.to_string()),
+    };
+    let long = |value: &Value| value.as_long();
+    let double = |value: &Value| value.as_double();
+    let strings = |f: fn(&str) -> String, value: &Value| match string(value) {
+        None => Value::Null,
+        Some(s) => Value::String(f(&s)),
+    };
+    let doubles = |f: fn(f64) -> f64, value: &Value| match value.to_number() {
+        Value::Double(d) => Value::Double(f(d)),
+        Value::Long(l) => Value::Double(f(l as f64)),
+        _ => Value::Null,
+    };
+    Ok(match name {
+        "isnull" => Value::from_bool(matches!(args[0], Value::Null)),
+        "notnull" => Value::from_bool(!matches!(args[0], Value::Null)),
+        "concat" => {
+            if args.iter().any(|arg| matches!(arg, Value::Null)) {
+                Value::Null
+            } else {
+                Value::String(args.iter().map(Value::to_string).collect())
+            }
+        }
+        "lower" => strings(str::to_lowercase, &args[0]),
+        "upper" => strings(str::to_uppercase, &args[0]),
+        "reverse" => strings(|s| s.chars().rev().collect(), &args[0]),
+        "trim" | "ltrim" | "rtrim" if args.len() == 1 => {
+            let Some(s) = string(&args[0]) else {
+                return Ok(Value::Null);
+            };
+            Value::String(
+                match name {
+                    "trim" => s.trim(),
+                    "ltrim" => s.trim_start(),
+                    _ => s.trim_end(),
+                }
+                .into(),
+            )
+        }
+        "strlen" => {
+            string(&args[0]).map_or(
+                Value::Null,
+                |s| Value::Long(s.encode_utf16().count() as i64),
+            )
+        }
+        "substring" => match (string(&args[0]), long(&args[1]), long(&args[2])) {
+            (Some(s), Some(index), Some(length)) => {
+                let chars = s.chars().skip(index.max(0) as usize);
+                Value::String(if length < 0 {
+                    chars.collect()
+                } else {
+                    chars.take(length as usize).collect()
+                })
+            }
+            _ => Value::Null,
+        },
+        "left" | "right" => match (string(&args[0]), long(&args[1])) {
+            (Some(s), Some(length)) if length >= 0 => {
+                let count = s.chars().count();
+                let length = (length as usize).min(count);
+                Value::String(if name == "left" {
+                    s.chars().take(length).collect()
+                } else {
+                    s.chars().skip(count - length).collect()
+                })
+            }
+            (Some(_), Some(_)) => {
+                return Err(EvalError::InvalidArgument(format!(
+                    "{name} takes a non-negative length"
+                )))
+            }
+            _ => Value::Null,
+        },
+        "replace" => match (string(&args[0]), string(&args[1]), string(&args[2])) {
+            (Some(s), Some(pattern), Some(replacement)) => {
+                Value::String(s.replace(&pattern, &replacement))
+            }
+            _ => Value::Null,
+        },
+        "abs" => match args[0].to_number() {
+            Value::Long(l) => Value::Long(l.wrapping_abs()),
+            Value::Double(d) => Value::Double(d.abs()),
+            _ => Value::Null,
+        },
+        "floor" | "ceil" => match args[0].to_number() {
+            Value::Long(l) => Value::Long(l),
+            Value::Double(d) if name == "floor" => Value::Double(d.floor()),
+            Value::Double(d) => Value::Double(d.ceil()),
+            _ => Value::Null,
+        },
+        "round" => {
+            let scale = args.get(1).map_or(Some(0), long);
+            match (args[0].to_number(), scale) {
+                (Value::Long(l), Some(scale)) if scale >= 0 => Value::Long(l),
+                (Value::Long(l), Some(scale)) => {
+                    let factor = 10f64.powi(-scale as i32);
+                    Value::Long(((l as f64 / factor).round() * factor) as i64)
+                }
+                (Value::Double(d), Some(scale)) => {
+                    let factor = 10f64.powi(scale as i32);
+                    Value::Double((d * factor).round() / factor)
+                }
+                _ => Value::Null,
+            }
+        }
+        "sqrt" => doubles(f64::sqrt, &args[0]),
+        "exp" => doubles(f64::exp, &args[0]),
+        "log" => doubles(f64::ln, &args[0]),
+        "log10" => doubles(f64::log10, &args[0]),
+        "pow" => match (double(&args[0]), double(&args[1])) {
+            (Some(base), Some(exponent)) => Value::Double(base.powf(exponent)),
+            _ => Value::Null,
+        },
+        "greatest" | "least" => {
+            args.retain(|arg| !matches!(arg, Value::Null));
+            let pick = if name == "greatest" {
+                Ordering::Greater
+            } else {
+                Ordering::Less
+            };
+            let mut best = None::<Value>;
+            for arg in args {
+                if best
+                    .as_ref()
+                    .is_none_or(|current| compare(&arg, current) == Some(pick))
+                {
+                    best = Some(arg);
+                }
+            }
+            best.unwrap_or(Value::Null)
+        }
+        "cast" => {
+            let Some(target) = string(&args[1]) else {
+                return Ok(Value::Null);
+            };
+            match (target.to_ascii_uppercase().as_str(), &args[0]) {
+                (_, Value::Null) => Value::Null,
+                ("LONG", value) => value.as_long().map_or(Value::Null, Value::Long),
+                ("DOUBLE" | "FLOAT", value) => value.as_double().map_or(Value::Null, Value::Double),
+                ("STRING", value) => Value::String(value.to_string()),
+                _ => return Err(EvalError::Unsupported(format!("cast to {target}"))),
+            }
+        }
+        "timestamp_floor" => {
+            if args[2..].iter().any(|arg| !matches!(arg, Value::Null)) {
+                return Err(EvalError::Unsupported(
+                    "timestamp_floor with an origin or time zone".into(),
+                ));
+            }
+            match (long(&args[0]), string(&args[1])) {
+                (Some(millis), Some(period)) => Value::Long(timestamp_floor(millis, &period)?),
+                _ => Value::Null,
+            }
+        }
+        "array" => Value::Array(args),
+        "array_length" => match &args[0] {
+            Value::Array(items) => Value::Long(items.len() as i64),
+            _ => Value::Null,
+        },
+        "array_offset" | "array_ordinal" => match (&args[0], long(&args[1])) {
+            (Value::Array(items), Some(index)) => {
+                let index = if name == "array_offset" {
+                    index
+                } else {
+                    index - 1
+                };
+                usize::try_from(index)
+                    .ok()
+                    .and_then(|index| items.get(index).cloned())
+                    .unwrap_or(Value::Null)
+            }
+            _ => Value::Null,
+        },
+        "array_contains" | "array_overlap" => {
+            let Value::Array(items) = &args[0] else {
+                return Ok(Value::Null);
+            };
+            let values = args[1].clone().into_array();
+            let contains = |value: &Value| items.iter().any(|item| item == value);
+            Value::from_bool(if name == "array_contains" {
+                values.iter().all(contains)
+            } else {
+                values.iter().any(contains)
+            })
+        }
+        "array_append" | "array_concat" => {
+            let mut items = args[0].clone().into_array();
+            items.extend(args[1].clone().into_array());
+            Value::Array(items)
+        }
+        "array_slice" => {
+            let Value::Array(items) = &args[0] else {
+                return Ok(Value::Null);
+            };
+            let start = long(&args[1]).unwrap_or(0).max(0) as usize;
+            let end = args
+                .get(2)
+                .and_then(long)
+                .map_or(items.len(), |end| (end.max(0) as usize).min(items.len()));
+            if start > end {
+                Value::Null
+            } else {
+                Value::Array(items[start..end].to_vec())
+            }
+        }
+        "array_to_string" => match (&args[0], string(&args[1])) {
+            (Value::Array(items), Some(separator)) => Value::String(
+                items
+                    .iter()
+                    .map(Value::to_string)
+                    .collect::<Vec<_>>()
+                    .join(&separator),
+            ),
+            _ => Value::Null,
+        },
+        "string_to_array" => match (string(&args[0]), string(&args[1])) {
+            (Some(s), Some(separator)) => {
+                // Druid splits on a regular expression.
+                if separator.contains(|c| "\\^$.|?*+()[]{}".contains(c)) {
+                    return Err(EvalError::Unsupported(
+                        "string_to_array with a regular expression".into(),
+                    ));
+                }
+                Value::Array(
+                    s.split(separator.as_str())
+                        .map(|part| Value::String(part.into()))
+                        .collect(),
+                )
+            }
+            _ => Value::Null,
+        },
+        _ => return Err(EvalError::Unsupported(name.into())),
+    })
+}
+
+/// Floor epoch milliseconds to a period in UTC.
+fn timestamp_floor(millis: i64, period: &str) -> Result<i64, EvalError> {
+    const SECOND: i64 = 1000;
+    const DAY: i64 = 24 * 60 * 60 * SECOND;
+    // 1970-01-05, the first Monday after the epoch.
+    const MONDAY: i64 = 4 * DAY;
+
+    let invalid = || EvalError::InvalidArgument(format!("unsupported period {period}"));
+    let (time, rest) = match period.strip_prefix("PT") {
+        Some(rest) => (true, rest),
+        None => (false, period.strip_prefix('P').ok_or_else(invalid)?),
+    };
+    let split = rest.len().checked_sub(1).ok_or_else(invalid)?;
+    let (count, unit) = rest.split_at(split);
+    let count: i64 = count.parse().map_err(|_| invalid())?;
+    if count <= 0 {
+        return Err(invalid());
+    }
+    let floor =
+        |millis: i64, step: i64, origin: i64| (millis - origin).div_euclid(step) * step + origin;
+    let months = |step: i64| {
+        let date = DateTime::from_timestamp_millis(millis)
+            .ok_or_else(invalid)?
+            .date_naive();
+        let month = floor(
+            date.year() as i64 * 12 + date.month0() as i64,
+            step,
+            1970 * 12,
+        );
+        let start = NaiveDate::from_ymd_opt(
+            month.div_euclid(12) as i32,
+            month.rem_euclid(12) as u32 + 1,
+            1,
+        )
+        .ok_or_else(invalid)?;
+        Ok(start
+            .and_hms_opt(0, 0, 0)
+            .unwrap()
+            .and_utc()
+            .timestamp_millis())
+    };
+    match (time, unit) {
+        (true, "S") => Ok(floor(millis, count * SECOND, 0)),
+        (true, "M") => Ok(floor(millis, count * 60 * SECOND, 0)),
+        (true, "H") => Ok(floor(millis, count * 60 * 60 * SECOND, 0)),
+        (false, "D") => Ok(floor(millis, count * DAY, 0)),
+        (false, "W") => Ok(floor(millis, count * 7 * DAY, MONDAY)),
+        (false, "M") => months(count),
+        (false, "Y") => months(count * 12),
+        _ => Err(invalid()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::{EvalError, Value};
+    use crate::{components::druid_types::DruidNativeType, expr::Expr};
+
+    fn eval(expression: &str, row: &HashMap<String, DruidNativeType>) -> Value {
+        Expr::parse(expression).unwrap().evaluate(row).unwrap()
+    }
+
+    #[test]
+    fn evaluates_expressions() {
+        let row = HashMap::from([
+            ("added".to_string(), DruidNativeType::long(10)),
+            ("deleted".to_string(), DruidNativeType::double(2.5)),
+            ("page".to_string(), DruidNativeType::string("Rust")),
+            (
+                "tags".to_string(),
+                DruidNativeType::Array(vec!["a".into(), "b".into()]),
+            ),
+            // 2024-05-15T13:45:00Z, a Wednesday
+            (
+                "__time".to_string(),
+                DruidNativeType::long(1715780700000i64),
+            ),
+        ]);
+        assert_eq!(eval("added / 3 + 2 ^ 3", &row), Value::Long(11));
+        assert_eq!(eval("added - deleted", &row), Value::Double(7.5));
+        assert_eq!(
+            eval("page + '-' + added", &row),
+            Value::String("Rust-10".into())
+        );
+        assert_eq!(eval("added > 5 && page == 'Rust'", &row), Value::Long(1));
+        assert_eq!(eval("missing + 1", &row), Value::Null);
+        assert_eq!(eval("missing > 1 || added > 1", &row), Value::Long(1));
+        assert_eq!(eval("missing > 1 && added > 1", &row), Value::Null);
+        assert_eq!(
+            eval("nvl(missing, concat(upper(page), strlen(page)))", &row),
+            Value::String("RUST4".into())
+        );
+        assert_eq!(
+            eval(
+                "case_searched(added > 100, 'big', added > 5, 'medium', 'small')",
+                &row
+            ),
+            Value::String("medium".into())
+        );
+        assert_eq!(
+            eval("map((t) -> concat(t, '!'), tags)", &row),
+            Value::Array(vec![Value::String("a!".into()), Value::String("b!".into())])
+        );
+        assert_eq!(
+            eval("fold((x, acc) -> acc + x, [1, 2, 3], 0)", &row),
+            Value::Long(6)
+        );
+        assert_eq!(eval("array_contains(tags, 'b')", &row), Value::Long(1));
+        assert_eq!(
+            eval("timestamp_floor(__time, 'PT1H')", &row),
+            Value::Long(1715778000000)
+        );
+        // 2024-05-13T00:00:00Z, the Monday before
+        assert_eq!(
+            eval("timestamp_floor(__time, 'P1W')", &row),
+            Value::Long(1715558400000)
+        );
+        // 2024-05-01T00:00:00Z
+        assert_eq!(
+            eval("timestamp_floor(__time, 'P1M')", &row),
+            Value::Long(1714521600000)
+        );
+        assert_eq!(
+            eval("TIMESTAMP_FLOOR(__time, 'P1M')", &row),
+            Value::Long(1714521600000)
+        );
+    }
+
+    #[test]
+    fn reports_errors() {
+        let row = HashMap::new();
+        let evaluate = |expression: &str| Expr::parse(expression).unwrap().evaluate(&row);
+        assert!(matches!(evaluate("1 / 0"), Err(EvalError::DivisionByZero)));
+        assert!(matches!(
+            evaluate("frobnicate(1)"),
+            Err(EvalError::UnknownFunction(name)) if name == "frobnicate"
+        ));
+        assert!(matches!(
+            evaluate("lookup(x, 'names')"),
+            Err(EvalError::Unsupported(_))
+        ));
+        assert!(matches!(
+            evaluate("upper('a', 'b')"),
+            Err(EvalError::ArgumentCount { found: 2, .. })
+        ));
+        assert_eq!(evaluate("1.0 / 0").unwrap(), Value::Double(f64::INFINITY));
+    }
+}
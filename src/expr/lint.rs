@@ -0,0 +1,221 @@
+//! Warnings about expressions that parse but that Druid would likely reject.
+
+use std::fmt;
+
+use super::Expr;
+
+/// Druid's built-in expression functions with their minimum and maximum
+/// number of arguments, `None` for any number.
+const FUNCTIONS: &[(&str, usize, Option<usize>)] = &[
+    ("abs", 1, Some(1)),
+    ("acos", 1, Some(1)),
+    ("all", 2, Some(2)),
+    ("any", 2, Some(2)),
+    ("array", 0, None),
+    ("array_append", 2, Some(2)),
+    ("array_concat", 2, Some(2)),
+    ("array_contains", 2, Some(2)),
+    ("array_length", 1, Some(1)),
+    ("array_offset", 2, Some(2)),
+    ("array_offset_of", 2, Some(2)),
+    ("array_ordinal", 2, Some(2)),
+    ("array_ordinal_of", 2, Some(2)),
+    ("array_overlap", 2, Some(2)),
+    ("array_prepend", 2, Some(2)),
+    ("array_set_add", 2, Some(2)),
+    ("array_set_add_all", 2, Some(2)),
+    ("array_slice", 2, Some(3)),
+    ("array_to_string", 2, Some(2)),
+    ("asin", 1, Some(1)),
+    ("atan", 1, Some(1)),
+    ("atan2", 2, Some(2)),
+    ("bitwise_and", 2, Some(2)),
+    ("bitwise_not", 1, Some(1)),
+    ("bitwise_or", 2, Some(2)),
+    ("bitwise_xor", 2, Some(2)),
+    ("cartesian_fold", 3, None),
+    ("cartesian_map", 2, None),
+    ("case_searched", 2, None),
+    ("case_simple", 3, None),
+    ("cast", 2, Some(2)),
+    ("cbrt", 1, Some(1)),
+    ("ceil", 1, Some(1)),
+    ("concat", 0, None),
+    ("cos", 1, Some(1)),
+    ("cosh", 1, Some(1)),
+    ("div", 2, Some(2)),
+    ("exp", 1, Some(1)),
+    ("filter", 2, Some(2)),
+    ("floor", 1, Some(1)),
+    ("fold", 3, Some(3)),
+    ("format", 1, None),
+    ("greatest", 0, None),
+    ("hypot", 2, Some(2)),
+    ("if", 3, Some(3)),
+    ("isnull", 1, Some(1)),
+    ("json_query", 2, Some(2)),
+    ("json_value", 2, Some(3)),
+    ("least", 0, None),
+    ("left", 2, Some(2)),
+    ("like", 2, Some(3)),
+    ("log", 1, Some(1)),
+    ("log10", 1, Some(1)),
+    ("lookup", 2, Some(3)),
+    ("lower", 1, Some(1)),
+    ("lpad", 3, Some(3)),
+    ("ltrim", 1, Some(2)),
+    ("map", 2, Some(2)),
+    ("max", 2, Some(2)),
+    ("min", 2, Some(2)),
+    ("notnull", 1, Some(1)),
+    ("nvl", 2, Some(2)),
+    ("parse_long", 1, Some(2)),
+    ("pow", 2, Some(2)),
+    ("regexp_extract", 2, Some(3)),
+    ("regexp_like", 2, Some(2)),
+    ("regexp_replace", 3, Some(3)),
+    ("repeat", 2, Some(2)),
+    ("replace", 3, Some(3)),
+    ("reverse", 1, Some(1)),
+    ("right", 2, Some(2)),
+    ("round", 1, Some(2)),
+    ("rpad", 3, Some(3)),
+    ("rtrim", 1, Some(2)),
+    ("safe_divide", 2, Some(2)),
+    ("signum", 1, Some(1)),
+    ("sin", 1, Some(1)),
+    ("sinh", 1, Some(1)),
+    ("sqrt", 1, Some(1)),
+    ("string_to_array", 2, Some(2)),
+    ("strlen", 1, Some(1)),
+    ("strpos", 2, Some(3)),
+    ("substring", 3, Some(3)),
+    ("tan", 1, Some(1)),
+    ("tanh", 1, Some(1)),
+    ("timestamp", 1, Some(2)),
+    ("timestamp_ceil", 2, Some(4)),
+    ("timestamp_extract", 2, Some(3)),
+    ("timestamp_floor", 2, Some(4)),
+    ("timestamp_format", 1, Some(3)),
+    ("timestamp_parse", 1, Some(3)),
+    ("timestamp_shift", 3, Some(4)),
+    ("to_json_string", 1, Some(1)),
+    ("trim", 1, Some(2)),
+    ("upper", 1, Some(1)),
+];
+
+/// Functions that take a lambda as their first argument.
+const APPLY_FUNCTIONS: &[&str] = &[
+    "all",
+    "any",
+    "cartesian_fold",
+    "cartesian_map",
+    "filter",
+    "fold",
+    "map",
+];
+
+/// A likely mistake in an expression.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Lint {
+    UnknownFunction(String),
+    ArgumentCount {
+        function: String,
+        found: usize,
+    },
+    /// A lambda anywhere but the first argument of an apply function.
+    MisplacedLambda,
+}
+
+impl fmt::Display for Lint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnknownFunction(name) => write!(f, "unknown function {name}"),
+            Self::ArgumentCount { function, found } => {
+                write!(f, "function {function} can't take {found} arguments")
+            }
+            Self::MisplacedLambda => {
+                write!(
+                    f,
+                    "lambdas are only allowed as the first argument of apply functions"
+                )
+            }
+        }
+    }
+}
+
+/// The argument count range of a built-in function.
+pub(super) fn arity(name: &str) -> Option<(usize, Option<usize>)> {
+    FUNCTIONS
+        .iter()
+        .find(|(function, _, _)| function.eq_ignore_ascii_case(name))
+        .map(|&(_, min, max)| (min, max))
+}
+
+pub(super) fn lint(expr: &Expr, lints: &mut Vec<Lint>) {
+    match expr {
+        Expr::Null | Expr::Long(_) | Expr::Double(_) | Expr::String(_) | Expr::Identifier(_) => {}
+        Expr::Array(items) => items.iter().for_each(|item| lint_argument(item, lints)),
+        Expr::Unary { expr, .. } => lint_argument(expr, lints),
+        Expr::Binary { left, right, .. } => {
+            lint_argument(left, lints);
+            lint_argument(right, lints);
+        }
+        Expr::Function { name, args } => {
+            match arity(name) {
+                None => lints.push(Lint::UnknownFunction(name.clone())),
+                Some((min, max)) if args.len() < min || max.is_some_and(|max| args.len() > max) => {
+                    lints.push(Lint::ArgumentCount {
+                        function: name.clone(),
+                        found: args.len(),
+                    })
+                }
+                Some(_) => {}
+            }
+            for (i, arg) in args.iter().enumerate() {
+                if i == 0
+                    && APPLY_FUNCTIONS
+                        .iter()
+                        .any(|function| function.eq_ignore_ascii_case(name))
+                {
+                    lint(arg, lints);
+                } else {
+                    lint_argument(arg, lints);
+                }
+            }
+        }
+        Expr::Lambda { body, .. } => lint_argument(body, lints),
+    }
+}
+
+/// Lint an expression where a lambda isn't allowed.
+fn lint_argument(expr: &Expr, lints: &mut Vec<Lint>) {
+    if matches!(expr, Expr::Lambda { .. }) {
+        lints.push(Lint::MisplacedLambda);
+    }
+    lint(expr, lints);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Lint;
+    use crate::expr::Expr;
+
+    #[test]
+    fn finds_mistakes() {
+        let lint = |expression: &str| Expr::parse(expression).unwrap().lint();
+        assert!(lint("map((x) -> upper(x), tags)").is_empty());
+        assert!(lint("MAP((x) -> Upper(x), tags) + TIMESTAMP_FLOOR(__time, 'P1D')").is_empty());
+        assert_eq!(
+            lint("upper(frobnicate(x), 'y') + concat((a) -> a)"),
+            vec![
+                Lint::ArgumentCount {
+                    function: "upper".into(),
+                    found: 2
+                },
+                Lint::UnknownFunction("frobnicate".into()),
+                Lint::MisplacedLambda,
+            ]
+        );
+    }
+}
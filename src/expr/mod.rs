@@ -0,0 +1,821 @@
+//! Druid expressions, as used by expression virtual columns, filters and
+//! post-aggregators.
+//!
+//! [`Expr`] is the syntax tree of an expression. It is built with
+//! [`ident`], [`lit`], the arithmetic operators, the comparison methods and
+//! the function helpers of this module, and rendered into the expression
+//! string Druid expects with `to_string`:
+//!
+//! ```
+//! use query_druid::expr::{concat, ident, lit, timestamp_floor};
+//!
+//! let expr = (ident("added") - ident("deleted")) * lit(2);
+//! assert_eq!(expr.to_string(), "(added - deleted) * 2");
+//!
+//! let expr = concat([ident("page name"), lit("'s edits")]);
+//! assert_eq!(expr.to_string(), r#"concat("page name", '\'s edits')"#);
+//!
+//! let hour = timestamp_floor(ident("__time"), "PT1H");
+//! let column = hour.virtual_column("hour");
+//! ```
+//!
+//! Existing expression strings are parsed with [`Expr::parse`], checked for
+//! likely mistakes with [`Expr::lint`] and evaluated locally against a row
+//! with [`Expr::evaluate`]:
+//!
+//! ```
+//! use std::collections::HashMap;
+//!
+//! use query_druid::{components::druid_types::DruidNativeType, expr::{Expr, Value}};
+//!
+//! let expr = Expr::parse("concat(page, '-', added * 2)").unwrap();
+//! assert!(expr.lint().is_empty());
+//! let row = HashMap::from([
+//!     ("page".to_string(), DruidNativeType::string("Rust")),
+//!     ("added".to_string(), DruidNativeType::long(21)),
+//! ]);
+//! assert_eq!(expr.evaluate(&row).unwrap(), Value::String("Rust-42".into()));
+//! ```
+
+use std::{
+    collections::HashMap,
+    fmt,
+    ops::{Add, Div, Mul, Neg, Not, Rem, Sub},
+    str::FromStr,
+};
+
+use crate::components::{
+    druid_types::DruidNativeType, filters::Filter, virtual_columns::VirtaulColumn,
+};
+
+mod eval;
+mod lint;
+mod parser;
+
+pub use self::{
+    eval::{EvalError, Value},
+    lint::Lint,
+    parser::ParseError,
+};
+
+/// A Druid expression.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Null,
+    Long(i64),
+    Double(f64),
+    String(String),
+    /// An array literal like `[1, 2, 3]`.
+    Array(Vec<Expr>),
+    /// A column or lambda parameter.
+    Identifier(String),
+    Unary {
+        op: UnaryOp,
+        expr: Box<Expr>,
+    },
+    Binary {
+        op: BinaryOp,
+        left: Box<Expr>,
+        right: Box<Expr>,
+    },
+    Function {
+        name: String,
+        args: Vec<Expr>,
+    },
+    /// A lambda like `(x, y) -> x + y`, only valid as an argument of the
+    /// apply functions.
+    Lambda {
+        params: Vec<String>,
+        body: Box<Expr>,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnaryOp {
+    Neg,
+    Not,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinaryOp {
+    Pow,
+    Mul,
+    Div,
+    Mod,
+    Add,
+    Sub,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Eq,
+    Ne,
+    And,
+    Or,
+}
+
+impl BinaryOp {
+    pub fn symbol(self) -> &'static str {
+        match self {
+            Self::Pow => "^",
+            Self::Mul => "*",
+            Self::Div => "/",
+            Self::Mod => "%",
+            Self::Add => "+",
+            Self::Sub => "-",
+            Self::Lt => "<",
+            Self::Le => "<=",
+            Self::Gt => ">",
+            Self::Ge => ">=",
+            Self::Eq => "==",
+            Self::Ne => "!=",
+            Self::And => "&&",
+            Self::Or => "||",
+        }
+    }
+
+    /// Binding strength, higher binds tighter.
+    pub(crate) fn precedence(self) -> u8 {
+        match self {
+            Self::Pow => 6,
+            Self::Mul | Self::Div | Self::Mod => 5,
+            Self::Add | Self::Sub => 4,
+            Self::Lt | Self::Le | Self::Gt | Self::Ge | Self::Eq | Self::Ne => 3,
+            // One left-associative level, as in Druid's grammar.
+            Self::And | Self::Or => 1,
+        }
+    }
+
+    fn is_right_associative(self) -> bool {
+        self == Self::Pow
+    }
+
+    fn is_comparison(self) -> bool {
+        self.precedence() <= 3
+    }
+}
+
+/// Precedence of unary operators, tighter than any binary operator.
+const UNARY_PRECEDENCE: u8 = 7;
+
+/// The value types of expressions, as used for the `output_type` of virtual
+/// columns.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExprType {
+    Long,
+    Double,
+    String,
+    LongArray,
+    DoubleArray,
+    StringArray,
+}
+
+impl ExprType {
+    fn array(self) -> Self {
+        match self {
+            Self::Long | Self::LongArray => Self::LongArray,
+            Self::Double | Self::DoubleArray => Self::DoubleArray,
+            Self::String | Self::StringArray => Self::StringArray,
+        }
+    }
+
+    fn element(self) -> Self {
+        match self {
+            Self::Long | Self::LongArray => Self::Long,
+            Self::Double | Self::DoubleArray => Self::Double,
+            Self::String | Self::StringArray => Self::String,
+        }
+    }
+
+    fn is_array(self) -> bool {
+        matches!(
+            self,
+            Self::LongArray | Self::DoubleArray | Self::StringArray
+        )
+    }
+
+    /// The type of a Druid type name like `LONG` or `ARRAY<STRING>`.
+    pub fn from_druid(name: &str) -> Option<Self> {
+        match name.to_ascii_uppercase().as_str() {
+            "LONG" => Some(Self::Long),
+            "DOUBLE" | "FLOAT" => Some(Self::Double),
+            "STRING" => Some(Self::String),
+            "ARRAY<LONG>" => Some(Self::LongArray),
+            "ARRAY<DOUBLE>" | "ARRAY<FLOAT>" => Some(Self::DoubleArray),
+            "ARRAY<STRING>" => Some(Self::StringArray),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for ExprType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Long => "LONG",
+            Self::Double => "DOUBLE",
+            Self::String => "STRING",
+            Self::LongArray => "ARRAY<LONG>",
+            Self::DoubleArray => "ARRAY<DOUBLE>",
+            Self::StringArray => "ARRAY<STRING>",
+        })
+    }
+}
+
+impl Expr {
+    fn binary(op: BinaryOp, left: Expr, right: Expr) -> Self {
+        Self::Binary {
+            op,
+            left: Box::new(left),
+            right: Box::new(right),
+        }
+    }
+
+    pub fn pow<E: Into<Expr>>(self, exponent: E) -> Self {
+        Self::binary(BinaryOp::Pow, self, exponent.into())
+    }
+
+    #[allow(clippy::should_implement_trait)]
+    pub fn eq<E: Into<Expr>>(self, other: E) -> Self {
+        Self::binary(BinaryOp::Eq, self, other.into())
+    }
+
+    #[allow(clippy::should_implement_trait)]
+    pub fn ne<E: Into<Expr>>(self, other: E) -> Self {
+        Self::binary(BinaryOp::Ne, self, other.into())
+    }
+
+    #[allow(clippy::should_implement_trait)]
+    pub fn lt<E: Into<Expr>>(self, other: E) -> Self {
+        Self::binary(BinaryOp::Lt, self, other.into())
+    }
+
+    #[allow(clippy::should_implement_trait)]
+    pub fn le<E: Into<Expr>>(self, other: E) -> Self {
+        Self::binary(BinaryOp::Le, self, other.into())
+    }
+
+    #[allow(clippy::should_implement_trait)]
+    pub fn gt<E: Into<Expr>>(self, other: E) -> Self {
+        Self::binary(BinaryOp::Gt, self, other.into())
+    }
+
+    #[allow(clippy::should_implement_trait)]
+    pub fn ge<E: Into<Expr>>(self, other: E) -> Self {
+        Self::binary(BinaryOp::Ge, self, other.into())
+    }
+
+    pub fn and<E: Into<Expr>>(self, other: E) -> Self {
+        Self::binary(BinaryOp::And, self, other.into())
+    }
+
+    pub fn or<E: Into<Expr>>(self, other: E) -> Self {
+        Self::binary(BinaryOp::Or, self, other.into())
+    }
+
+    /// The type of the expression's values, `None` when it depends on the
+    /// type of a column or can't be told.
+    pub fn output_type(&self) -> Option<ExprType> {
+        self.output_type_with(&HashMap::new())
+    }
+
+    /// Like [`Self::output_type`], with the types of some columns known.
+    pub fn output_type_with(&self, columns: &HashMap<String, ExprType>) -> Option<ExprType> {
+        let infer = |expr: &Expr| expr.output_type_with(columns);
+        match self {
+            Self::Null | Self::Lambda { .. } => None,
+            Self::Long(_) => Some(ExprType::Long),
+            Self::Double(_) => Some(ExprType::Double),
+            Self::String(_) => Some(ExprType::String),
+            Self::Array(items) => items.iter().find_map(infer).map(ExprType::array),
+            Self::Identifier(name) => columns.get(name).copied(),
+            Self::Unary {
+                op: UnaryOp::Not, ..
+            } => Some(ExprType::Long),
+            Self::Unary { expr, .. } => infer(expr),
+            Self::Binary { op, .. } if op.is_comparison() => Some(ExprType::Long),
+            Self::Binary { op, left, right } => match (infer(left)?, infer(right)?) {
+                (ExprType::Long, ExprType::Long) => Some(ExprType::Long),
+                // Adding strings concatenates them.
+                (ExprType::String, _) | (_, ExprType::String) if *op == BinaryOp::Add => {
+                    Some(ExprType::String)
+                }
+                (ExprType::String, _) | (_, ExprType::String) => None,
+                (left, right) if left.is_array() || right.is_array() => None,
+                _ => Some(ExprType::Double),
+            },
+            Self::Function { name, args } => {
+                let arg = |i: usize| args.get(i).and_then(infer);
+                match name.to_ascii_lowercase().as_str() {
+                    "timestamp_floor" | "timestamp_ceil" | "timestamp_shift"
+                    | "timestamp_extract" | "timestamp_parse" | "timestamp" | "strlen"
+                    | "array_length" | "array_contains" | "array_overlap" | "array_offset_of"
+                    | "array_ordinal_of" | "isnull" | "notnull" | "like" | "regexp_like"
+                    | "strpos" | "bitwise_and" | "bitwise_or" | "bitwise_xor" | "round_long" => {
+                        Some(ExprType::Long)
+                    }
+                    "concat" | "format" | "lower" | "upper" | "substring" | "left" | "right"
+                    | "replace" | "trim" | "ltrim" | "rtrim" | "lpad" | "rpad" | "reverse"
+                    | "repeat" | "lookup" | "timestamp_format" | "array_to_string"
+                    | "regexp_extract" | "regexp_replace" | "json_query" | "to_json_string" => {
+                        Some(ExprType::String)
+                    }
+                    "sqrt" | "exp" | "log" | "log10" | "sin" | "cos" | "tan" | "pow" | "div"
+                    | "safe_divide" => Some(ExprType::Double),
+                    "abs" | "floor" | "ceil" | "round" | "greatest" | "least" | "nvl"
+                    | "coalesce" => args.iter().find_map(infer),
+                    "if" => arg(1).or_else(|| arg(2)),
+                    "case_searched" => args
+                        .iter()
+                        .enumerate()
+                        .filter(|(i, _)| i % 2 == 1 || *i == args.len() - 1)
+                        .find_map(|(_, arg)| infer(arg)),
+                    "case_simple" => args
+                        .iter()
+                        .enumerate()
+                        .skip(1)
+                        .filter(|(i, _)| i % 2 == 0 || *i == args.len() - 1)
+                        .find_map(|(_, arg)| infer(arg)),
+                    "cast" => match args.get(1) {
+                        Some(Self::String(name)) => ExprType::from_druid(name),
+                        _ => None,
+                    },
+                    "json_value" => match args.get(2) {
+                        Some(Self::String(name)) => ExprType::from_druid(name),
+                        _ => None,
+                    },
+                    "array" => args.iter().find_map(infer).map(ExprType::array),
+                    "array_offset" | "array_ordinal" => arg(0).map(ExprType::element),
+                    "array_append" | "array_concat" | "array_set_add" | "array_set_add_all"
+                    | "array_slice" => arg(0).map(ExprType::array),
+                    "array_prepend" => arg(1).map(ExprType::array),
+                    "string_to_array" => Some(ExprType::StringArray),
+                    _ => None,
+                }
+            }
+        }
+    }
+
+    /// An expression virtual column computing this expression, with the
+    /// output type set when it can be inferred.
+    pub fn virtual_column(&self, name: &str) -> VirtaulColumn {
+        VirtaulColumn::expression(
+            name.into(),
+            self.to_string(),
+            self.output_type().map(|t| t.to_string()),
+        )
+    }
+
+    /// An expression filter matching the rows this expression is true for.
+    pub fn to_filter(&self) -> Filter {
+        Filter::expression(self.to_string())
+    }
+
+    /// Parse an expression string, like the `expression` of an expression
+    /// virtual column or filter.
+    pub fn parse(expression: &str) -> Result<Self, ParseError> {
+        parser::parse(expression)
+    }
+
+    /// Evaluate the expression against a row, with missing columns as null.
+    ///
+    /// Nulls propagate through operators and most functions, like Druid does
+    /// in its SQL compatible null handling mode. Functions that need cluster
+    /// state, like `lookup`, are [`EvalError::Unsupported`].
+    pub fn evaluate(&self, row: &HashMap<String, DruidNativeType>) -> Result<Value, EvalError> {
+        eval::Scope::new(row).eval(self)
+    }
+
+    /// Likely mistakes, like unknown functions, that Druid would reject at
+    /// query time.
+    pub fn lint(&self) -> Vec<Lint> {
+        let mut lints = Vec::new();
+        lint::lint(self, &mut lints);
+        lints
+    }
+
+    fn precedence(&self) -> u8 {
+        match self {
+            Self::Binary { op, .. } => op.precedence(),
+            Self::Lambda { .. } => 0,
+            Self::Unary { .. } => UNARY_PRECEDENCE,
+            // A negative literal renders with a unary minus.
+            Self::Long(l) if *l < 0 => UNARY_PRECEDENCE,
+            Self::Double(d) if d.is_sign_negative() => UNARY_PRECEDENCE,
+            _ => u8::MAX,
+        }
+    }
+
+    fn fmt_operand(&self, f: &mut fmt::Formatter<'_>, parenthesize: bool) -> fmt::Result {
+        if parenthesize {
+            write!(f, "({self})")
+        } else {
+            write!(f, "{self}")
+        }
+    }
+}
+
+/// Write `name` as an identifier, quoting it when it isn't a plain one.
+pub fn write_identifier<W: fmt::Write>(f: &mut W, name: &str) -> fmt::Result {
+    let plain = name
+        .chars()
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_' || c == '$')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$')
+        && !matches!(
+            name.to_ascii_lowercase().as_str(),
+            "null" | "nan" | "infinity"
+        );
+    if plain {
+        f.write_str(name)
+    } else {
+        write_quoted(f, name, '"')
+    }
+}
+
+/// Write `value` as a string literal.
+pub fn write_string<W: fmt::Write>(f: &mut W, value: &str) -> fmt::Result {
+    write_quoted(f, value, '\'')
+}
+
+fn write_quoted<W: fmt::Write>(f: &mut W, value: &str, quote: char) -> fmt::Result {
+    f.write_char(quote)?;
+    for c in value.chars() {
+        match c {
+            '\\' => f.write_str("\\\\")?,
+            '\n' => f.write_str("\\n")?,
+            '\r' => f.write_str("\\r")?,
+            '\t' => f.write_str("\\t")?,
+            '\'' | '"' if c == quote => write!(f, "\\{c}")?,
+            c if c.is_control() => write!(f, "\\u{:04x}", c as u32)?,
+            c => f.write_char(c)?,
+        }
+    }
+    f.write_char(quote)
+}
+
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Null => f.write_str("null"),
+            Self::Long(l) => write!(f, "{l}"),
+            Self::Double(d) if d.is_nan() => f.write_str("NaN"),
+            Self::Double(d) if d.is_infinite() => {
+                f.write_str(if *d > 0.0 { "Infinity" } else { "-Infinity" })
+            }
+            Self::Double(d) => write!(f, "{d:?}"),
+            Self::String(s) => write_string(f, s),
+            Self::Array(items) => {
+                f.write_str("[")?;
+                write_list(f, items)?;
+                f.write_str("]")
+            }
+            Self::Identifier(name) => write_identifier(f, name),
+            Self::Unary { op, expr } => {
+                f.write_str(match op {
+                    UnaryOp::Neg => "-",
+                    UnaryOp::Not => "!",
+                })?;
+                expr.fmt_operand(f, expr.precedence() < UNARY_PRECEDENCE)
+            }
+            Self::Binary { op, left, right } => {
+                let precedence = op.precedence();
+                let (left_parens, right_parens) = if op.is_right_associative() {
+                    (
+                        left.precedence() <= precedence,
+                        right.precedence() < precedence,
+                    )
+                } else {
+                    // `a && b || c` parses as `(a && b) || c`, but reads as
+                    // if `&&` bound tighter, so mixed logic is parenthesized.
+                    let mixed = matches!(
+                        **left,
+                        Self::Binary { op: inner, .. }
+                            if inner != *op && inner.precedence() == BinaryOp::Or.precedence()
+                    );
+                    (
+                        left.precedence() < precedence || mixed,
+                        right.precedence() <= precedence,
+                    )
+                };
+                left.fmt_operand(f, left_parens)?;
+                write!(f, " {} ", op.symbol())?;
+                right.fmt_operand(f, right_parens)
+            }
+            Self::Function { name, args } => {
+                write!(f, "{name}(")?;
+                write_list(f, args)?;
+                f.write_str(")")
+            }
+            Self::Lambda { params, body } => {
+                f.write_str("(")?;
+                for (i, param) in params.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write_identifier(f, param)?;
+                }
+                write!(f, ") -> {body}")
+            }
+        }
+    }
+}
+
+fn write_list(f: &mut fmt::Formatter<'_>, items: &[Expr]) -> fmt::Result {
+    for (i, item) in items.iter().enumerate() {
+        if i > 0 {
+            f.write_str(", ")?;
+        }
+        write!(f, "{item}")?;
+    }
+    Ok(())
+}
+
+impl FromStr for Expr {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+impl From<i64> for Expr {
+    fn from(value: i64) -> Self {
+        Self::Long(value)
+    }
+}
+
+impl From<i32> for Expr {
+    fn from(value: i32) -> Self {
+        Self::Long(value.into())
+    }
+}
+
+impl From<f64> for Expr {
+    fn from(value: f64) -> Self {
+        Self::Double(value)
+    }
+}
+
+/// Druid has no booleans, they are the longs 1 and 0.
+impl From<bool> for Expr {
+    fn from(value: bool) -> Self {
+        Self::Long(value.into())
+    }
+}
+
+impl From<&str> for Expr {
+    fn from(value: &str) -> Self {
+        Self::String(value.into())
+    }
+}
+
+impl From<String> for Expr {
+    fn from(value: String) -> Self {
+        Self::String(value)
+    }
+}
+
+macro_rules! binary_operator {
+    ($($trait:ident $method:ident $op:ident),*) => {
+        $(
+            impl<E: Into<Expr>> $trait<E> for Expr {
+                type Output = Expr;
+
+                fn $method(self, rhs: E) -> Self::Output {
+                    Expr::binary(BinaryOp::$op, self, rhs.into())
+                }
+            }
+        )*
+    };
+}
+
+binary_operator!(Add add Add, Sub sub Sub, Mul mul Mul, Div div Div, Rem rem Mod);
+
+impl Neg for Expr {
+    type Output = Expr;
+
+    fn neg(self) -> Self::Output {
+        Expr::Unary {
+            op: UnaryOp::Neg,
+            expr: Box::new(self),
+        }
+    }
+}
+
+impl Not for Expr {
+    type Output = Expr;
+
+    fn not(self) -> Self::Output {
+        Expr::Unary {
+            op: UnaryOp::Not,
+            expr: Box::new(self),
+        }
+    }
+}
+
+/// A column.
+pub fn ident(name: &str) -> Expr {
+    Expr::Identifier(name.into())
+}
+
+/// A literal, from a number, string or bool.
+pub fn lit<E: Into<Expr>>(value: E) -> Expr {
+    value.into()
+}
+
+/// A call of any function.
+pub fn function<I: IntoIterator<Item = Expr>>(name: &str, args: I) -> Expr {
+    Expr::Function {
+        name: name.into(),
+        args: args.into_iter().collect(),
+    }
+}
+
+/// A lambda for the apply functions, like `array_apply`.
+pub fn lambda(params: &[&str], body: Expr) -> Expr {
+    Expr::Lambda {
+        params: params.iter().map(|p| p.to_string()).collect(),
+        body: Box::new(body),
+    }
+}
+
+/// `timestamp_floor(expr, period)`, the millisecond timestamp floored to an
+/// ISO 8601 period like `PT1H`.
+pub fn timestamp_floor(expr: Expr, period: &str) -> Expr {
+    function("timestamp_floor", [expr, period.into()])
+}
+
+/// `timestamp_floor` with an origin timestamp and a time zone.
+pub fn timestamp_floor_with(
+    expr: Expr,
+    period: &str,
+    origin: Option<Expr>,
+    timezone: Option<&str>,
+) -> Expr {
+    let mut args = vec![expr, period.into()];
+    if origin.is_some() || timezone.is_some() {
+        args.push(origin.unwrap_or(Expr::Null));
+    }
+    if let Some(timezone) = timezone {
+        args.push(timezone.into());
+    }
+    function("timestamp_floor", args)
+}
+
+pub fn concat<I: IntoIterator<Item = Expr>>(args: I) -> Expr {
+    function("concat", args)
+}
+
+/// `nvl(expr, default)`, `default` when `expr` is null.
+pub fn nvl(expr: Expr, default: Expr) -> Expr {
+    function("nvl", [expr, default])
+}
+
+/// `case_searched(cond1, then1, ..., else)`, the value of the first true
+/// condition.
+pub fn case_searched<I: IntoIterator<Item = (Expr, Expr)>>(
+    cases: I,
+    otherwise: Option<Expr>,
+) -> Expr {
+    let args = cases
+        .into_iter()
+        .flat_map(|(condition, then)| [condition, then])
+        .chain(otherwise);
+    function("case_searched", args)
+}
+
+/// `json_value(expr, path)`, the scalar at a JSONPath of a nested column,
+/// optionally converted to `returning`.
+pub fn json_value(expr: Expr, path: &str, returning: Option<ExprType>) -> Expr {
+    let returning = returning.map(|t| Expr::String(t.to_string()));
+    function(
+        "json_value",
+        [expr, path.into()].into_iter().chain(returning),
+    )
+}
+
+/// `lookup(expr, name)`, the value of `expr` in a registered lookup.
+pub fn lookup(expr: Expr, name: &str) -> Expr {
+    function("lookup", [expr, name.into()])
+}
+
+pub fn array<I: IntoIterator<Item = Expr>>(items: I) -> Expr {
+    Expr::Array(items.into_iter().collect())
+}
+
+pub fn array_length(array: Expr) -> Expr {
+    function("array_length", [array])
+}
+
+/// The element at a 0 based index.
+pub fn array_offset(array: Expr, index: Expr) -> Expr {
+    function("array_offset", [array, index])
+}
+
+/// The element at a 1 based index.
+pub fn array_ordinal(array: Expr, index: Expr) -> Expr {
+    function("array_ordinal", [array, index])
+}
+
+/// Whether `array` contains all of `values`, a scalar or an array.
+pub fn array_contains(array: Expr, values: Expr) -> Expr {
+    function("array_contains", [array, values])
+}
+
+pub fn array_overlap(left: Expr, right: Expr) -> Expr {
+    function("array_overlap", [left, right])
+}
+
+pub fn array_append(array: Expr, value: Expr) -> Expr {
+    function("array_append", [array, value])
+}
+
+pub fn array_concat(left: Expr, right: Expr) -> Expr {
+    function("array_concat", [left, right])
+}
+
+pub fn array_slice(array: Expr, start: Expr, end: Expr) -> Expr {
+    function("array_slice", [array, start, end])
+}
+
+pub fn array_to_string(array: Expr, separator: &str) -> Expr {
+    function("array_to_string", [array, separator.into()])
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::{
+        array, array_length, case_searched, ident, json_value, lambda, lit, nvl, timestamp_floor,
+        ExprType,
+    };
+    use crate::components::virtual_columns::VirtaulColumn;
+
+    #[test]
+    fn renders_expressions() {
+        let expr = ident("a") - (ident("b") - ident("c")) - -ident("d");
+        assert_eq!(expr.to_string(), "a - (b - c) - -d");
+        let expr = (ident("a") + lit(1)).pow(lit(2).pow(3)) / lit(2.5);
+        assert_eq!(expr.to_string(), "(a + 1) ^ 2 ^ 3 / 2.5");
+        let expr = !(ident("a")
+            .gt(1)
+            .and(ident("b").eq("x"))
+            .or(ident("c").le(-1)));
+        assert_eq!(expr.to_string(), "!((a > 1 && b == 'x') || c <= -1)");
+        let expr = ident("a").or(ident("b").and(ident("c")));
+        assert_eq!(expr.to_string(), "a || (b && c)");
+        let expr = ident("a").and(ident("b")).and(ident("c"));
+        assert_eq!(expr.to_string(), "a && b && c");
+        let expr = ident("x") - lit(-1);
+        assert_eq!(expr.to_string(), "x - -1");
+        let expr = case_searched(
+            [(ident("dim").eq("it's"), lit("a\\b"))],
+            Some(ident("2nd col")),
+        );
+        assert_eq!(
+            expr.to_string(),
+            r#"case_searched(dim == 'it\'s', 'a\\b', "2nd col")"#
+        );
+        let expr = super::function(
+            "array_apply",
+            [lambda(&["x"], ident("x") * 2), array([lit(1), lit(2)])],
+        );
+        assert_eq!(expr.to_string(), "array_apply((x) -> x * 2, [1, 2])");
+    }
+
+    #[test]
+    fn infers_types() {
+        let hour = timestamp_floor(ident("__time"), "P1D");
+        assert_eq!(hour.output_type(), Some(ExprType::Long));
+        assert!(matches!(
+            hour.virtual_column("day"),
+            VirtaulColumn::Expression { expression, output_type: Some(t), .. }
+                if expression == "timestamp_floor(__time, 'P1D')" && t == "LONG"
+        ));
+        assert_eq!((ident("added") * 1.5).output_type(), None);
+        let columns = HashMap::from([("added".to_string(), ExprType::Long)]);
+        assert_eq!(
+            (ident("added") * 1.5).output_type_with(&columns),
+            Some(ExprType::Double)
+        );
+        assert_eq!(
+            nvl(ident("page"), lit("none")).output_type(),
+            Some(ExprType::String)
+        );
+        assert_eq!(
+            array_length(array([lit("a")])).output_type(),
+            Some(ExprType::Long)
+        );
+        assert_eq!(
+            json_value(ident("obj"), "$.x", Some(ExprType::Double)).output_type(),
+            Some(ExprType::Double)
+        );
+        assert_eq!(
+            array([lit(1), lit(2)]).output_type(),
+            Some(ExprType::LongArray)
+        );
+    }
+}
@@ -0,0 +1,424 @@
+//! Parsing of Druid expression strings into [`Expr`]s.
+
+use std::{iter::Peekable, str::CharIndices};
+
+use super::{BinaryOp, Expr};
+
+/// An expression string that isn't valid, with the byte offset of the
+/// problem.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("invalid expression at {position}: {message}")]
+pub struct ParseError {
+    pub position: usize,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Long(i64),
+    Double(f64),
+    String(String),
+    Identifier(String),
+    /// A double quoted identifier, never a keyword.
+    QuotedIdentifier(String),
+    Op(BinaryOp),
+    Not,
+    LeftParen,
+    RightParen,
+    LeftBracket,
+    RightBracket,
+    Comma,
+    Arrow,
+    End,
+}
+
+fn error<T>(position: usize, message: impl Into<String>) -> Result<T, ParseError> {
+    Err(ParseError {
+        position,
+        message: message.into(),
+    })
+}
+
+struct Lexer<'s> {
+    source: &'s str,
+    chars: Peekable<CharIndices<'s>>,
+}
+
+impl<'s> Lexer<'s> {
+    fn tokens(source: &'s str) -> Result<Vec<(usize, Token)>, ParseError> {
+        let mut lexer = Lexer {
+            source,
+            chars: source.char_indices().peekable(),
+        };
+        let mut tokens = Vec::new();
+        loop {
+            let (position, token) = lexer.next()?;
+            tokens.push((position, token.clone()));
+            if token == Token::End {
+                return Ok(tokens);
+            }
+        }
+    }
+
+    fn bump_if(&mut self, expected: char) -> bool {
+        self.chars.next_if(|&(_, c)| c == expected).is_some()
+    }
+
+    fn next(&mut self) -> Result<(usize, Token), ParseError> {
+        while self.chars.next_if(|(_, c)| c.is_whitespace()).is_some() {}
+        let Some((start, c)) = self.chars.next() else {
+            return Ok((self.source.len(), Token::End));
+        };
+        let token = match c {
+            '(' => Token::LeftParen,
+            ')' => Token::RightParen,
+            '[' => Token::LeftBracket,
+            ']' => Token::RightBracket,
+            ',' => Token::Comma,
+            '+' => Token::Op(BinaryOp::Add),
+            '-' if self.bump_if('>') => Token::Arrow,
+            '-' => Token::Op(BinaryOp::Sub),
+            '*' => Token::Op(BinaryOp::Mul),
+            '/' => Token::Op(BinaryOp::Div),
+            '%' => Token::Op(BinaryOp::Mod),
+            '^' => Token::Op(BinaryOp::Pow),
+            '<' if self.bump_if('=') => Token::Op(BinaryOp::Le),
+            '<' => Token::Op(BinaryOp::Lt),
+            '>' if self.bump_if('=') => Token::Op(BinaryOp::Ge),
+            '>' => Token::Op(BinaryOp::Gt),
+            '=' if self.bump_if('=') => Token::Op(BinaryOp::Eq),
+            '!' if self.bump_if('=') => Token::Op(BinaryOp::Ne),
+            '!' => Token::Not,
+            '&' if self.bump_if('&') => Token::Op(BinaryOp::And),
+            '|' if self.bump_if('|') => Token::Op(BinaryOp::Or),
+            '\'' => Token::String(self.quoted(start, '\'')?),
+            '"' => Token::QuotedIdentifier(self.quoted(start, '"')?),
+            c if c.is_ascii_digit() || c == '.' => self.number(start)?,
+            c if c.is_ascii_alphabetic() || c == '_' || c == '$' => {
+                let mut end = start + c.len_utf8();
+                while let Some((i, c)) = self
+                    .chars
+                    .next_if(|&(_, c)| c.is_ascii_alphanumeric() || c == '_' || c == '$')
+                {
+                    end = i + c.len_utf8();
+                }
+                match &self.source[start..end] {
+                    "NaN" => Token::Double(f64::NAN),
+                    "Infinity" => Token::Double(f64::INFINITY),
+                    name => Token::Identifier(name.into()),
+                }
+            }
+            c => return error(start, format!("unexpected character {c:?}")),
+        };
+        Ok((start, token))
+    }
+
+    fn number(&mut self, start: usize) -> Result<Token, ParseError> {
+        let mut end = start + 1;
+        let mut double = self.source[start..].starts_with('.');
+        while let Some((i, c)) = self
+            .chars
+            .next_if(|&(_, c)| c.is_ascii_digit() || c == '.' || c == 'e' || c == 'E')
+        {
+            end = i + 1;
+            if matches!(c, '.' | 'e' | 'E') {
+                double = true;
+            }
+            if matches!(c, 'e' | 'E') {
+                if let Some((i, _)) = self.chars.next_if(|&(_, c)| c == '-' || c == '+') {
+                    end = i + 1;
+                }
+            }
+        }
+        let text = &self.source[start..end];
+        let parsed = if double {
+            text.parse().map(Token::Double).ok()
+        } else {
+            text.parse().map(Token::Long).ok()
+        };
+        match parsed {
+            Some(token) => Ok(token),
+            None => error(start, format!("invalid number {text}")),
+        }
+    }
+
+    fn quoted(&mut self, start: usize, quote: char) -> Result<String, ParseError> {
+        let mut value = String::new();
+        loop {
+            match self.chars.next() {
+                None => return error(start, "unterminated quote"),
+                Some((_, c)) if c == quote => return Ok(value),
+                Some((i, '\\')) => match self.chars.next() {
+                    Some((_, 'n')) => value.push('\n'),
+                    Some((_, 'r')) => value.push('\r'),
+                    Some((_, 't')) => value.push('\t'),
+                    Some((_, 'b')) => value.push('\u{8}'),
+                    Some((_, 'f')) => value.push('\u{c}'),
+                    Some((_, 'u')) => {
+                        let digits: String = (0..4)
+                            .filter_map(|_| self.chars.next())
+                            .map(|(_, c)| c)
+                            .collect();
+                        match u32::from_str_radix(&digits, 16)
+                            .ok()
+                            .and_then(char::from_u32)
+                        {
+                            Some(c) if digits.len() == 4 => value.push(c),
+                            _ => return error(i, "invalid unicode escape"),
+                        }
+                    }
+                    Some((_, c @ ('\'' | '"' | '\\' | '/'))) => value.push(c),
+                    _ => return error(i, "invalid escape"),
+                },
+                Some((_, c)) => value.push(c),
+            }
+        }
+    }
+}
+
+struct Parser {
+    tokens: Vec<(usize, Token)>,
+    next: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> &Token {
+        &self.tokens[self.next].1
+    }
+
+    fn peek_at(&self, offset: usize) -> &Token {
+        let index = (self.next + offset).min(self.tokens.len() - 1);
+        &self.tokens[index].1
+    }
+
+    fn position(&self) -> usize {
+        self.tokens[self.next].0
+    }
+
+    fn bump(&mut self) -> Token {
+        let token = self.tokens[self.next].1.clone();
+        if token != Token::End {
+            self.next += 1;
+        }
+        token
+    }
+
+    fn expect(&mut self, expected: Token, what: &str) -> Result<(), ParseError> {
+        if *self.peek() == expected {
+            self.bump();
+            Ok(())
+        } else {
+            error(self.position(), format!("expected {what}"))
+        }
+    }
+
+    fn expr(&mut self, min_precedence: u8) -> Result<Expr, ParseError> {
+        let mut left = self.unary()?;
+        while let Token::Op(op) = *self.peek() {
+            let precedence = op.precedence();
+            if precedence < min_precedence {
+                break;
+            }
+            self.bump();
+            let next = if op.is_right_associative() {
+                precedence
+            } else {
+                precedence + 1
+            };
+            let right = self.expr(next)?;
+            left = Expr::binary(op, left, right);
+        }
+        Ok(left)
+    }
+
+    /// Unary operators bind tighter than any binary operator, so `-a ^ 2` is
+    /// `(-a) ^ 2` as in Druid.
+    fn unary(&mut self) -> Result<Expr, ParseError> {
+        match self.peek() {
+            Token::Op(BinaryOp::Sub) => {
+                self.bump();
+                Ok(match self.unary()? {
+                    Expr::Long(l) => Expr::Long(l.wrapping_neg()),
+                    Expr::Double(d) => Expr::Double(-d),
+                    expr => -expr,
+                })
+            }
+            Token::Not => {
+                self.bump();
+                Ok(!self.unary()?)
+            }
+            _ => self.primary(),
+        }
+    }
+
+    fn primary(&mut self) -> Result<Expr, ParseError> {
+        let position = self.position();
+        match self.bump() {
+            Token::Long(l) => Ok(Expr::Long(l)),
+            Token::Double(d) => Ok(Expr::Double(d)),
+            Token::String(s) => Ok(Expr::String(s)),
+            Token::QuotedIdentifier(name) => Ok(Expr::Identifier(name)),
+            Token::Identifier(name) if name.eq_ignore_ascii_case("null") => Ok(Expr::Null),
+            Token::Identifier(name) if *self.peek() == Token::LeftParen => {
+                self.bump();
+                let args = self.list(Token::RightParen, ")")?;
+                Ok(Expr::Function { name, args })
+            }
+            Token::Identifier(name) => Ok(Expr::Identifier(name)),
+            Token::LeftParen => {
+                let expr = self.expr(0)?;
+                self.expect(Token::RightParen, ")")?;
+                Ok(expr)
+            }
+            Token::LeftBracket => Ok(Expr::Array(self.list(Token::RightBracket, "]")?)),
+            // A typed array literal like `<LONG>[1, 2]`, the type is dropped.
+            Token::Op(BinaryOp::Lt) => {
+                if !matches!(self.bump(), Token::Identifier(_)) {
+                    return error(position, "expected an array type");
+                }
+                // `ARRAY<STRING>` element types lex as `ARRAY < STRING >>`.
+                while matches!(self.peek(), Token::Op(BinaryOp::Lt) | Token::Identifier(_)) {
+                    self.bump();
+                }
+                while *self.peek() == Token::Op(BinaryOp::Gt) {
+                    self.bump();
+                }
+                self.expect(Token::LeftBracket, "[")?;
+                Ok(Expr::Array(self.list(Token::RightBracket, "]")?))
+            }
+            Token::End => error(position, "unexpected end of expression"),
+            _ => error(position, "expected an expression"),
+        }
+    }
+
+    /// Comma separated expressions or lambdas up to `close`.
+    fn list(&mut self, close: Token, what: &str) -> Result<Vec<Expr>, ParseError> {
+        let mut items = Vec::new();
+        if *self.peek() == close {
+            self.bump();
+            return Ok(items);
+        }
+        loop {
+            items.push(match self.lambda_params() {
+                Some(params) => {
+                    let body = self.expr(0)?;
+                    Expr::Lambda {
+                        params,
+                        body: Box::new(body),
+                    }
+                }
+                None => self.expr(0)?,
+            });
+            match self.bump() {
+                Token::Comma => {}
+                token if token == close => return Ok(items),
+                _ => {
+                    return error(
+                        self.tokens[self.next - 1].0,
+                        format!("expected , or {what}"),
+                    )
+                }
+            }
+        }
+    }
+
+    /// Consume the parameters and arrow of a lambda, if one starts here.
+    fn lambda_params(&mut self) -> Option<Vec<String>> {
+        let identifier = |token: &Token| match token {
+            Token::Identifier(name) | Token::QuotedIdentifier(name) => Some(name.clone()),
+            _ => None,
+        };
+        if let (Some(name), Token::Arrow) = (identifier(self.peek()), self.peek_at(1)) {
+            self.next += 2;
+            return Some(vec![name]);
+        }
+        if *self.peek() != Token::LeftParen {
+            return None;
+        }
+        let mut params = Vec::new();
+        let mut offset = 1;
+        if *self.peek_at(offset) != Token::RightParen {
+            loop {
+                params.push(identifier(self.peek_at(offset))?);
+                offset += 1;
+                match self.peek_at(offset) {
+                    Token::Comma => offset += 1,
+                    Token::RightParen => break,
+                    _ => return None,
+                }
+            }
+        }
+        if *self.peek_at(offset + 1) != Token::Arrow {
+            return None;
+        }
+        self.next += offset + 2;
+        Some(params)
+    }
+}
+
+pub(super) fn parse(source: &str) -> Result<Expr, ParseError> {
+    let mut parser = Parser {
+        tokens: Lexer::tokens(source)?,
+        next: 0,
+    };
+    let expr = parser.expr(0)?;
+    match parser.peek() {
+        Token::End => Ok(expr),
+        _ => error(parser.position(), "unexpected token after expression"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse;
+    use crate::expr::{ident, lambda, lit, Expr};
+
+    #[test]
+    fn parses_expressions() {
+        assert_eq!(
+            parse("a - (b - c) - -d").unwrap(),
+            ident("a") - (ident("b") - ident("c")) - -ident("d")
+        );
+        assert_eq!(
+            parse("2 ^ 3 ^ 2 * -1.5e1").unwrap(),
+            lit(2).pow(lit(3).pow(2)) * lit(-15.0)
+        );
+        assert_eq!(
+            parse(r#"!("my col" >= 'it\'s' || x == null) && y"#).unwrap(),
+            (!(ident("my col").ge("it's").or(ident("x").eq(Expr::Null)))).and(ident("y"))
+        );
+        assert_eq!(
+            parse("map((x) -> x + 1, <LONG>[1, 2])").unwrap(),
+            super::super::function(
+                "map",
+                [
+                    lambda(&["x"], ident("x") + 1),
+                    Expr::Array(vec![lit(1), lit(2)])
+                ]
+            )
+        );
+        let source = "case_searched(x > 1, concat(page, '\\n'), nvl(y, 0.5))";
+        assert_eq!(parse(source).unwrap().to_string(), source);
+    }
+
+    #[test]
+    fn parses_logic_left_to_right() {
+        let expr = parse("a || b && c").unwrap();
+        assert_eq!(expr, ident("a").or(ident("b")).and(ident("c")));
+        assert_eq!(parse(&expr.to_string()).unwrap(), expr);
+        assert_eq!(
+            parse("a && b || c && d").unwrap(),
+            ident("a").and(ident("b")).or(ident("c")).and(ident("d"))
+        );
+        let expr = ident("a").or(ident("b").and(ident("c")));
+        assert_eq!(parse(&expr.to_string()).unwrap(), expr);
+    }
+
+    #[test]
+    fn reports_errors() {
+        assert_eq!(parse("a +").unwrap_err().position, 3);
+        assert_eq!(parse("f(a b)").unwrap_err().position, 4);
+        assert_eq!(parse("'open").unwrap_err().position, 0);
+        assert_eq!(parse("a # b").unwrap_err().position, 2);
+    }
+}
@@ -0,0 +1,259 @@
+use serde::{Deserialize, Serialize};
+
+use crate::components::{
+    aggregations::Aggregator, filters::Filter, granularities::Granularity, intervals::Interval,
+};
+
+/// The `dataSchema` section shared by batch tasks and streaming supervisors.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DataSchema {
+    pub data_source: String,
+    pub timestamp_spec: TimestampSpec,
+    pub dimensions_spec: DimensionsSpec,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metrics_spec: Option<Vec<Aggregator>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub granularity_spec: Option<GranularitySpec>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub transform_spec: Option<TransformSpec>,
+}
+
+impl DataSchema {
+    pub fn new(
+        data_source: String,
+        timestamp_spec: TimestampSpec,
+        dimensions_spec: DimensionsSpec,
+    ) -> Self {
+        Self {
+            data_source,
+            timestamp_spec,
+            dimensions_spec,
+            metrics_spec: None,
+            granularity_spec: None,
+            transform_spec: None,
+        }
+    }
+
+    pub fn metrics_spec(mut self, metrics_spec: &[Aggregator]) -> Self {
+        self.metrics_spec = Some(metrics_spec.to_vec());
+        self
+    }
+
+    pub fn granularity_spec(mut self, granularity_spec: GranularitySpec) -> Self {
+        self.granularity_spec = Some(granularity_spec);
+        self
+    }
+
+    pub fn transform_spec(mut self, transform_spec: TransformSpec) -> Self {
+        self.transform_spec = Some(transform_spec);
+        self
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TimestampSpec {
+    pub column: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub format: Option<String>, // "iso", "posix", "millis", "micro", "nano", "auto" or a Joda pattern
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub missing_value: Option<String>,
+}
+
+impl TimestampSpec {
+    pub fn new(column: String) -> Self {
+        Self {
+            column,
+            format: None,
+            missing_value: None,
+        }
+    }
+
+    pub fn format(mut self, format: &str) -> Self {
+        self.format = Some(format.into());
+        self
+    }
+
+    pub fn missing_value(mut self, missing_value: &str) -> Self {
+        self.missing_value = Some(missing_value.into());
+        self
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DimensionsSpec {
+    #[serde(default)]
+    pub dimensions: Vec<DimensionSchema>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dimension_exclusions: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub include_all_dimensions: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub use_schema_discovery: Option<bool>,
+}
+
+impl DimensionsSpec {
+    pub fn new(dimensions: &[DimensionSchema]) -> Self {
+        Self {
+            dimensions: dimensions.to_vec(),
+            ..Default::default()
+        }
+    }
+
+    /// A spec without explicit dimensions, letting Druid discover them.
+    pub fn discover() -> Self {
+        Self {
+            use_schema_discovery: Some(true),
+            ..Default::default()
+        }
+    }
+
+    pub fn dimension_exclusions(mut self, dimension_exclusions: &[String]) -> Self {
+        self.dimension_exclusions = Some(dimension_exclusions.to_vec());
+        self
+    }
+
+    pub fn include_all_dimensions(mut self, include_all_dimensions: bool) -> Self {
+        self.include_all_dimensions = Some(include_all_dimensions);
+        self
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase", rename_all_fields = "camelCase")]
+pub enum DimensionSchema {
+    String {
+        name: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        multi_value_handling: Option<String>, // must be one of SORTED_ARRAY, SORTED_SET or ARRAY
+        #[serde(skip_serializing_if = "Option::is_none")]
+        create_bitmap_index: Option<bool>,
+    },
+    Long {
+        name: String,
+    },
+    Float {
+        name: String,
+    },
+    Double {
+        name: String,
+    },
+    Json {
+        name: String,
+    },
+}
+
+impl DimensionSchema {
+    pub fn string(name: String) -> Self {
+        Self::String {
+            name,
+            multi_value_handling: None,
+            create_bitmap_index: None,
+        }
+    }
+
+    pub fn long(name: String) -> Self {
+        Self::Long { name }
+    }
+
+    pub fn float(name: String) -> Self {
+        Self::Float { name }
+    }
+
+    pub fn double(name: String) -> Self {
+        Self::Double { name }
+    }
+
+    pub fn json(name: String) -> Self {
+        Self::Json { name }
+    }
+
+    pub fn name(&self) -> &str {
+        match self {
+            Self::String { name, .. }
+            | Self::Long { name }
+            | Self::Float { name }
+            | Self::Double { name }
+            | Self::Json { name } => name,
+        }
+    }
+}
+
+/// The `uniform` granularity spec.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GranularitySpec {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub segment_granularity: Option<Granularity>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub query_granularity: Option<Granularity>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rollup: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub intervals: Option<Vec<Interval>>,
+}
+
+impl GranularitySpec {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn segment_granularity(mut self, segment_granularity: Granularity) -> Self {
+        self.segment_granularity = Some(segment_granularity);
+        self
+    }
+
+    pub fn query_granularity(mut self, query_granularity: Granularity) -> Self {
+        self.query_granularity = Some(query_granularity);
+        self
+    }
+
+    pub fn rollup(mut self, rollup: bool) -> Self {
+        self.rollup = Some(rollup);
+        self
+    }
+
+    pub fn intervals(mut self, intervals: &[Interval]) -> Self {
+        self.intervals = Some(intervals.to_vec());
+        self
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransformSpec {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub filter: Option<Filter>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub transforms: Option<Vec<Transform>>,
+}
+
+impl TransformSpec {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn filter(mut self, filter: Filter) -> Self {
+        self.filter = Some(filter);
+        self
+    }
+
+    pub fn transforms(mut self, transforms: &[Transform]) -> Self {
+        self.transforms = Some(transforms.to_vec());
+        self
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase", rename_all_fields = "camelCase")]
+pub enum Transform {
+    Expression { name: String, expression: String },
+}
+
+impl Transform {
+    pub fn expression(name: String, expression: String) -> Self {
+        Self::Expression { name, expression }
+    }
+}
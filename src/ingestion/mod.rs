@@ -0,0 +1,4 @@
+pub mod data_schema;
+pub mod msq;
+pub mod supervisors;
+pub mod tasks;
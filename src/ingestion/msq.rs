@@ -0,0 +1,232 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::tasks::TaskState;
+
+/// Response of `/druid/v2/sql/task`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SqlTaskSubmitted {
+    pub task_id: String,
+    pub state: TaskState,
+}
+
+/// The `multiStageQuery` task report of a multi-stage query task.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MultiStageQueryReport {
+    pub task_id: String,
+    pub payload: MultiStageQueryPayload,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MultiStageQueryPayload {
+    pub status: MsqStatus,
+    #[serde(default)]
+    pub stages: Vec<MsqStage>,
+    /// Counters keyed by stage number, then worker number, then counter name.
+    #[serde(default)]
+    pub counters: HashMap<String, HashMap<String, HashMap<String, MsqCounter>>>,
+    pub results: Option<MsqResults>,
+}
+
+impl MultiStageQueryPayload {
+    /// Rows read or written by each stage, summed over workers and
+    /// partitions, for the given counter like `input0`, `output` or
+    /// `shuffle`.
+    pub fn stage_rows(&self, counter_name: &str) -> HashMap<u32, u64> {
+        let mut rows = HashMap::new();
+        for (stage, workers) in &self.counters {
+            let stage = match stage.parse::<u32>() {
+                Ok(stage) => stage,
+                Err(_) => continue,
+            };
+            for counters in workers.values() {
+                if let Some(MsqCounter::Channel { rows: r, .. }) = counters.get(counter_name) {
+                    *rows.entry(stage).or_insert(0) += r.iter().sum::<u64>();
+                }
+            }
+        }
+        rows
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MsqStatus {
+    pub status: TaskState,
+    pub start_time: Option<DateTime<Utc>>,
+    pub duration_ms: Option<i64>,
+    pub pending_tasks: Option<u64>,
+    pub running_tasks: Option<u64>,
+    pub error_report: Option<MsqErrorReport>,
+    #[serde(default)]
+    pub warnings: Vec<MsqErrorReport>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MsqErrorReport {
+    pub task_id: Option<String>,
+    pub host: Option<String>,
+    pub stage_number: Option<u32>,
+    pub error: MsqError,
+    pub exception_stack_trace: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MsqError {
+    pub error_code: String,
+    pub error_message: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MsqStage {
+    pub stage_number: u32,
+    pub phase: Option<StagePhase>,
+    pub worker_count: Option<u64>,
+    pub partition_count: Option<u64>,
+    pub start_time: Option<DateTime<Utc>>,
+    pub duration: Option<i64>,
+    pub sort: Option<bool>,
+    pub definition: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum StagePhase {
+    New,
+    ReadingInput,
+    PostReading,
+    ResultsReady,
+    Finished,
+    Failed,
+    /// A phase added in a newer Druid version.
+    #[serde(other)]
+    Unknown,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase", rename_all_fields = "camelCase")]
+pub enum MsqCounter {
+    /// Per-partition counts of an input, output or shuffle channel.
+    Channel {
+        #[serde(default)]
+        rows: Vec<u64>,
+        #[serde(default)]
+        bytes: Vec<u64>,
+        #[serde(default)]
+        frames: Vec<u64>,
+        #[serde(default)]
+        files: Vec<u64>,
+        #[serde(default)]
+        total_files: Vec<u64>,
+    },
+    SortProgress {
+        total_merging_levels: Option<i64>,
+        progress_digest: Option<f64>,
+    },
+    /// Number of warnings of each error code.
+    Warnings {
+        #[serde(flatten)]
+        counts: HashMap<String, u64>,
+    },
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MsqResults {
+    pub signature: Vec<MsqColumn>,
+    pub sql_type_names: Option<Vec<String>>,
+    #[serde(default)]
+    pub results: Vec<Vec<serde_json::Value>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MsqColumn {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub column_type: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ingestion::tasks::{TaskReports, TaskState};
+
+    use super::{MsqCounter, StagePhase};
+
+    #[test]
+    fn parses_report() {
+        let body = r#"{
+            "multiStageQuery": {
+                "type": "multiStageQuery",
+                "taskId": "query-abc",
+                "payload": {
+                    "status": {
+                        "status": "RUNNING",
+                        "startTime": "2022-09-14T22:12:09.266Z",
+                        "durationMs": 10000,
+                        "pendingTasks": 0,
+                        "runningTasks": 2,
+                        "warnings": [{
+                            "taskId": "query-abc-worker0_0",
+                            "host": "localhost:8101",
+                            "stageNumber": 0,
+                            "error": {"errorCode": "CannotParseExternalData", "errorMessage": "bad row"}
+                        }]
+                    },
+                    "stages": [
+                        {"stageNumber": 0, "phase": "READING_INPUT", "workerCount": 2, "partitionCount": 1, "sort": true},
+                        {"stageNumber": 1, "phase": "NEW"}
+                    ],
+                    "counters": {
+                        "0": {
+                            "0": {
+                                "input0": {"type": "channel", "rows": [10, 5], "bytes": [100, 50], "files": [1], "totalFiles": [1]},
+                                "sortProgress": {"type": "sortProgress", "totalMergingLevels": 3, "progressDigest": 0.5},
+                                "warnings": {"type": "warnings", "CannotParseExternalData": 1}
+                            },
+                            "1": {
+                                "input0": {"type": "channel", "rows": [7]}
+                            }
+                        }
+                    }
+                }
+            }
+        }"#;
+        let reports: TaskReports = serde_json::from_str(body).unwrap();
+        let payload = reports.multi_stage_query.unwrap().payload;
+        assert_eq!(payload.status.status, TaskState::Running);
+        assert_eq!(
+            payload.status.warnings[0].error.error_code,
+            "CannotParseExternalData"
+        );
+        assert_eq!(payload.stages[0].phase, Some(StagePhase::ReadingInput));
+        let phase: StagePhase = serde_json::from_str(r#""OUT_OF_RETRIES""#).unwrap();
+        assert_eq!(phase, StagePhase::Unknown);
+        assert_eq!(payload.stage_rows("input0")[&0], 22);
+        assert!(matches!(
+            &payload.counters["0"]["0"]["warnings"],
+            MsqCounter::Warnings { counts } if counts["CannotParseExternalData"] == 1
+        ));
+        assert!(matches!(
+            &payload.counters["0"]["0"]["input0"],
+            MsqCounter::Channel { total_files, .. } if total_files == &[1]
+        ));
+        assert!(matches!(
+            payload.counters["0"]["0"]["sortProgress"],
+            MsqCounter::SortProgress {
+                total_merging_levels: Some(3),
+                progress_digest: Some(digest),
+            } if digest == 0.5
+        ));
+    }
+}
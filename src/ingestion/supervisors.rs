@@ -0,0 +1,428 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::{data_schema::DataSchema, tasks::InputFormat};
+use crate::components::druid_types::DruidNativeType;
+
+/// A streaming ingestion supervisor spec.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase", rename_all_fields = "camelCase")]
+pub enum SupervisorSpec {
+    Kafka {
+        spec: KafkaIngestionSpec,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        suspended: Option<bool>,
+    },
+    Kinesis {
+        spec: KinesisIngestionSpec,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        suspended: Option<bool>,
+    },
+}
+
+impl SupervisorSpec {
+    pub fn kafka(
+        data_schema: DataSchema,
+        io_config: KafkaIoConfig,
+        tuning_config: Option<StreamingTuningConfig>,
+    ) -> Self {
+        Self::Kafka {
+            spec: KafkaIngestionSpec {
+                data_schema,
+                io_config,
+                tuning_config: tuning_config.map(|t| t.with_type("kafka")),
+            },
+            suspended: None,
+        }
+    }
+
+    pub fn kinesis(
+        data_schema: DataSchema,
+        io_config: KinesisIoConfig,
+        tuning_config: Option<StreamingTuningConfig>,
+    ) -> Self {
+        Self::Kinesis {
+            spec: KinesisIngestionSpec {
+                data_schema,
+                io_config,
+                tuning_config: tuning_config.map(|t| t.with_type("kinesis")),
+            },
+            suspended: None,
+        }
+    }
+
+    /// Create the supervisor in the suspended state.
+    pub fn suspended(mut self, flag: bool) -> Self {
+        match &mut self {
+            Self::Kafka { suspended, .. } | Self::Kinesis { suspended, .. } => {
+                *suspended = Some(flag)
+            }
+        }
+        self
+    }
+
+    pub fn data_schema(&self) -> &DataSchema {
+        match self {
+            Self::Kafka { spec, .. } => &spec.data_schema,
+            Self::Kinesis { spec, .. } => &spec.data_schema,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KafkaIngestionSpec {
+    pub data_schema: DataSchema,
+    pub io_config: KafkaIoConfig,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tuning_config: Option<StreamingTuningConfig>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KinesisIngestionSpec {
+    pub data_schema: DataSchema,
+    pub io_config: KinesisIoConfig,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tuning_config: Option<StreamingTuningConfig>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KafkaIoConfig {
+    pub topic: String,
+    pub consumer_properties: HashMap<String, String>, // must contain "bootstrap.servers"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub input_format: Option<InputFormat>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub task_count: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub replicas: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub task_duration: Option<String>, // ISO 8601 period
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub use_earliest_offset: Option<bool>,
+}
+
+impl KafkaIoConfig {
+    pub fn new(topic: String, bootstrap_servers: String) -> Self {
+        Self {
+            topic,
+            consumer_properties: HashMap::from([(
+                "bootstrap.servers".to_string(),
+                bootstrap_servers,
+            )]),
+            input_format: None,
+            task_count: None,
+            replicas: None,
+            task_duration: None,
+            use_earliest_offset: None,
+        }
+    }
+
+    pub fn consumer_property(mut self, key: &str, value: &str) -> Self {
+        self.consumer_properties.insert(key.into(), value.into());
+        self
+    }
+
+    pub fn input_format(mut self, input_format: InputFormat) -> Self {
+        self.input_format = Some(input_format);
+        self
+    }
+
+    pub fn task_count(mut self, task_count: u64) -> Self {
+        self.task_count = Some(task_count);
+        self
+    }
+
+    pub fn replicas(mut self, replicas: u64) -> Self {
+        self.replicas = Some(replicas);
+        self
+    }
+
+    pub fn task_duration(mut self, task_duration: &str) -> Self {
+        self.task_duration = Some(task_duration.into());
+        self
+    }
+
+    pub fn use_earliest_offset(mut self, use_earliest_offset: bool) -> Self {
+        self.use_earliest_offset = Some(use_earliest_offset);
+        self
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KinesisIoConfig {
+    pub stream: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub endpoint: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub input_format: Option<InputFormat>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub task_count: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub replicas: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub task_duration: Option<String>, // ISO 8601 period
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub use_earliest_sequence_number: Option<bool>,
+}
+
+impl KinesisIoConfig {
+    pub fn new(stream: String) -> Self {
+        Self {
+            stream,
+            endpoint: None,
+            input_format: None,
+            task_count: None,
+            replicas: None,
+            task_duration: None,
+            use_earliest_sequence_number: None,
+        }
+    }
+
+    pub fn endpoint(mut self, endpoint: &str) -> Self {
+        self.endpoint = Some(endpoint.into());
+        self
+    }
+
+    pub fn input_format(mut self, input_format: InputFormat) -> Self {
+        self.input_format = Some(input_format);
+        self
+    }
+
+    pub fn task_count(mut self, task_count: u64) -> Self {
+        self.task_count = Some(task_count);
+        self
+    }
+
+    pub fn replicas(mut self, replicas: u64) -> Self {
+        self.replicas = Some(replicas);
+        self
+    }
+
+    pub fn task_duration(mut self, task_duration: &str) -> Self {
+        self.task_duration = Some(task_duration.into());
+        self
+    }
+
+    pub fn use_earliest_sequence_number(mut self, use_earliest_sequence_number: bool) -> Self {
+        self.use_earliest_sequence_number = Some(use_earliest_sequence_number);
+        self
+    }
+}
+
+/// Tuning config of a streaming supervisor.
+///
+/// The `type` is filled in by [`SupervisorSpec::kafka`] and
+/// [`SupervisorSpec::kinesis`].
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StreamingTuningConfig {
+    #[serde(rename = "type")]
+    tuning_config_type: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_rows_in_memory: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_rows_per_segment: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub intermediate_persist_period: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reset_offset_automatically: Option<bool>,
+}
+
+impl StreamingTuningConfig {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    fn with_type(mut self, tuning_config_type: &str) -> Self {
+        self.tuning_config_type = tuning_config_type.into();
+        self
+    }
+
+    pub fn max_rows_in_memory(mut self, max_rows_in_memory: u64) -> Self {
+        self.max_rows_in_memory = Some(max_rows_in_memory);
+        self
+    }
+
+    pub fn max_rows_per_segment(mut self, max_rows_per_segment: u64) -> Self {
+        self.max_rows_per_segment = Some(max_rows_per_segment);
+        self
+    }
+
+    pub fn intermediate_persist_period(mut self, intermediate_persist_period: &str) -> Self {
+        self.intermediate_persist_period = Some(intermediate_persist_period.into());
+        self
+    }
+
+    pub fn reset_offset_automatically(mut self, reset_offset_automatically: bool) -> Self {
+        self.reset_offset_automatically = Some(reset_offset_automatically);
+        self
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SupervisorId {
+    pub id: String,
+}
+
+/// Response of `/druid/indexer/v1/supervisor/{id}/status`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SupervisorStatus {
+    pub id: String,
+    pub generation_time: Option<DateTime<Utc>>,
+    pub payload: SupervisorStatusPayload,
+}
+
+/// Kafka supervisors report lag in offsets (`minimum_lag`, `aggregate_lag`)
+/// while Kinesis supervisors report it in milliseconds
+/// (`minimum_lag_millis`, `aggregate_lag_millis`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SupervisorStatusPayload {
+    pub data_source: String,
+    pub stream: Option<String>,
+    pub partitions: Option<u64>,
+    pub replicas: Option<u64>,
+    pub duration_seconds: Option<u64>,
+    #[serde(default)]
+    pub active_tasks: Vec<SupervisorTaskReport>,
+    #[serde(default)]
+    pub publishing_tasks: Vec<SupervisorTaskReport>,
+    pub latest_offsets: Option<HashMap<String, DruidNativeType>>,
+    pub minimum_lag: Option<HashMap<String, i64>>,
+    pub aggregate_lag: Option<i64>,
+    pub minimum_lag_millis: Option<HashMap<String, i64>>,
+    pub aggregate_lag_millis: Option<i64>,
+    pub offsets_last_updated: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub suspended: bool,
+    #[serde(default)]
+    pub healthy: bool,
+    pub state: String,
+    pub detailed_state: Option<String>,
+    #[serde(default)]
+    pub recent_errors: Vec<SupervisorError>,
+}
+
+impl SupervisorStatusPayload {
+    /// Lag of each partition, in offsets for Kafka or milliseconds for
+    /// Kinesis.
+    pub fn partition_lag(&self) -> Option<&HashMap<String, i64>> {
+        self.minimum_lag
+            .as_ref()
+            .or(self.minimum_lag_millis.as_ref())
+    }
+
+    /// Lag summed over all partitions, in offsets for Kafka or milliseconds
+    /// for Kinesis.
+    pub fn total_lag(&self) -> Option<i64> {
+        self.aggregate_lag.or(self.aggregate_lag_millis)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SupervisorTaskReport {
+    pub id: String,
+    pub starting_offsets: Option<HashMap<String, DruidNativeType>>,
+    pub start_time: Option<DateTime<Utc>>,
+    pub remaining_seconds: Option<i64>,
+    #[serde(rename = "type")]
+    pub task_type: Option<String>, // ACTIVE or PUBLISHING
+    pub current_offsets: Option<HashMap<String, DruidNativeType>>,
+    pub lag: Option<HashMap<String, i64>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SupervisorError {
+    pub timestamp: DateTime<Utc>,
+    pub exception_class: Option<String>,
+    pub message: Option<String>,
+    #[serde(default)]
+    pub stream_exception: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{KafkaIoConfig, StreamingTuningConfig, SupervisorSpec, SupervisorStatus};
+    use crate::ingestion::data_schema::{DataSchema, DimensionsSpec, TimestampSpec};
+
+    #[test]
+    fn kafka_spec_shape() {
+        let spec = SupervisorSpec::kafka(
+            DataSchema::new(
+                "wiki".into(),
+                TimestampSpec::new("time".into()),
+                DimensionsSpec::discover(),
+            ),
+            KafkaIoConfig::new("wiki".into(), "localhost:9092".into())
+                .task_count(2)
+                .use_earliest_offset(true),
+            Some(StreamingTuningConfig::new().max_rows_per_segment(5_000_000)),
+        );
+        let json = serde_json::to_value(&spec).unwrap();
+        assert_eq!(json["type"], "kafka");
+        assert_eq!(json["spec"]["tuningConfig"]["type"], "kafka");
+        assert_eq!(
+            json["spec"]["ioConfig"]["consumerProperties"]["bootstrap.servers"],
+            "localhost:9092"
+        );
+        assert_eq!(json["spec"]["ioConfig"]["taskCount"], 2);
+        assert!(json.get("suspended").is_none());
+    }
+
+    #[test]
+    fn parses_status() {
+        let body = r#"{
+            "id": "wiki",
+            "generationTime": "2022-06-01T10:00:00.000Z",
+            "payload": {
+                "dataSource": "wiki",
+                "stream": "wiki",
+                "partitions": 2,
+                "replicas": 1,
+                "durationSeconds": 3600,
+                "activeTasks": [{
+                    "id": "index_kafka_wiki_abc",
+                    "startingOffsets": {"0": 10, "1": 20},
+                    "startTime": "2022-06-01T09:00:00.000Z",
+                    "remainingSeconds": 100,
+                    "type": "ACTIVE",
+                    "currentOffsets": {"0": 15, "1": 25},
+                    "lag": {"0": 5, "1": 2}
+                }],
+                "publishingTasks": [],
+                "latestOffsets": {"0": 20, "1": 27},
+                "minimumLag": {"0": 5, "1": 2},
+                "aggregateLag": 7,
+                "offsetsLastUpdated": "2022-06-01T10:00:00.000Z",
+                "suspended": false,
+                "healthy": true,
+                "state": "RUNNING",
+                "detailedState": "RUNNING",
+                "recentErrors": [{
+                    "timestamp": "2022-06-01T09:30:00.000Z",
+                    "exceptionClass": "org.apache.druid.java.util.common.ISE",
+                    "message": "boom",
+                    "streamException": false
+                }]
+            }
+        }"#;
+        let status: SupervisorStatus = serde_json::from_str(body).unwrap();
+        assert_eq!(status.payload.total_lag(), Some(7));
+        assert_eq!(status.payload.partition_lag().unwrap()["0"], 5);
+        assert_eq!(status.payload.active_tasks.len(), 1);
+        assert_eq!(
+            status.payload.recent_errors[0].message.as_deref(),
+            Some("boom")
+        );
+    }
+}
@@ -0,0 +1,506 @@
+use serde::{Deserialize, Serialize};
+
+use super::data_schema::{
+    DataSchema, DimensionsSpec, GranularitySpec, TimestampSpec, TransformSpec,
+};
+use super::msq::MultiStageQueryReport;
+use crate::components::aggregations::Aggregator;
+
+/// A native batch `index_parallel` ingestion task.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IndexParallelTask {
+    #[serde(rename = "type")]
+    task_type: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    id: Option<String>,
+    spec: IndexParallelSpec,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IndexParallelSpec {
+    pub data_schema: DataSchema,
+    pub io_config: IndexParallelIoConfig,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tuning_config: Option<IndexParallelTuningConfig>,
+}
+
+impl IndexParallelTask {
+    pub fn new(data_schema: DataSchema, io_config: IndexParallelIoConfig) -> Self {
+        Self {
+            task_type: "index_parallel".into(),
+            id: None,
+            spec: IndexParallelSpec {
+                data_schema,
+                io_config,
+                tuning_config: None,
+            },
+        }
+    }
+
+    pub fn id(mut self, id: &str) -> Self {
+        self.id = Some(id.into());
+        self
+    }
+
+    pub fn tuning_config(mut self, tuning_config: IndexParallelTuningConfig) -> Self {
+        self.spec.tuning_config = Some(tuning_config);
+        self
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IndexParallelIoConfig {
+    #[serde(rename = "type")]
+    io_config_type: String,
+    pub input_source: InputSource,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub input_format: Option<InputFormat>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub append_to_existing: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub drop_existing: Option<bool>,
+}
+
+impl IndexParallelIoConfig {
+    pub fn new(input_source: InputSource) -> Self {
+        Self {
+            io_config_type: "index_parallel".into(),
+            input_source,
+            input_format: None,
+            append_to_existing: None,
+            drop_existing: None,
+        }
+    }
+
+    pub fn input_format(mut self, input_format: InputFormat) -> Self {
+        self.input_format = Some(input_format);
+        self
+    }
+
+    pub fn append_to_existing(mut self, append_to_existing: bool) -> Self {
+        self.append_to_existing = Some(append_to_existing);
+        self
+    }
+
+    pub fn drop_existing(mut self, drop_existing: bool) -> Self {
+        self.drop_existing = Some(drop_existing);
+        self
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IndexParallelTuningConfig {
+    #[serde(rename = "type")]
+    tuning_config_type: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_rows_in_memory: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_num_concurrent_sub_tasks: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_parse_exceptions: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub partitions_spec: Option<PartitionsSpec>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub force_guaranteed_rollup: Option<bool>,
+}
+
+impl Default for IndexParallelTuningConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl IndexParallelTuningConfig {
+    pub fn new() -> Self {
+        Self {
+            tuning_config_type: "index_parallel".into(),
+            max_rows_in_memory: None,
+            max_num_concurrent_sub_tasks: None,
+            max_parse_exceptions: None,
+            partitions_spec: None,
+            force_guaranteed_rollup: None,
+        }
+    }
+
+    pub fn max_rows_in_memory(mut self, max_rows_in_memory: u64) -> Self {
+        self.max_rows_in_memory = Some(max_rows_in_memory);
+        self
+    }
+
+    pub fn max_num_concurrent_sub_tasks(mut self, max_num_concurrent_sub_tasks: u64) -> Self {
+        self.max_num_concurrent_sub_tasks = Some(max_num_concurrent_sub_tasks);
+        self
+    }
+
+    pub fn max_parse_exceptions(mut self, max_parse_exceptions: u64) -> Self {
+        self.max_parse_exceptions = Some(max_parse_exceptions);
+        self
+    }
+
+    pub fn partitions_spec(mut self, partitions_spec: PartitionsSpec) -> Self {
+        self.partitions_spec = Some(partitions_spec);
+        self
+    }
+
+    /// Required by hashed and range partitioning.
+    pub fn force_guaranteed_rollup(mut self, force_guaranteed_rollup: bool) -> Self {
+        self.force_guaranteed_rollup = Some(force_guaranteed_rollup);
+        self
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase", rename_all_fields = "camelCase")]
+pub enum PartitionsSpec {
+    Dynamic {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        max_rows_per_segment: Option<u64>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        max_total_rows: Option<u64>,
+    },
+    Hashed {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        target_rows_per_segment: Option<u64>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        num_shards: Option<u64>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        partition_dimensions: Option<Vec<String>>,
+    },
+    Range {
+        partition_dimensions: Vec<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        target_rows_per_segment: Option<u64>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        max_rows_per_segment: Option<u64>,
+    },
+}
+
+impl PartitionsSpec {
+    pub fn dynamic(max_rows_per_segment: Option<u64>, max_total_rows: Option<u64>) -> Self {
+        Self::Dynamic {
+            max_rows_per_segment,
+            max_total_rows,
+        }
+    }
+
+    pub fn hashed(
+        target_rows_per_segment: Option<u64>,
+        num_shards: Option<u64>,
+        partition_dimensions: Option<Vec<String>>,
+    ) -> Self {
+        Self::Hashed {
+            target_rows_per_segment,
+            num_shards,
+            partition_dimensions,
+        }
+    }
+
+    pub fn range(
+        partition_dimensions: Vec<String>,
+        target_rows_per_segment: Option<u64>,
+        max_rows_per_segment: Option<u64>,
+    ) -> Self {
+        Self::Range {
+            partition_dimensions,
+            target_rows_per_segment,
+            max_rows_per_segment,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase", rename_all_fields = "camelCase")]
+pub enum InputSource {
+    Inline { data: String },
+    Local { base_dir: String, filter: String },
+    Http { uris: Vec<String> },
+}
+
+impl InputSource {
+    pub fn inline(data: String) -> Self {
+        Self::Inline { data }
+    }
+
+    pub fn local(base_dir: String, filter: String) -> Self {
+        Self::Local { base_dir, filter }
+    }
+
+    pub fn http(uris: Vec<String>) -> Self {
+        Self::Http { uris }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase", rename_all_fields = "camelCase")]
+pub enum InputFormat {
+    Json,
+    Csv {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        columns: Option<Vec<String>>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        find_columns_from_header: Option<bool>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        skip_header_rows: Option<u64>,
+    },
+}
+
+impl InputFormat {
+    pub fn json() -> Self {
+        Self::Json {}
+    }
+
+    pub fn csv(
+        columns: Option<Vec<String>>,
+        find_columns_from_header: Option<bool>,
+        skip_header_rows: Option<u64>,
+    ) -> Self {
+        Self::Csv {
+            columns,
+            find_columns_from_header,
+            skip_header_rows,
+        }
+    }
+}
+
+/// Whether ingested rows are added to or overwrite existing segments.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IngestionMode {
+    Append,
+    Replace,
+}
+
+/// Schema of rows pushed through [`Client::ingest_rows`](crate::prelude::Client::ingest_rows).
+///
+/// This is a [`DataSchema`] without the datasource name, plus the ingestion
+/// mode, which defaults to [`IngestionMode::Append`].
+#[derive(Debug, Clone)]
+pub struct RowSchema {
+    pub timestamp_spec: TimestampSpec,
+    pub dimensions_spec: DimensionsSpec,
+    pub metrics_spec: Option<Vec<Aggregator>>,
+    pub granularity_spec: Option<GranularitySpec>,
+    pub transform_spec: Option<TransformSpec>,
+    pub mode: IngestionMode,
+}
+
+impl RowSchema {
+    pub fn new(timestamp_spec: TimestampSpec, dimensions_spec: DimensionsSpec) -> Self {
+        Self {
+            timestamp_spec,
+            dimensions_spec,
+            metrics_spec: None,
+            granularity_spec: None,
+            transform_spec: None,
+            mode: IngestionMode::Append,
+        }
+    }
+
+    pub fn metrics_spec(mut self, metrics_spec: &[Aggregator]) -> Self {
+        self.metrics_spec = Some(metrics_spec.to_vec());
+        self
+    }
+
+    pub fn granularity_spec(mut self, granularity_spec: GranularitySpec) -> Self {
+        self.granularity_spec = Some(granularity_spec);
+        self
+    }
+
+    pub fn transform_spec(mut self, transform_spec: TransformSpec) -> Self {
+        self.transform_spec = Some(transform_spec);
+        self
+    }
+
+    pub fn mode(mut self, mode: IngestionMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    pub fn into_data_schema(self, data_source: String) -> DataSchema {
+        DataSchema {
+            data_source,
+            timestamp_spec: self.timestamp_spec,
+            dimensions_spec: self.dimensions_spec,
+            metrics_spec: self.metrics_spec,
+            granularity_spec: self.granularity_spec,
+            transform_spec: self.transform_spec,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskSubmitted {
+    pub task: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskStatusResponse {
+    pub task: String,
+    pub status: TaskStatus,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskStatus {
+    pub id: String,
+    #[serde(rename = "type")]
+    pub task_type: Option<String>,
+    pub created_time: Option<String>,
+    pub status_code: TaskState,
+    pub runner_status_code: Option<String>,
+    pub duration: Option<i64>,
+    pub location: Option<TaskLocation>,
+    pub data_source: Option<String>,
+    pub error_msg: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum TaskState {
+    Waiting,
+    Pending,
+    Running,
+    Success,
+    Failed,
+    /// A state added in a newer Druid version.
+    #[serde(other)]
+    Unknown,
+}
+
+impl TaskState {
+    pub fn is_complete(&self) -> bool {
+        matches!(self, Self::Success | Self::Failed)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskLocation {
+    pub host: Option<String>,
+    pub port: i64,
+    pub tls_port: i64,
+}
+
+/// Reports of a task from `/druid/indexer/v1/task/{id}/reports`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskReports {
+    pub ingestion_stats_and_errors: Option<IngestionStatsAndErrorsReport>,
+    pub multi_stage_query: Option<MultiStageQueryReport>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IngestionStatsAndErrorsReport {
+    pub task_id: String,
+    pub payload: IngestionStatsAndErrors,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IngestionStatsAndErrors {
+    pub ingestion_state: Option<String>,
+    pub row_stats: Option<RowStatsPhases>,
+    pub error_msg: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RowStatsPhases {
+    pub determine_partitions: Option<RowStats>,
+    pub build_segments: Option<RowStats>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct RowStats {
+    pub processed: u64,
+    pub processed_bytes: u64,
+    pub processed_with_error: u64,
+    pub thrown_away: u64,
+    pub unparseable: u64,
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::{
+        IndexParallelTuningConfig, InputSource, PartitionsSpec, TaskReports, TaskState,
+        TaskStatusResponse,
+    };
+
+    #[test]
+    fn parses_completed_report() {
+        let body = r#"{
+            "ingestionStatsAndErrors": {
+                "type": "ingestionStatsAndErrors",
+                "taskId": "index_parallel_wiki_abc",
+                "payload": {
+                    "ingestionState": "COMPLETED",
+                    "unparseableEvents": {},
+                    "rowStats": {
+                        "determinePartitions": {"processed": 0, "processedBytes": 0, "processedWithError": 0, "thrownAway": 0, "unparseable": 0},
+                        "buildSegments": {"processed": 3, "processedBytes": 120, "processedWithError": 0, "thrownAway": 1, "unparseable": 2}
+                    },
+                    "errorMsg": null
+                }
+            }
+        }"#;
+        let reports: TaskReports = serde_json::from_str(body).unwrap();
+        let stats = reports
+            .ingestion_stats_and_errors
+            .and_then(|r| r.payload.row_stats)
+            .and_then(|s| s.build_segments)
+            .unwrap();
+        assert_eq!(stats.processed, 3);
+        assert_eq!(stats.thrown_away, 1);
+        assert_eq!(stats.unparseable, 2);
+    }
+
+    #[test]
+    fn parses_status() {
+        let body = r#"{
+            "task": "index_parallel_wiki_abc",
+            "status": {
+                "id": "index_parallel_wiki_abc",
+                "type": "index_parallel",
+                "createdTime": "2022-06-01T00:00:00.000Z",
+                "queueInsertionTime": "1970-01-01T00:00:00.000Z",
+                "statusCode": "RUNNING",
+                "status": "RUNNING",
+                "runnerStatusCode": "RUNNING",
+                "duration": -1,
+                "location": {"host": "localhost", "port": 8100, "tlsPort": -1},
+                "dataSource": "wiki",
+                "errorMsg": null
+            }
+        }"#;
+        let status: TaskStatusResponse = serde_json::from_str(body).unwrap();
+        assert_eq!(status.status.status_code, TaskState::Running);
+        assert!(!status.status.status_code.is_complete());
+        let state: TaskState = serde_json::from_str(r#""PAUSED""#).unwrap();
+        assert_eq!(state, TaskState::Unknown);
+    }
+
+    #[test]
+    fn serializes_camel_case_fields() {
+        assert_eq!(
+            serde_json::to_value(InputSource::local("/data".into(), "*.json".into())).unwrap(),
+            json!({"type": "local", "baseDir": "/data", "filter": "*.json"})
+        );
+        assert_eq!(
+            serde_json::to_value(PartitionsSpec::dynamic(Some(5_000_000), None)).unwrap(),
+            json!({"type": "dynamic", "maxRowsPerSegment": 5_000_000})
+        );
+        assert_eq!(
+            serde_json::to_value(IndexParallelTuningConfig::default()).unwrap(),
+            json!({"type": "index_parallel"})
+        );
+    }
+}
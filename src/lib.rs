@@ -11,9 +11,21 @@
 //! The library is arranged in two modules, [`components`] and [`queries`].
 //! `components` has all of the Druid native query building blocks like
 //! aggregations and filters in their own modules. `queries` has all types of
-//! queries, including the SQL query, and various structs in the
-//! [`response`](queries::response) module for representing different query and
-//! error responses from Druid.
+//! queries, including the SQL query, a [builder](queries::sql_builder) of
+//! parameterized SQL and the [`sql!`] macro, and various structs in the
+//! [`response`](queries::response) module for representing different query
+//! and error responses from Druid.
+//!
+//! The [`ingestion`] module has the specs and reports of ingestion tasks, and
+//! [`management`] has the configuration of cluster features like lookups and
+//! auto-compaction. The [`system_tables`] module gives typed access to the
+//! `sys` schema, and [`row`] converts result rows into user structs, derived
+//! with the `derive` feature. The `arrow` and `polars` features add the
+//! `record_batch` and `dataframe` modules for converting results into Arrow
+//! record batches and Polars data frames. [`export`] writes results as CSV,
+//! newline-delimited JSON or, with the `parquet` feature, Parquet. [`expr`]
+//! builds, parses and locally evaluates Druid expressions for virtual columns
+//! and expression filters.
 //!
 //! All types are named using rust standard `PascalCase` unlike Druid's
 //! `camelCase`. They are converted appropriately during serialization.
@@ -62,6 +74,18 @@
 //! ```
 
 mod async_impl;
+#[cfg(any(feature = "arrow", feature = "polars"))]
+mod columnar;
 pub mod components;
+#[cfg(feature = "polars")]
+pub mod dataframe;
+pub mod export;
+pub mod expr;
+pub mod ingestion;
+pub mod management;
 pub mod prelude;
 pub mod queries;
+#[cfg(feature = "arrow")]
+pub mod record_batch;
+pub mod row;
+pub mod system_tables;
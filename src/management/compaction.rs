@@ -0,0 +1,348 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    components::{
+        aggregations::Aggregator,
+        filters::Filter,
+        granularities::Granularity,
+        intervals::{validate_period, InvalidPeriod},
+    },
+    ingestion::{
+        data_schema::DimensionSchema,
+        tasks::{IndexParallelTuningConfig, PartitionsSpec},
+    },
+};
+
+/// Returned by [`CompactionConfig::target_rows_per_segment`] when the tuning
+/// config doesn't use hashed or range partitioning.
+#[derive(Debug, Clone, thiserror::Error)]
+#[error("a target row count needs a hashed or range partitions spec")]
+pub struct NoSecondaryPartitioning;
+
+/// Auto-compaction config of a single datasource.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CompactionConfig {
+    pub data_source: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub task_priority: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub input_segment_size_bytes: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_rows_per_segment: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub skip_offset_from_latest: Option<String>, // ISO 8601 period, Druid defaults to "P1D"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tuning_config: Option<IndexParallelTuningConfig>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub granularity_spec: Option<CompactionGranularitySpec>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dimensions_spec: Option<CompactionDimensionsSpec>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub transform_spec: Option<CompactionTransformSpec>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metrics_spec: Option<Vec<Aggregator>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub task_context: Option<serde_json::Map<String, serde_json::Value>>,
+}
+
+impl CompactionConfig {
+    pub fn new(data_source: String) -> Self {
+        Self {
+            data_source,
+            task_priority: None,
+            input_segment_size_bytes: None,
+            max_rows_per_segment: None,
+            skip_offset_from_latest: None,
+            tuning_config: None,
+            granularity_spec: None,
+            dimensions_spec: None,
+            transform_spec: None,
+            metrics_spec: None,
+            task_context: None,
+        }
+    }
+
+    pub fn task_priority(mut self, task_priority: u32) -> Self {
+        self.task_priority = Some(task_priority);
+        self
+    }
+
+    pub fn input_segment_size_bytes(mut self, input_segment_size_bytes: u64) -> Self {
+        self.input_segment_size_bytes = Some(input_segment_size_bytes);
+        self
+    }
+
+    /// Prefer a partitions spec in the tuning config, Druid has deprecated
+    /// this in its favour.
+    pub fn max_rows_per_segment(mut self, max_rows_per_segment: u64) -> Self {
+        self.max_rows_per_segment = Some(max_rows_per_segment);
+        self
+    }
+
+    /// Aim for about `target_rows_per_segment` rows in each compacted
+    /// segment, keeping the rest of the tuning config.
+    ///
+    /// Only hashed and range partitioning take a target, so the tuning config
+    /// must already have such a partitions spec. A range spec's
+    /// `max_rows_per_segment` is cleared, as Druid takes only one of the two.
+    /// Both kinds of partitioning need perfect rollup, so
+    /// `forceGuaranteedRollup` is set as well.
+    pub fn target_rows_per_segment(
+        mut self,
+        target_rows_per_segment: u64,
+    ) -> Result<Self, NoSecondaryPartitioning> {
+        let Some(tuning_config) = self.tuning_config.take() else {
+            return Err(NoSecondaryPartitioning);
+        };
+        let partitions_spec = match tuning_config.partitions_spec.clone() {
+            Some(PartitionsSpec::Hashed {
+                partition_dimensions,
+                ..
+            }) => PartitionsSpec::hashed(Some(target_rows_per_segment), None, partition_dimensions),
+            Some(PartitionsSpec::Range {
+                partition_dimensions,
+                ..
+            }) => PartitionsSpec::range(partition_dimensions, Some(target_rows_per_segment), None),
+            Some(PartitionsSpec::Dynamic { .. }) | None => return Err(NoSecondaryPartitioning),
+        };
+        self.tuning_config = Some(
+            tuning_config
+                .partitions_spec(partitions_spec)
+                .force_guaranteed_rollup(true),
+        );
+        Ok(self)
+    }
+
+    /// An ISO 8601 period like `P1D`, Druid's default.
+    pub fn skip_offset_from_latest(mut self, period: &str) -> Result<Self, InvalidPeriod> {
+        validate_period(period)?;
+        self.skip_offset_from_latest = Some(period.into());
+        Ok(self)
+    }
+
+    pub fn tuning_config(mut self, tuning_config: IndexParallelTuningConfig) -> Self {
+        self.tuning_config = Some(tuning_config);
+        self
+    }
+
+    pub fn granularity_spec(mut self, granularity_spec: CompactionGranularitySpec) -> Self {
+        self.granularity_spec = Some(granularity_spec);
+        self
+    }
+
+    pub fn dimensions_spec(mut self, dimensions: &[DimensionSchema]) -> Self {
+        self.dimensions_spec = Some(CompactionDimensionsSpec {
+            dimensions: dimensions.to_vec(),
+        });
+        self
+    }
+
+    pub fn transform_spec(mut self, filter: Filter) -> Self {
+        self.transform_spec = Some(CompactionTransformSpec {
+            filter: Some(filter),
+        });
+        self
+    }
+
+    pub fn metrics_spec(mut self, metrics_spec: &[Aggregator]) -> Self {
+        self.metrics_spec = Some(metrics_spec.to_vec());
+        self
+    }
+
+    pub fn task_context(
+        mut self,
+        task_context: serde_json::Map<String, serde_json::Value>,
+    ) -> Self {
+        self.task_context = Some(task_context);
+        self
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CompactionGranularitySpec {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub segment_granularity: Option<Granularity>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub query_granularity: Option<Granularity>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rollup: Option<bool>,
+}
+
+impl CompactionGranularitySpec {
+    pub fn segment_granularity(mut self, segment_granularity: Granularity) -> Self {
+        self.segment_granularity = Some(segment_granularity);
+        self
+    }
+
+    pub fn query_granularity(mut self, query_granularity: Granularity) -> Self {
+        self.query_granularity = Some(query_granularity);
+        self
+    }
+
+    pub fn rollup(mut self, rollup: bool) -> Self {
+        self.rollup = Some(rollup);
+        self
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CompactionDimensionsSpec {
+    pub dimensions: Vec<DimensionSchema>,
+}
+
+/// Rows not matching `filter` are dropped during compaction.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CompactionTransformSpec {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub filter: Option<Filter>,
+}
+
+/// Response of `/druid/coordinator/v1/compaction/status`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CompactionStatusResponse {
+    pub latest_status: Vec<CompactionStatus>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CompactionStatus {
+    pub data_source: String,
+    pub schedule_status: CompactionScheduleStatus,
+    #[serde(default)]
+    pub message: Option<String>,
+    pub bytes_awaiting_compaction: u64,
+    pub bytes_compacted: u64,
+    pub bytes_skipped: u64,
+    pub segment_count_awaiting_compaction: u64,
+    pub segment_count_compacted: u64,
+    pub segment_count_skipped: u64,
+    pub interval_count_awaiting_compaction: u64,
+    pub interval_count_compacted: u64,
+    pub interval_count_skipped: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum CompactionScheduleStatus {
+    Running,
+    NotEnabled,
+    AwaitingFirstRun,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CompactionConfig, CompactionGranularitySpec, CompactionStatusResponse};
+    use crate::components::{
+        aggregations::Aggregator, dimension_specs::DimensionSpec, filters::Filter,
+        granularities::Granularity,
+    };
+    use crate::ingestion::tasks::{IndexParallelTuningConfig, PartitionsSpec};
+
+    #[test]
+    fn config_shape() {
+        let config = CompactionConfig::new("wikipedia".into())
+            .skip_offset_from_latest("PT6H")
+            .unwrap()
+            .tuning_config(
+                IndexParallelTuningConfig::new().partitions_spec(PartitionsSpec::hashed(
+                    Some(5_000_000),
+                    None,
+                    None,
+                )),
+            )
+            .granularity_spec(
+                CompactionGranularitySpec::default()
+                    .segment_granularity(Granularity::simple("day".into())),
+            )
+            .transform_spec(Filter::selector(
+                DimensionSpec::default("isRobot".into(), "isRobot".into(), None),
+                "false".into(),
+                None,
+            ))
+            .metrics_spec(&[Aggregator::long_sum("added".into(), "added".into())]);
+        let json = serde_json::to_value(&config).unwrap();
+        assert_eq!(json["dataSource"], "wikipedia");
+        assert_eq!(json["skipOffsetFromLatest"], "PT6H");
+        assert_eq!(
+            json["tuningConfig"]["partitionsSpec"]["targetRowsPerSegment"],
+            5_000_000
+        );
+        assert_eq!(json["granularitySpec"]["segmentGranularity"], "day");
+        assert_eq!(json["transformSpec"]["filter"]["type"], "selector");
+        assert_eq!(json["metricsSpec"][0]["type"], "longSum");
+        assert!(json.get("taskPriority").is_none());
+
+        let parsed: CompactionConfig = serde_json::from_value(json).unwrap();
+        assert_eq!(parsed.skip_offset_from_latest.as_deref(), Some("PT6H"));
+    }
+
+    #[test]
+    fn parses_status() {
+        let body = r#"{"latestStatus": [{
+            "dataSource": "wikipedia", "scheduleStatus": "RUNNING",
+            "bytesAwaitingCompaction": 100, "bytesCompacted": 2000, "bytesSkipped": 0,
+            "segmentCountAwaitingCompaction": 1, "segmentCountCompacted": 20, "segmentCountSkipped": 0,
+            "intervalCountAwaitingCompaction": 1, "intervalCountCompacted": 10, "intervalCountSkipped": 0
+        }]}"#;
+        let status: CompactionStatusResponse = serde_json::from_str(body).unwrap();
+        assert_eq!(status.latest_status[0].bytes_compacted, 2000);
+    }
+
+    #[test]
+    fn typed_row_counts() {
+        let config = CompactionConfig::new("wikipedia".into())
+            .max_rows_per_segment(1_000_000)
+            .tuning_config(
+                IndexParallelTuningConfig::new()
+                    .max_rows_in_memory(10_000)
+                    .partitions_spec(PartitionsSpec::hashed(None, Some(4), None)),
+            )
+            .target_rows_per_segment(3_000_000)
+            .unwrap();
+        let json = serde_json::to_value(&config).unwrap();
+        assert_eq!(json["maxRowsPerSegment"], 1_000_000);
+        assert_eq!(json["tuningConfig"]["maxRowsInMemory"], 10_000);
+        assert_eq!(json["tuningConfig"]["forceGuaranteedRollup"], true);
+        assert_eq!(json["tuningConfig"]["partitionsSpec"]["type"], "hashed");
+        assert_eq!(
+            json["tuningConfig"]["partitionsSpec"]["targetRowsPerSegment"],
+            3_000_000
+        );
+        assert!(json["tuningConfig"]["partitionsSpec"]
+            .get("numShards")
+            .is_none());
+
+        let config =
+            CompactionConfig::new("wikipedia".into())
+                .tuning_config(IndexParallelTuningConfig::new().partitions_spec(
+                    PartitionsSpec::range(vec!["page".into()], None, Some(2_000_000)),
+                ))
+                .target_rows_per_segment(3_000_000)
+                .unwrap();
+        let json = serde_json::to_value(&config).unwrap();
+        let spec = &json["tuningConfig"]["partitionsSpec"];
+        assert_eq!(spec["targetRowsPerSegment"], 3_000_000);
+        assert!(spec.get("maxRowsPerSegment").is_none());
+
+        for tuning_config in [
+            None,
+            Some(
+                IndexParallelTuningConfig::new()
+                    .partitions_spec(PartitionsSpec::dynamic(Some(5_000_000), None)),
+            ),
+        ] {
+            let mut config = CompactionConfig::new("wikipedia".into());
+            config.tuning_config = tuning_config;
+            assert!(config.target_rows_per_segment(3_000_000).is_err());
+        }
+
+        assert!(CompactionConfig::new("wikipedia".into())
+            .skip_offset_from_latest("1 day")
+            .is_err());
+    }
+}
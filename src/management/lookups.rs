@@ -0,0 +1,353 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+/// A cluster lookup as stored in the coordinator's lookup config.
+///
+/// The `version` must increase with every update for nodes to pick up the
+/// change.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LookupSpec {
+    pub version: String,
+    pub lookup_extractor_factory: LookupExtractorFactory,
+}
+
+impl LookupSpec {
+    pub fn new(version: String, lookup_extractor_factory: LookupExtractorFactory) -> Self {
+        Self {
+            version,
+            lookup_extractor_factory,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(
+    tag = "type",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub enum LookupExtractorFactory {
+    Map {
+        map: HashMap<String, String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        injective: Option<bool>,
+    },
+    CachedNamespace {
+        extraction_namespace: ExtractionNamespace,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        first_cache_timeout: Option<u64>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        injective: Option<bool>,
+    },
+    Kafka {
+        kafka_topic: String,
+        kafka_properties: HashMap<String, String>, // must contain "bootstrap.servers"
+        #[serde(skip_serializing_if = "Option::is_none")]
+        connect_timeout: Option<u64>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        injective: Option<bool>,
+    },
+}
+
+impl LookupExtractorFactory {
+    pub fn map(map: HashMap<String, String>, injective: Option<bool>) -> Self {
+        Self::Map { map, injective }
+    }
+
+    pub fn cached_namespace(
+        extraction_namespace: ExtractionNamespace,
+        first_cache_timeout: Option<u64>,
+        injective: Option<bool>,
+    ) -> Self {
+        Self::CachedNamespace {
+            extraction_namespace,
+            first_cache_timeout,
+            injective,
+        }
+    }
+
+    pub fn kafka(
+        kafka_topic: String,
+        kafka_properties: HashMap<String, String>,
+        connect_timeout: Option<u64>,
+        injective: Option<bool>,
+    ) -> Self {
+        Self::Kafka {
+            kafka_topic,
+            kafka_properties,
+            connect_timeout,
+            injective,
+        }
+    }
+}
+
+/// Where a `cachedNamespace` lookup loads its data from.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(
+    tag = "type",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub enum ExtractionNamespace {
+    Uri {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        uri: Option<String>, // exactly one of uri and uri_prefix must be set
+        #[serde(skip_serializing_if = "Option::is_none")]
+        uri_prefix: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        file_regex: Option<String>,
+        namespace_parse_spec: NamespaceParseSpec,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        poll_period: Option<String>, // ISO 8601 period
+    },
+    Jdbc {
+        connector_config: JdbcConnectorConfig,
+        table: String,
+        key_column: String,
+        value_column: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        filter: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        ts_column: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        poll_period: Option<String>, // ISO 8601 period
+    },
+}
+
+impl ExtractionNamespace {
+    pub fn uri(uri: String, namespace_parse_spec: NamespaceParseSpec) -> Self {
+        Self::Uri {
+            uri: Some(uri),
+            uri_prefix: None,
+            file_regex: None,
+            namespace_parse_spec,
+            poll_period: None,
+        }
+    }
+
+    pub fn uri_prefix(
+        uri_prefix: String,
+        file_regex: Option<String>,
+        namespace_parse_spec: NamespaceParseSpec,
+    ) -> Self {
+        Self::Uri {
+            uri: None,
+            uri_prefix: Some(uri_prefix),
+            file_regex,
+            namespace_parse_spec,
+            poll_period: None,
+        }
+    }
+
+    pub fn jdbc(
+        connector_config: JdbcConnectorConfig,
+        table: String,
+        key_column: String,
+        value_column: String,
+    ) -> Self {
+        Self::Jdbc {
+            connector_config,
+            table,
+            key_column,
+            value_column,
+            filter: None,
+            ts_column: None,
+            poll_period: None,
+        }
+    }
+
+    pub fn poll_period(mut self, period: &str) -> Self {
+        match &mut self {
+            Self::Uri { poll_period, .. } | Self::Jdbc { poll_period, .. } => {
+                *poll_period = Some(period.into())
+            }
+        }
+        self
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JdbcConnectorConfig {
+    #[serde(rename = "connectURI")]
+    pub connect_uri: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub password: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(
+    tag = "format",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub enum NamespaceParseSpec {
+    Csv {
+        columns: Vec<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        key_column: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        value_column: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        has_header_row: Option<bool>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        skip_header_rows: Option<u64>,
+    },
+    Tsv {
+        columns: Vec<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        key_column: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        value_column: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        delimiter: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        has_header_row: Option<bool>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        skip_header_rows: Option<u64>,
+    },
+    CustomJson {
+        key_field_name: String,
+        value_field_name: String,
+    },
+    SimpleJson,
+}
+
+impl NamespaceParseSpec {
+    pub fn csv(
+        columns: Vec<String>,
+        key_column: Option<String>,
+        value_column: Option<String>,
+    ) -> Self {
+        Self::Csv {
+            columns,
+            key_column,
+            value_column,
+            has_header_row: None,
+            skip_header_rows: None,
+        }
+    }
+
+    pub fn tsv(
+        columns: Vec<String>,
+        key_column: Option<String>,
+        value_column: Option<String>,
+        delimiter: Option<String>,
+    ) -> Self {
+        Self::Tsv {
+            columns,
+            key_column,
+            value_column,
+            delimiter,
+            has_header_row: None,
+            skip_header_rows: None,
+        }
+    }
+
+    pub fn custom_json(key_field_name: String, value_field_name: String) -> Self {
+        Self::CustomJson {
+            key_field_name,
+            value_field_name,
+        }
+    }
+
+    pub fn simple_json() -> Self {
+        Self::SimpleJson {}
+    }
+}
+
+/// Response of `/druid/coordinator/v1/lookups/status/{tier}/{id}`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LookupStatus {
+    pub loaded: bool,
+    #[serde(default)]
+    pub pending_nodes: Vec<String>,
+}
+
+/// Lookups of a single node, from
+/// `/druid/coordinator/v1/lookups/nodeStatus/{tier}`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct NodeLookupsState {
+    pub current: HashMap<String, LookupSpec>,
+    pub to_load: HashMap<String, LookupSpec>,
+    pub to_drop: Vec<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::{
+        ExtractionNamespace, JdbcConnectorConfig, LookupExtractorFactory, LookupSpec,
+        NamespaceParseSpec, NodeLookupsState,
+    };
+
+    #[test]
+    fn cached_namespace_shape() {
+        let spec = LookupSpec::new(
+            "v1".into(),
+            LookupExtractorFactory::cached_namespace(
+                ExtractionNamespace::jdbc(
+                    JdbcConnectorConfig {
+                        connect_uri: "jdbc:postgresql://db/lookups".into(),
+                        user: Some("druid".into()),
+                        password: None,
+                    },
+                    "countries".into(),
+                    "code".into(),
+                    "name".into(),
+                )
+                .poll_period("PT10M"),
+                Some(60_000),
+                None,
+            ),
+        );
+        let json = serde_json::to_value(&spec).unwrap();
+        let factory = &json["lookupExtractorFactory"];
+        assert_eq!(factory["type"], "cachedNamespace");
+        assert_eq!(factory["firstCacheTimeout"], 60_000);
+        let namespace = &factory["extractionNamespace"];
+        assert_eq!(namespace["type"], "jdbc");
+        assert_eq!(namespace["keyColumn"], "code");
+        assert_eq!(namespace["pollPeriod"], "PT10M");
+        assert_eq!(
+            namespace["connectorConfig"]["connectURI"],
+            "jdbc:postgresql://db/lookups"
+        );
+
+        let uri = ExtractionNamespace::uri(
+            "s3://bucket/countries.json".into(),
+            NamespaceParseSpec::custom_json("code".into(), "name".into()),
+        );
+        let json = serde_json::to_value(&uri).unwrap();
+        assert_eq!(json["namespaceParseSpec"]["format"], "customJson");
+        assert_eq!(json["namespaceParseSpec"]["keyFieldName"], "code");
+        assert!(json.get("uriPrefix").is_none());
+    }
+
+    #[test]
+    fn parses_node_status() {
+        let body = r#"{
+            "localhost:8083": {
+                "current": {
+                    "countries": {"version": "v1", "lookupExtractorFactory": {"type": "map", "map": {"US": "United States"}}}
+                },
+                "toLoad": {},
+                "toDrop": ["old"]
+            }
+        }"#;
+        let status: HashMap<String, NodeLookupsState> = serde_json::from_str(body).unwrap();
+        let node = &status["localhost:8083"];
+        assert!(matches!(
+            &node.current["countries"].lookup_extractor_factory,
+            LookupExtractorFactory::Map { map, .. } if map["US"] == "United States"
+        ));
+        assert_eq!(node.to_drop, vec!["old".to_string()]);
+    }
+}
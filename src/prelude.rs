@@ -1,5 +1,10 @@
+pub use crate::async_impl::avatica::{
+    AvaticaConnection, AvaticaResultSet, AvaticaStatement, PreparedStatement,
+};
 pub use crate::async_impl::client::Client;
 pub use crate::async_impl::client::Error;
+pub use crate::async_impl::sql_task::SqlTask;
+pub use crate::async_impl::statement::{Statement, StatementPages};
 
 pub use crate::queries::datasource_metadata::DataSourceMetadata;
 pub use crate::queries::groupby::GroupBy;
@@ -9,8 +14,9 @@ pub use crate::queries::response::{
 };
 pub use crate::queries::scan::Scan;
 pub use crate::queries::search::Search;
-pub use crate::queries::segment_metadata::SegmentMetadata;
+pub use crate::queries::segment_metadata::{AnalysisType, SegmentMetadata};
 pub use crate::queries::sql::Sql;
+pub use crate::queries::sql_result::{ColumnSchema, SqlResultSet, SqlValue};
 pub use crate::queries::time_boundary::TimeBoundary;
 pub use crate::queries::timeseries::Timeseries;
 pub use crate::queries::topn::TopN;
@@ -22,6 +28,7 @@ pub use crate::components::data_sources::DataSource;
 pub use crate::components::dimension_specs::DimensionSpec;
 pub use crate::components::druid_types::{DruidNativeType, DruidSqlType};
 pub use crate::components::extraction_functions::ExtractionFunction;
+pub use crate::components::filter_dsl::col;
 pub use crate::components::filters::{Bound, Filter};
 pub use crate::components::granularities::Granularity;
 pub use crate::components::having_specs::HavingSpec;
@@ -0,0 +1,305 @@
+use chrono::Datelike;
+use serde::{Deserialize, Serialize};
+
+use crate::components::druid_types::DruidSqlType;
+
+/// A request of the Avatica JSON protocol.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(
+    tag = "request",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub enum AvaticaRequest {
+    OpenConnection {
+        connection_id: String,
+    },
+    CloseConnection {
+        connection_id: String,
+    },
+    CreateStatement {
+        connection_id: String,
+    },
+    CloseStatement {
+        connection_id: String,
+        statement_id: u32,
+    },
+    Prepare {
+        connection_id: String,
+        sql: String,
+        max_row_count: i64,
+    },
+    Execute {
+        statement_handle: StatementHandle,
+        parameter_values: Vec<TypedValue>,
+        first_frame_max_size: i32,
+    },
+    PrepareAndExecute {
+        connection_id: String,
+        statement_id: u32,
+        sql: String,
+        max_rows_total: i64,
+        max_rows_in_first_frame: i32,
+    },
+    Fetch {
+        connection_id: String,
+        statement_id: u32,
+        offset: u64,
+        frame_max_size: i32,
+    },
+}
+
+impl AvaticaRequest {
+    pub fn connection_id(&self) -> &str {
+        match self {
+            Self::OpenConnection { connection_id }
+            | Self::CloseConnection { connection_id }
+            | Self::CreateStatement { connection_id }
+            | Self::CloseStatement { connection_id, .. }
+            | Self::Prepare { connection_id, .. }
+            | Self::PrepareAndExecute { connection_id, .. }
+            | Self::Fetch { connection_id, .. } => connection_id,
+            Self::Execute {
+                statement_handle, ..
+            } => &statement_handle.connection_id,
+        }
+    }
+}
+
+/// A response of the Avatica JSON protocol.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(
+    tag = "response",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub enum AvaticaResponse {
+    OpenConnection,
+    CloseConnection,
+    CreateStatement {
+        connection_id: String,
+        statement_id: u32,
+    },
+    CloseStatement,
+    Prepare {
+        statement: StatementHandle,
+    },
+    ExecuteResults {
+        #[serde(default)]
+        missing_statement: bool,
+        #[serde(default)]
+        results: Vec<ResultSetResponse>,
+    },
+    Fetch {
+        frame: Frame,
+        #[serde(default)]
+        missing_statement: bool,
+    },
+    Error(AvaticaError),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, thiserror::Error)]
+#[serde(rename_all = "camelCase")]
+#[error("avatica error {error_code} ({sql_state}): {error_message}")]
+pub struct AvaticaError {
+    #[serde(default)]
+    pub error_message: String,
+    #[serde(default)]
+    pub error_code: i32,
+    #[serde(default)]
+    pub sql_state: String,
+    pub severity: Option<String>,
+    #[serde(default)]
+    pub exceptions: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StatementHandle {
+    pub connection_id: String,
+    pub id: u32,
+    pub signature: Option<Signature>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Signature {
+    #[serde(default)]
+    pub columns: Vec<ColumnMetaData>,
+    pub sql: Option<String>,
+    #[serde(default)]
+    pub parameters: Vec<AvaticaParameter>,
+    pub cursor_factory: Option<serde_json::Value>,
+    pub statement_type: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ColumnMetaData {
+    pub ordinal: u32,
+    pub column_name: String,
+    pub label: Option<String>,
+    pub nullable: Option<i32>, // 0: no nulls, 1: nullable, 2: unknown
+    pub signed: Option<bool>,
+    pub precision: Option<i32>,
+    pub scale: Option<i32>,
+    pub schema_name: Option<String>,
+    pub table_name: Option<String>,
+    #[serde(rename = "type")]
+    pub column_type: AvaticaType,
+    pub column_class_name: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AvaticaType {
+    pub id: i32, // java.sql.Types constant
+    pub name: String,
+    pub rep: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AvaticaParameter {
+    pub signed: Option<bool>,
+    pub precision: Option<i32>,
+    pub scale: Option<i32>,
+    pub parameter_type: i32, // java.sql.Types constant
+    pub type_name: String,
+    pub class_name: Option<String>,
+    pub name: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResultSetResponse {
+    pub connection_id: String,
+    pub statement_id: u32,
+    #[serde(default)]
+    pub own_statement: bool,
+    pub signature: Option<Signature>,
+    pub first_frame: Option<Frame>,
+    pub update_count: Option<i64>,
+}
+
+/// A batch of rows starting at `offset`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Frame {
+    pub offset: u64,
+    pub done: bool,
+    #[serde(default)]
+    pub rows: Vec<Vec<serde_json::Value>>,
+}
+
+/// A parameter value in Avatica's `{"type": rep, "value": ...}` form.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TypedValue {
+    #[serde(rename = "type")]
+    pub rep: String,
+    pub value: serde_json::Value,
+}
+
+impl From<&DruidSqlType> for TypedValue {
+    fn from(value: &DruidSqlType) -> Self {
+        let (rep, value) = match value {
+            DruidSqlType::Tinyint(v) => ("BYTE", (*v).into()),
+            DruidSqlType::Smallint(v) => ("SHORT", (*v).into()),
+            DruidSqlType::Integer(v) => ("INTEGER", (*v).into()),
+            DruidSqlType::Bigint(v) => ("LONG", (*v).into()),
+            DruidSqlType::Boolean(v) => ("BOOLEAN", (*v).into()),
+            DruidSqlType::Float(v) => ("FLOAT", (*v).into()),
+            DruidSqlType::Double(v) | DruidSqlType::Decimal(v) | DruidSqlType::Real(v) => {
+                ("DOUBLE", (*v).into())
+            }
+            DruidSqlType::Timestamp(v) => ("JAVA_SQL_TIMESTAMP", v.timestamp_millis().into()),
+            DruidSqlType::Date(v) => (
+                "JAVA_SQL_DATE",
+                i64::from(v.date_naive().num_days_from_ce() - EPOCH_DAYS_FROM_CE).into(),
+            ),
+            DruidSqlType::Char(v) => ("STRING", v.to_string().into()),
+            DruidSqlType::Varchar(v) => ("STRING", v.clone().into()),
+        };
+        Self {
+            rep: rep.to_string(),
+            value,
+        }
+    }
+}
+
+/// Days from 0001-01-01 to 1970-01-01.
+const EPOCH_DAYS_FROM_CE: i32 = 719_163;
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+
+    use super::{AvaticaRequest, AvaticaResponse, TypedValue};
+    use crate::components::druid_types::DruidSqlType;
+
+    #[test]
+    fn request_shape() {
+        let req = AvaticaRequest::Fetch {
+            connection_id: "c".into(),
+            statement_id: 1,
+            offset: 100,
+            frame_max_size: 50,
+        };
+        let json = serde_json::to_value(&req).unwrap();
+        assert_eq!(json["request"], "fetch");
+        assert_eq!(json["connectionId"], "c");
+        assert_eq!(json["frameMaxSize"], 50);
+    }
+
+    #[test]
+    fn typed_values() {
+        let date = Utc.with_ymd_and_hms(1970, 1, 3, 0, 0, 0).unwrap();
+        let v = TypedValue::from(&DruidSqlType::date(date));
+        assert_eq!(v.rep, "JAVA_SQL_DATE");
+        assert_eq!(v.value, 2);
+        let v = TypedValue::from(&DruidSqlType::timestamp(date));
+        assert_eq!(v.value, 172_800_000i64);
+        let v = TypedValue::from(&DruidSqlType::varchar("x"));
+        assert_eq!(v.rep, "STRING");
+    }
+
+    #[test]
+    fn parses_responses() {
+        let body = r#"{
+            "response": "prepare",
+            "statement": {
+                "connectionId": "c",
+                "id": 3,
+                "signature": {
+                    "columns": [{
+                        "ordinal": 0, "autoIncrement": false, "caseSensitive": false, "searchable": true,
+                        "currency": false, "nullable": 1, "signed": false, "displaySize": 10,
+                        "label": "page", "columnName": "page", "schemaName": "", "precision": 0, "scale": 0,
+                        "tableName": "", "catalogName": "",
+                        "type": {"type": "scalar", "id": 12, "name": "VARCHAR", "rep": "STRING"},
+                        "readOnly": false, "writable": false, "definitelyWritable": false,
+                        "columnClassName": "java.lang.String"
+                    }],
+                    "sql": "SELECT page FROM wikipedia WHERE added > ?",
+                    "parameters": [{"signed": true, "precision": 0, "scale": 0, "parameterType": -5, "typeName": "BIGINT", "className": "java.lang.Long", "name": "?1"}],
+                    "cursorFactory": {"style": "LIST", "clazz": null, "fieldNames": null},
+                    "statementType": "SELECT"
+                }
+            },
+            "rpcMetadata": {"response": "rpcMetadata", "serverAddress": "localhost:8082"}
+        }"#;
+        match serde_json::from_str(body).unwrap() {
+            AvaticaResponse::Prepare { statement } => {
+                let signature = statement.signature.unwrap();
+                assert_eq!(signature.columns[0].column_type.name, "VARCHAR");
+                assert_eq!(signature.parameters[0].type_name, "BIGINT");
+            }
+            r => panic!("unexpected response {r:?}"),
+        }
+        let body = r#"{"response": "error", "exceptions": [], "errorMessage": "boom", "errorCode": -1, "sqlState": "00000", "severity": "ERROR"}"#;
+        assert!(matches!(
+            serde_json::from_str(body).unwrap(),
+            AvaticaResponse::Error(e) if e.error_message == "boom"
+        ));
+    }
+}
@@ -0,0 +1,184 @@
+use serde::{Deserialize, Serialize};
+
+use super::Query;
+use crate::components::granularities::Granularity;
+
+/// The plan of a SQL query from `EXPLAIN PLAN FOR`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExplainResult {
+    /// The native queries the SQL query translates to.
+    pub plan: Vec<ExplainedQuery>,
+    pub resources: Vec<ExplainResource>,
+    pub attributes: Option<ExplainAttributes>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExplainedQuery {
+    pub query: Query,
+    #[serde(default)]
+    pub signature: Vec<ColumnSignature>,
+    #[serde(default)]
+    pub column_mappings: Vec<ColumnMapping>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ColumnSignature {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub column_type: Option<String>,
+}
+
+/// Maps a column of the native query to a column of the SQL result.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ColumnMapping {
+    pub query_column: String,
+    pub output_column: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExplainResource {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub resource_type: String, // DATASOURCE, EXTERNAL, ...
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExplainAttributes {
+    pub statement_type: String, // SELECT, INSERT or REPLACE
+    pub target_data_source: Option<String>,
+    pub partitioned_by: Option<Granularity>,
+    pub clustered_by: Option<Vec<String>>,
+    pub replace_time_chunks: Option<String>,
+}
+
+/// A row of the `EXPLAIN PLAN FOR` result, where every column holds JSON as
+/// a string.
+#[derive(Debug, Clone, Deserialize)]
+pub(crate) struct ExplainRow {
+    #[serde(rename = "PLAN")]
+    plan: String,
+    #[serde(rename = "RESOURCES")]
+    resources: Option<String>,
+    #[serde(rename = "ATTRIBUTES")]
+    attributes: Option<String>,
+}
+
+impl TryFrom<ExplainRow> for ExplainResult {
+    type Error = serde_json::Error;
+
+    fn try_from(row: ExplainRow) -> Result<Self, Self::Error> {
+        Ok(Self {
+            plan: serde_json::from_str(&row.plan)?,
+            resources: match row.resources {
+                Some(r) => serde_json::from_str(&r)?,
+                None => Vec::new(),
+            },
+            attributes: row
+                .attributes
+                .map(|a| serde_json::from_str(&a))
+                .transpose()?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ExplainResult, ExplainRow};
+    use crate::{
+        components::{data_sources::DataSource, limit_specs::LimitSpec},
+        queries::Query,
+    };
+
+    fn explain(plan: &str) -> ExplainResult {
+        let row = ExplainRow {
+            plan: plan.to_string(),
+            resources: Some(r#"[{"name":"wikipedia","type":"DATASOURCE"}]"#.to_string()),
+            attributes: Some(r#"{"statementType":"SELECT"}"#.to_string()),
+        };
+        ExplainResult::try_from(row).unwrap()
+    }
+
+    #[test]
+    fn parses_groupby_plan() {
+        let result = explain(
+            r#"[{
+                "query": {
+                    "queryType": "groupBy",
+                    "dataSource": {"type": "table", "name": "wikipedia"},
+                    "intervals": {"type": "intervals", "intervals": ["-146136543-09-08T08:23:32.096Z/146140482-04-24T15:36:27.903Z"]},
+                    "filter": {"type": "bound", "dimension": "added", "lower": "10", "upper": null, "lowerStrict": true, "upperStrict": false, "extractionFn": null, "ordering": {"type": "numeric"}},
+                    "granularity": {"type": "all"},
+                    "dimensions": [{"type": "default", "dimension": "page", "outputName": "d0", "outputType": "STRING"}],
+                    "aggregations": [{"type": "longSum", "name": "a0", "fieldName": "added"}],
+                    "postAggregations": [],
+                    "limitSpec": {"type": "default", "columns": [{"dimension": "a0", "direction": "descending", "dimensionOrder": {"type": "numeric"}}], "limit": 10},
+                    "context": {"sqlQueryId": "abc", "useNativeQueryExplain": true},
+                    "descending": false
+                },
+                "signature": [{"name": "d0", "type": "STRING"}, {"name": "a0", "type": "LONG"}],
+                "columnMappings": [{"queryColumn": "d0", "outputColumn": "page"}, {"queryColumn": "a0", "outputColumn": "EXPR$1"}]
+            }]"#,
+        );
+        assert_eq!(result.resources[0].name, "wikipedia");
+        assert_eq!(result.attributes.unwrap().statement_type, "SELECT");
+        let explained = &result.plan[0];
+        assert_eq!(explained.column_mappings[1].output_column, "EXPR$1");
+        let json = match &explained.query {
+            Query::GroupBy(q) => serde_json::to_value(q).unwrap(),
+            q => panic!("unexpected query {q:?}"),
+        };
+        assert_eq!(json["queryType"], "groupBy");
+        assert_eq!(json["granularity"], "all");
+        assert_eq!(json["aggregations"][0]["fieldName"], "added");
+        assert_eq!(json["filter"]["lowerStrict"], true);
+        assert_eq!(json["limitSpec"]["columns"][0]["dimensionOrder"], "numeric");
+    }
+
+    #[test]
+    fn parses_nested_query_plan() {
+        let result = explain(
+            r#"[{
+                "query": {
+                    "queryType": "timeseries",
+                    "dataSource": {
+                        "type": "query",
+                        "query": {
+                            "queryType": "scan",
+                            "dataSource": {"type": "table", "name": "wikipedia"},
+                            "intervals": {"type": "intervals", "intervals": ["2015-09-12/2015-09-13"]},
+                            "resultFormat": "compactedList",
+                            "limit": 10,
+                            "columns": ["__time", "page"],
+                            "legacy": false,
+                            "granularity": {"type": "all"}
+                        }
+                    },
+                    "intervals": {"type": "intervals", "intervals": ["2015-09-12/2015-09-13"]},
+                    "granularity": {"type": "period", "period": "PT1H", "timeZone": "UTC", "origin": null},
+                    "aggregations": [{"type": "count", "name": "a0"}]
+                },
+                "signature": [{"name": "a0", "type": "LONG"}]
+            }]"#,
+        );
+        let json = serde_json::to_value(&result.plan[0].query).unwrap();
+        assert_eq!(json["queryType"], "timeseries");
+        assert_eq!(json["dataSource"]["type"], "query");
+        assert_eq!(json["dataSource"]["query"]["queryType"], "scan");
+        assert_eq!(json["granularity"]["period"], "PT1H");
+        assert!(matches!(
+            serde_json::from_value::<DataSource>(json["dataSource"].clone()),
+            Ok(DataSource::Query(_))
+        ));
+    }
+
+    #[test]
+    fn parses_noop_limit_spec() {
+        let spec: LimitSpec = serde_json::from_str(r#"{"type": "NoopLimitSpec"}"#).unwrap();
+        assert!(matches!(spec, LimitSpec::Noop));
+    }
+}
@@ -1,10 +1,22 @@
 use serde::{Deserialize, Serialize};
 
 use crate::components::{
-    aggregations::Aggregator, context::Context, data_sources::DataSource,
-    dimension_specs::DimensionSpec, filters::Filter, granularities::Granularity,
-    having_specs::HavingSpec, intervals::Interval, limit_specs::LimitSpec,
-    post_aggregations::PostAggregator, virtual_columns::VirtaulColumn,
+    aggregations::Aggregator,
+    context::Context,
+    data_sources::DataSource,
+    dimension_specs::DimensionSpec,
+    filters::Filter,
+    granularities::Granularity,
+    having_specs::HavingSpec,
+    intervals::{deserialize_intervals, Interval},
+    limit_specs::LimitSpec,
+    post_aggregations::PostAggregator,
+    virtual_columns::VirtaulColumn,
+};
+
+use super::{
+    translation::{Select, TranslationError, Translator},
+    validation::{ValidationError, Validator},
 };
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -12,6 +24,7 @@ use crate::components::{
 pub struct GroupBy {
     query_type: String,
     data_source: DataSource,
+    #[serde(deserialize_with = "deserialize_intervals")]
     intervals: Vec<Interval>,
     granularity: Granularity,
     dimensions: Vec<DimensionSpec>,
@@ -88,4 +101,52 @@ impl GroupBy {
         self.context = Some(context);
         self
     }
+
+    /// Check the query for problems Druid would reject it for, see
+    /// [`Query::validate`](super::Query::validate).
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        let mut validator = Validator::default();
+        validator.intervals(&self.intervals);
+        validator.dimensions("dimensions", &self.dimensions);
+        validator.aggregations(self.aggregations.as_deref());
+        validator.post_aggregations(self.post_aggregations.as_deref());
+        if let Some(having) = &self.having {
+            validator.having("having", having);
+        }
+        if let Some(limit_spec) = &self.limit_spec {
+            validator.limit_spec("limit_spec", limit_spec);
+        }
+        if let Some(subtotals_spec) = &self.subtotals_spec {
+            validator.subtotals_spec(subtotals_spec);
+        }
+        validator.finish()
+    }
+
+    /// Translate the query into Druid SQL, with the subtotals spec as
+    /// `GROUPING SETS` and the limit spec as `ORDER BY`, `LIMIT` and
+    /// `OFFSET`.
+    pub fn to_sql(&self) -> Result<String, TranslationError> {
+        let mut translator = Translator::new(self.virtual_columns.as_deref())?;
+        let mut select = Select {
+            from: translator.data_source(&self.data_source)?,
+            conditions: translator.conditions(&self.intervals, self.filter.as_ref())?,
+            ..Select::default()
+        };
+        let time = translator.group_by_time(&self.granularity, &mut select)?;
+        for dimension in &self.dimensions {
+            translator.group_by_dimension(dimension, &mut select)?;
+        }
+        translator.aggregations(self.aggregations.as_deref(), &mut select)?;
+        translator.post_aggregations(self.post_aggregations.as_deref(), &mut select)?;
+        if let Some(subtotals_spec) = &self.subtotals_spec {
+            select.group_by = vec![translator.grouping_sets(subtotals_spec, time.as_deref())];
+        }
+        if let Some(having) = &self.having {
+            select.having = Some(translator.having(having)?);
+        }
+        if let Some(limit_spec) = &self.limit_spec {
+            translator.limit_spec(limit_spec, &mut select)?;
+        }
+        Ok(select.to_string())
+    }
 }
@@ -0,0 +1,102 @@
+use serde::{Deserialize, Serialize};
+
+use crate::components::aggregations::Aggregator;
+
+use super::response::ColumnAnalysis;
+
+/// A table listed in `INFORMATION_SCHEMA.TABLES`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TableInfo {
+    #[serde(rename = "TABLE_SCHEMA")]
+    pub schema: String,
+    #[serde(rename = "TABLE_NAME")]
+    pub name: String,
+    #[serde(rename = "TABLE_TYPE")]
+    pub table_type: String,
+}
+
+/// A column listed in `INFORMATION_SCHEMA.COLUMNS`, optionally with the
+/// native `segmentMetadata` analysis of the column.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ColumnDescriptor {
+    #[serde(rename = "COLUMN_NAME")]
+    pub name: String,
+    #[serde(rename = "DATA_TYPE")]
+    pub sql_type: String,
+    #[serde(rename = "IS_NULLABLE", deserialize_with = "yes_no")]
+    pub nullable: bool,
+    #[serde(rename = "ORDINAL_POSITION")]
+    pub ordinal: u32,
+    #[serde(skip_deserializing)]
+    pub analysis: Option<ColumnAnalysis>,
+}
+
+fn yes_no<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<bool, D::Error> {
+    Ok(String::deserialize(deserializer)? == "YES")
+}
+
+impl ColumnDescriptor {
+    pub fn is_numeric(&self) -> bool {
+        matches!(
+            self.sql_type.as_str(),
+            "BIGINT" | "INTEGER" | "SMALLINT" | "TINYINT" | "FLOAT" | "DOUBLE" | "REAL" | "DECIMAL"
+        )
+    }
+
+    /// The sum aggregator matching the column's type, `None` for
+    /// non-numeric columns.
+    pub fn sum_aggregator(&self, name: String) -> Option<Aggregator> {
+        let field_name = self.name.clone();
+        match self.sql_type.as_str() {
+            "BIGINT" | "INTEGER" | "SMALLINT" | "TINYINT" => {
+                Some(Aggregator::long_sum(name, field_name))
+            }
+            "FLOAT" => Some(Aggregator::float_sum(name, field_name)),
+            "DOUBLE" | "REAL" | "DECIMAL" => Some(Aggregator::double_sum(name, field_name)),
+            _ => None,
+        }
+    }
+}
+
+/// The columns of a table, ordered by their ordinal position.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TableSchema {
+    pub table: String,
+    pub columns: Vec<ColumnDescriptor>,
+}
+
+impl TableSchema {
+    pub fn column(&self, name: &str) -> Option<&ColumnDescriptor> {
+        self.columns.iter().find(|column| column.name == name)
+    }
+
+    pub fn has_column(&self, name: &str) -> bool {
+        self.column(name).is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ColumnDescriptor;
+    use crate::components::aggregations::Aggregator;
+
+    #[test]
+    fn picks_sum_aggregator() {
+        let column: ColumnDescriptor = serde_json::from_str(
+            r#"{"COLUMN_NAME": "added", "DATA_TYPE": "BIGINT", "IS_NULLABLE": "NO", "ORDINAL_POSITION": 3}"#,
+        )
+        .unwrap();
+        assert!(!column.nullable);
+        assert!(matches!(
+            column.sum_aggregator("total".into()),
+            Some(Aggregator::LongSum { name, field_name }) if name == "total" && field_name == "added"
+        ));
+
+        let column: ColumnDescriptor = serde_json::from_str(
+            r#"{"COLUMN_NAME": "page", "DATA_TYPE": "VARCHAR", "IS_NULLABLE": "YES", "ORDINAL_POSITION": 2}"#,
+        )
+        .unwrap();
+        assert!(column.nullable);
+        assert!(column.sum_aggregator("total".into()).is_none());
+    }
+}
@@ -1,24 +1,36 @@
-use serde::{Deserialize, Serialize};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::Value;
 
 use self::{
     datasource_metadata::DataSourceMetadata, groupby::GroupBy, scan::Scan, search::Search,
     segment_metadata::SegmentMetadata, sql::Sql, time_boundary::TimeBoundary,
-    timeseries::Timeseries, topn::TopN,
+    timeseries::Timeseries, topn::TopN, translation::TranslationError, validation::ValidationError,
 };
 
+pub mod avatica;
 pub mod datasource_metadata;
+pub mod explain;
 pub mod groupby;
+pub mod information_schema;
 pub mod response;
 pub mod scan;
 pub mod search;
 pub mod segment_metadata;
 pub mod sql;
+pub mod sql_builder;
+pub mod sql_result;
+pub mod statements;
 pub mod time_boundary;
 pub mod timeseries;
 pub mod topn;
+pub mod translation;
+pub mod validation;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
+/// Any of the native queries or the SQL query.
+///
+/// It is (de)serialized as the JSON body Druid expects for that query, with
+/// native queries told apart by their `queryType`.
+#[derive(Debug, Clone)]
 pub enum Query {
     Timeseries(Timeseries),
     TopN(Box<TopN>),
@@ -31,6 +43,112 @@ pub enum Query {
     Sql(Sql),
 }
 
+impl Query {
+    /// Check a native query for problems Druid would only report after a
+    /// round trip:
+    ///
+    /// - aggregator and post-aggregator output names are unique;
+    /// - post-aggregators only access aggregators and earlier
+    ///   post-aggregators;
+    /// - having specs and limit spec columns name existing outputs;
+    /// - subtotals only use the groupBy dimensions;
+    /// - a numeric topN metric names an existing metric;
+    /// - there is at least one interval and none of them are empty.
+    ///
+    /// All problems are returned, each with its path in the query like
+    /// `aggregations[2].name`. SQL queries are not checked.
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        match self {
+            Self::Timeseries(q) => q.validate(),
+            Self::TopN(q) => q.validate(),
+            Self::GroupBy(q) => q.validate(),
+            Self::Scan(q) => q.validate(),
+            Self::Search(q) => q.validate(),
+            Self::TimeBoundary(_)
+            | Self::SegmentMetadata(_)
+            | Self::DataSourceMetadata(_)
+            | Self::Sql(_) => Ok(()),
+        }
+    }
+
+    /// Translate a timeseries, topN, groupBy, scan or time boundary query
+    /// into equivalent Druid SQL, for reading or for running through
+    /// [`Client::sql`](crate::prelude::Client::sql).
+    ///
+    /// Filters become the `WHERE` clause, aggregators SQL aggregate
+    /// functions, the granularity a `TIME_FLOOR` of `__time` and limit specs
+    /// `ORDER BY` and `LIMIT`. Parts without an exact SQL equivalent, like
+    /// JavaScript aggregators, are a [`TranslationError::Unsupported`]. SQL
+    /// queries are returned as is.
+    pub fn to_sql(&self) -> Result<String, TranslationError> {
+        match self {
+            Self::Timeseries(q) => q.to_sql(),
+            Self::TopN(q) => q.to_sql(),
+            Self::GroupBy(q) => q.to_sql(),
+            Self::Scan(q) => q.to_sql(),
+            Self::TimeBoundary(q) => q.to_sql(),
+            Self::Sql(q) => Ok(q.query.clone()),
+            Self::Search(_) => Err(TranslationError::Unsupported("search queries".into())),
+            Self::SegmentMetadata(_) => Err(TranslationError::Unsupported(
+                "segment metadata queries".into(),
+            )),
+            Self::DataSourceMetadata(_) => Err(TranslationError::Unsupported(
+                "datasource metadata queries".into(),
+            )),
+        }
+    }
+}
+
+impl Serialize for Query {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match self {
+            Self::Timeseries(q) => q.serialize(serializer),
+            Self::TopN(q) => q.serialize(serializer),
+            Self::GroupBy(q) => q.serialize(serializer),
+            Self::Scan(q) => q.serialize(serializer),
+            Self::Search(q) => q.serialize(serializer),
+            Self::TimeBoundary(q) => q.serialize(serializer),
+            Self::SegmentMetadata(q) => q.serialize(serializer),
+            Self::DataSourceMetadata(q) => q.serialize(serializer),
+            Self::Sql(q) => q.serialize(serializer),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Query {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let val = Value::deserialize(deserializer)?;
+        let query_type = match val.get("queryType") {
+            Some(Value::String(s)) => s.clone(),
+            Some(_) => return Err(de::Error::custom(r#"field "queryType" is not a string"#)),
+            None if val.get("query").is_some() => {
+                return serde_json::from_value(val)
+                    .map(Self::Sql)
+                    .map_err(de::Error::custom)
+            }
+            None => return Err(de::Error::custom(r#"expected field "queryType" not found"#)),
+        };
+        match query_type.as_str() {
+            "timeseries" => serde_json::from_value(val).map(Self::Timeseries),
+            "topN" => serde_json::from_value(val).map(Self::TopN),
+            "groupBy" => serde_json::from_value(val).map(Self::GroupBy),
+            "scan" => serde_json::from_value(val).map(Self::Scan),
+            "search" => serde_json::from_value(val).map(Self::Search),
+            "timeBoundary" => serde_json::from_value(val).map(Self::TimeBoundary),
+            "segmentMetadata" => serde_json::from_value(val).map(Self::SegmentMetadata),
+            "dataSourceMetadata" => serde_json::from_value(val).map(Self::DataSourceMetadata),
+            _ => return Err(de::Error::custom(format!("unknown queryType {query_type}"))),
+        }
+        .map_err(de::Error::custom)
+    }
+}
+
 impl From<Timeseries> for Query {
     fn from(timeseries_query: Timeseries) -> Self {
         Self::Timeseries(timeseries_query)
@@ -1,12 +1,24 @@
 use std::collections::HashMap;
 
 use chrono::{DateTime, Utc};
-use serde::{Deserialize, Serialize};
+use serde::{
+    de::{
+        value::{MapAccessDeserializer, MapDeserializer},
+        DeserializeOwned, DeserializeSeed,
+    },
+    Deserialize, Deserializer, Serialize, Serializer,
+};
 
-use crate::components::{
-    druid_types::DruidNativeType, granularities::Granularity, intervals::Interval,
+use crate::{
+    components::{
+        aggregations::Aggregator, druid_types::DruidNativeType, granularities::Granularity,
+        intervals::Interval,
+    },
+    ingestion::data_schema::TimestampSpec,
 };
 
+use super::sql_result::{ObjectHeader, ObjectRows};
+
 #[derive(Debug, Clone, Serialize, Deserialize, thiserror::Error)]
 #[serde(rename_all = "camelCase")]
 #[error("query error: {error_message}")]
@@ -19,33 +31,33 @@ pub struct QueryError {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
-pub struct TimeseriesResult {
+pub struct TimeseriesResult<T = HashMap<String, DruidNativeType>> {
     pub timestamp: Option<DateTime<Utc>>,
-    pub result: HashMap<String, DruidNativeType>,
+    pub result: T,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
-pub struct TopNResult {
+pub struct TopNResult<T = HashMap<String, DruidNativeType>> {
     pub timestamp: DateTime<Utc>,
-    pub result: Vec<HashMap<String, DruidNativeType>>,
+    pub result: Vec<T>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
-pub struct GroupByResult {
+pub struct GroupByResult<T = HashMap<String, DruidNativeType>> {
     pub timestamp: DateTime<Utc>,
     pub version: String,
-    pub event: HashMap<String, DruidNativeType>,
+    pub event: T,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(untagged, rename_all = "camelCase")]
-pub enum ScanResult {
+#[serde(untagged, rename_all = "camelCase", rename_all_fields = "camelCase")]
+pub enum ScanResult<T = HashMap<String, DruidNativeType>> {
     List {
         segment_id: String,
         columns: Vec<String>,
-        events: Vec<HashMap<String, DruidNativeType>>,
+        events: Vec<T>,
     },
     CompactedList {
         segment_id: String,
@@ -54,6 +66,58 @@ pub enum ScanResult {
     },
 }
 
+/// A batch of `list` scan results, decoded without guessing the format.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ScanList<T> {
+    segment_id: String,
+    columns: Vec<String>,
+    events: Vec<T>,
+}
+
+impl<T> From<ScanList<T>> for ScanResult<T> {
+    fn from(batch: ScanList<T>) -> Self {
+        Self::List {
+            segment_id: batch.segment_id,
+            columns: batch.columns,
+            events: batch.events,
+        }
+    }
+}
+
+/// A batch of `compactedList` scan results, with each row still an array
+/// of column values.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ScanCompactedList {
+    segment_id: String,
+    columns: Vec<String>,
+    events: Vec<Vec<serde_json::Value>>,
+}
+
+impl ScanCompactedList {
+    /// Deserialize each row into `T` by name, reading its values as a map
+    /// keyed by the columns without building one.
+    pub(crate) fn into_list<T: DeserializeOwned>(self) -> serde_json::Result<ScanResult<T>> {
+        let events = self
+            .events
+            .into_iter()
+            .map(|row| {
+                let names = self.columns.iter().map(String::as_str);
+                let mut values = MapDeserializer::new(names.zip(row));
+                let event = T::deserialize(MapAccessDeserializer::new(&mut values))?;
+                values.end()?;
+                Ok(event)
+            })
+            .collect::<serde_json::Result<_>>()?;
+        Ok(ScanResult::List {
+            segment_id: self.segment_id,
+            columns: self.columns,
+            events,
+        })
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct SearchResult {
@@ -79,14 +143,37 @@ pub struct TimeBoundaryTimes {
 #[serde(rename_all = "camelCase")]
 pub struct SegmentMetadataResult {
     pub id: String,
-    pub intervals: Vec<Interval>,
-    pub columns: HashMap<String, HashMap<String, DruidNativeType>>,
-    pub aggregators: HashMap<String, HashMap<String, DruidNativeType>>,
-    pub query_granularity: Granularity,
+    pub intervals: Option<Vec<Interval>>,
+    pub columns: HashMap<String, ColumnAnalysis>,
+    pub aggregators: Option<HashMap<String, Aggregator>>,
+    pub timestamp_spec: Option<TimestampSpec>,
+    pub query_granularity: Option<Granularity>,
+    pub rollup: Option<bool>,
     pub size: usize,
     pub num_rows: usize,
 }
 
+/// Analysis of a single column by a `segmentMetadata` query.
+///
+/// Only the fields of the requested analysis types are filled in.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ColumnAnalysis {
+    #[serde(rename = "type")]
+    pub column_type: String,
+    pub type_signature: Option<String>,
+    #[serde(default)]
+    pub has_multiple_values: bool,
+    #[serde(default)]
+    pub has_nulls: bool,
+    #[serde(default)]
+    pub size: u64,
+    pub cardinality: Option<u64>,
+    pub min_value: Option<DruidNativeType>,
+    pub max_value: Option<DruidNativeType>,
+    pub error_message: Option<String>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct DataSourceMetadataResult {
@@ -103,7 +190,130 @@ pub struct DataSourceMetadataTimes {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(untagged, rename_all = "camelCase")]
 pub enum SqlResult {
+    /// A row of the object format, without its null values.
+    #[serde(deserialize_with = "deserialize_object_row")]
     Object(HashMap<String, DruidNativeType>),
     Array(Vec<DruidNativeType>),
     Csv(String),
+    /// The header row of the object format, queried with `header: true`,
+    /// with the column names in order. Without types it looks just like a
+    /// row of nulls, so it is only decoded by position, with
+    /// [`SqlResult::from_object_rows`].
+    #[serde(serialize_with = "serialize_object_header", skip_deserializing)]
+    Header(Vec<String>),
+}
+
+impl SqlResult {
+    /// Decode a response in the object format, starting with the header row
+    /// if `header` was set on the query.
+    pub fn from_object_rows(text: &str, header: bool) -> Result<Vec<Self>, serde_json::Error> {
+        let mut deserializer = serde_json::Deserializer::from_str(text);
+        let (header, rows) = ObjectRows { header }.deserialize(&mut deserializer)?;
+        deserializer.end()?;
+        let header = header.map(|ObjectHeader(columns)| {
+            Self::Header(columns.into_iter().map(|column| column.name).collect())
+        });
+        header
+            .into_iter()
+            .map(Ok)
+            .chain(rows.into_iter().map(serde_json::from_value))
+            .collect()
+    }
+}
+
+fn deserialize_object_row<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<HashMap<String, DruidNativeType>, D::Error> {
+    let row = HashMap::<String, Option<DruidNativeType>>::deserialize(deserializer)?;
+    Ok(row
+        .into_iter()
+        .filter_map(|(name, value)| Some((name, value?)))
+        .collect())
+}
+
+fn serialize_object_header<S: Serializer>(
+    names: &[String],
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.collect_map(names.iter().map(|name| (name, ())))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+
+    use super::{GroupByResult, ScanCompactedList, ScanResult, SegmentMetadataResult, SqlResult};
+    use crate::components::{aggregations::Aggregator, druid_types::DruidNativeType};
+
+    #[derive(Debug, Deserialize)]
+    struct Edits {
+        page: String,
+        added: i64,
+    }
+
+    #[test]
+    fn parses_into_user_types() {
+        let body = r#"[{"version": "v1", "timestamp": "2023-01-01T00:00:00.000Z",
+            "event": {"page": "Rust", "added": 42, "ignored": true}}]"#;
+        let rows: Vec<GroupByResult<Edits>> = serde_json::from_str(body).unwrap();
+        assert_eq!(rows[0].event.page, "Rust");
+        assert_eq!(rows[0].event.added, 42);
+
+        let body =
+            r#"[{"segmentId": "s", "columns": ["added", "page"], "events": [[42, "Rust"]]}]"#;
+        let batches: Vec<ScanCompactedList> = serde_json::from_str(body).unwrap();
+        let rows = batches
+            .into_iter()
+            .map(ScanCompactedList::into_list)
+            .collect::<Result<Vec<ScanResult<Edits>>, _>>()
+            .unwrap();
+        assert!(matches!(&rows[0], ScanResult::List { events, .. }
+            if events[0].page == "Rust" && events[0].added == 42));
+    }
+
+    #[test]
+    fn parses_segment_metadata() {
+        let body = r#"{
+            "id": "merged",
+            "intervals": null,
+            "columns": {
+                "__time": {"type": "LONG", "typeSignature": "LONG", "hasMultipleValues": false, "hasNulls": false,
+                    "size": 0, "cardinality": null, "minValue": null, "maxValue": null, "errorMessage": null},
+                "page": {"type": "STRING", "typeSignature": "STRING", "hasMultipleValues": false, "hasNulls": true,
+                    "size": 0, "cardinality": 4, "minValue": "Apple", "maxValue": "Zebra", "errorMessage": null}
+            },
+            "aggregators": {"added": {"type": "longSum", "name": "added", "fieldName": "added"}},
+            "timestampSpec": {"column": "__time", "format": "millis", "missingValue": null},
+            "queryGranularity": {"type": "none"},
+            "rollup": false,
+            "size": 0,
+            "numRows": 39244
+        }"#;
+        let result: SegmentMetadataResult = serde_json::from_str(body).unwrap();
+        let page = &result.columns["page"];
+        assert!(page.has_nulls);
+        assert_eq!(page.cardinality, Some(4));
+        assert!(matches!(&page.min_value, Some(DruidNativeType::String(s)) if s == "Apple"));
+        assert!(result.columns["__time"].min_value.is_none());
+        assert!(matches!(
+            result.aggregators.as_ref().unwrap()["added"],
+            Aggregator::LongSum { .. }
+        ));
+        assert_eq!(result.timestamp_spec.unwrap().column, "__time");
+        assert_eq!(result.rollup, Some(false));
+    }
+
+    #[test]
+    fn tells_sql_headers_apart_by_position() {
+        let body = r#"[{"page": null, "added": null}, {"page": null, "added": null},
+            {"page": "Rust", "added": 42}]"#;
+        let results = SqlResult::from_object_rows(body, true).unwrap();
+        assert!(matches!(&results[0], SqlResult::Header(names) if names == &["page", "added"]));
+        assert!(matches!(&results[1], SqlResult::Object(row) if row.is_empty()));
+        assert!(matches!(&results[2], SqlResult::Object(row) if row.len() == 2));
+
+        let results = SqlResult::from_object_rows(body, false).unwrap();
+        assert_eq!(results.len(), 3);
+        assert!(matches!(&results[0], SqlResult::Object(row) if row.is_empty()));
+    }
 }
@@ -1,15 +1,24 @@
 use serde::{Deserialize, Serialize};
 
 use crate::components::{
-    context::Context, data_sources::DataSource, filters::Filter, intervals::Interval,
+    context::Context,
+    data_sources::DataSource,
+    filters::Filter,
+    intervals::{deserialize_intervals, Interval},
     virtual_columns::VirtaulColumn,
 };
 
+use super::{
+    translation::{Select, TranslationError, Translator},
+    validation::{ValidationError, Validator},
+};
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Scan {
     query_type: String,
     data_source: DataSource,
+    #[serde(deserialize_with = "deserialize_intervals")]
     intervals: Vec<Interval>,
     result_format: Option<String>,
     filter: Option<Filter>,
@@ -47,6 +56,10 @@ impl Scan {
         self
     }
 
+    pub(crate) fn is_compacted_list(&self) -> bool {
+        self.result_format.as_deref() == Some("compactedList")
+    }
+
     pub fn filter(mut self, filter: Filter) -> Self {
         self.filter = Some(filter);
         self
@@ -91,4 +104,41 @@ impl Scan {
         self.context = Some(context);
         self
     }
+
+    /// Check the query for problems Druid would reject it for, see
+    /// [`Query::validate`](super::Query::validate).
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        let mut validator = Validator::default();
+        validator.intervals(&self.intervals);
+        validator.finish()
+    }
+
+    /// Translate the query into Druid SQL, selecting all columns and the
+    /// virtual columns if no columns are set.
+    pub fn to_sql(&self) -> Result<String, TranslationError> {
+        let translator = Translator::new(self.virtual_columns.as_deref())?;
+        let columns = match self.columns.as_deref() {
+            None | Some([]) => std::iter::once("*".to_string())
+                .chain(translator.virtual_columns(self.virtual_columns.as_deref()))
+                .collect(),
+            Some(columns) => columns
+                .iter()
+                .map(|column| translator.select_column(column))
+                .collect(),
+        };
+        let mut select = Select {
+            columns,
+            from: translator.data_source(&self.data_source)?,
+            conditions: translator.conditions(&self.intervals, self.filter.as_ref())?,
+            limit: self.limit,
+            offset: self.offset,
+            ..Select::default()
+        };
+        match self.order.as_deref() {
+            Some("ascending") => select.order_by.push("\"__time\" ASC".into()),
+            Some("descending") => select.order_by.push("\"__time\" DESC".into()),
+            _ => {}
+        }
+        Ok(select.to_string())
+    }
 }
@@ -1,15 +1,23 @@
 use serde::{Deserialize, Serialize};
 
 use crate::components::{
-    context::Context, data_sources::DataSource, filters::Filter, granularities::Granularity,
-    intervals::Interval, search_query_specs::SearchQuerySpec, virtual_columns::VirtaulColumn,
+    context::Context,
+    data_sources::DataSource,
+    filters::Filter,
+    granularities::Granularity,
+    intervals::{deserialize_intervals, Interval},
+    search_query_specs::SearchQuerySpec,
+    virtual_columns::VirtaulColumn,
 };
 
+use super::validation::{ValidationError, Validator};
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Search {
     query_type: String,
     data_source: DataSource,
+    #[serde(deserialize_with = "deserialize_intervals")]
     intervals: Vec<Interval>,
     query: SearchQuerySpec,
     granularity: Option<Granularity>,
@@ -72,4 +80,12 @@ impl Search {
         self.context = Some(context);
         self
     }
+
+    /// Check the query for problems Druid would reject it for, see
+    /// [`Query::validate`](super::Query::validate).
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        let mut validator = Validator::default();
+        validator.intervals(&self.intervals);
+        validator.finish()
+    }
 }
@@ -1,7 +1,10 @@
 use serde::{Deserialize, Serialize};
 
 use crate::components::{
-    context::Context, data_sources::DataSource, intervals::Interval, to_include::ToInclude,
+    context::Context,
+    data_sources::DataSource,
+    intervals::{deserialize_optional_intervals, Interval},
+    to_include::ToInclude,
     virtual_columns::VirtaulColumn,
 };
 
@@ -10,10 +13,11 @@ use crate::components::{
 pub struct SegmentMetadata {
     query_type: String,
     data_source: DataSource,
+    #[serde(default, deserialize_with = "deserialize_optional_intervals")]
     intervals: Option<Vec<Interval>>,
     to_include: Option<ToInclude>,
     merge: Option<bool>,
-    analysis_types: Option<Vec<String>>,
+    analysis_types: Option<Vec<AnalysisType>>,
     lenient_aggregator_merge: Option<bool>,
     virtual_columns: Option<Vec<VirtaulColumn>>,
     context: Option<Context>,
@@ -49,7 +53,7 @@ impl SegmentMetadata {
         self
     }
 
-    pub fn analysis_types(mut self, analysis_types: &[String]) -> Self {
+    pub fn analysis_types(mut self, analysis_types: &[AnalysisType]) -> Self {
         self.analysis_types = Some(analysis_types.to_vec());
         self
     }
@@ -69,3 +73,17 @@ impl SegmentMetadata {
         self
     }
 }
+
+/// Column and segment properties a `segmentMetadata` query can analyze.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum AnalysisType {
+    Cardinality,
+    Interval,
+    Minmax,
+    Size,
+    TimestampSpec,
+    QueryGranularity,
+    Aggregators,
+    Rollup,
+}
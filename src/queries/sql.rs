@@ -8,6 +8,8 @@ pub struct Sql {
     pub query: String,
     pub result_format: Option<ResultFormat>,
     pub header: Option<bool>,
+    pub types_header: Option<bool>,
+    pub sql_types_header: Option<bool>,
     pub parameters: Option<Vec<DruidSqlType>>,
     pub context: Option<Context>,
 }
@@ -28,6 +30,8 @@ impl Sql {
             query: query.into(),
             result_format: None,
             header: None,
+            types_header: None,
+            sql_types_header: None,
             parameters: None,
             context: None,
         }
@@ -43,6 +47,18 @@ impl Sql {
         self
     }
 
+    /// Add a row of the native types of the columns after the header row.
+    pub fn types_header(mut self, types_header: bool) -> Self {
+        self.types_header = Some(types_header);
+        self
+    }
+
+    /// Add a row of the SQL types of the columns after the header row.
+    pub fn sql_types_header(mut self, sql_types_header: bool) -> Self {
+        self.sql_types_header = Some(sql_types_header);
+        self
+    }
+
     pub fn parameters(mut self, parameters: &[DruidSqlType]) -> Self {
         self.parameters = Some(parameters.to_vec());
         self
@@ -52,4 +68,148 @@ impl Sql {
         self.context = Some(context);
         self
     }
+
+    /// Set the parameters after checking that there is one for each `?`
+    /// placeholder in the query.
+    ///
+    /// This is the runtime counterpart of [`sql!`](crate::sql!) for queries
+    /// that aren't known at compile time.
+    pub fn bind(self, parameters: &[DruidSqlType]) -> Result<Self, ParameterCountError> {
+        let placeholders = placeholder_count(&self.query);
+        if placeholders == parameters.len() {
+            Ok(self.parameters(parameters))
+        } else {
+            Err(ParameterCountError {
+                placeholders,
+                parameters: parameters.len(),
+            })
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, thiserror::Error)]
+#[error("query has {placeholders} placeholders but {parameters} parameters")]
+pub struct ParameterCountError {
+    pub placeholders: usize,
+    pub parameters: usize,
+}
+
+/// Count the `?` placeholders of a SQL query, skipping string literals,
+/// quoted identifiers and comments.
+pub const fn placeholder_count(query: &str) -> usize {
+    let bytes = query.as_bytes();
+    let mut count = 0;
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'?' => count += 1,
+            // A doubled quote inside a literal or identifier ends it and
+            // starts the next one right away, which skips it all the same.
+            quote @ (b'\'' | b'"') => {
+                i += 1;
+                while i < bytes.len() && bytes[i] != quote {
+                    i += 1;
+                }
+            }
+            b'-' if i + 1 < bytes.len() && bytes[i + 1] == b'-' => {
+                while i < bytes.len() && bytes[i] != b'\n' {
+                    i += 1;
+                }
+            }
+            b'/' if i + 1 < bytes.len() && bytes[i + 1] == b'*' => {
+                i += 2;
+                while i < bytes.len()
+                    && !(bytes[i] == b'*' && i + 1 < bytes.len() && bytes[i + 1] == b'/')
+                {
+                    i += 1;
+                }
+                i += 1;
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    count
+}
+
+/// Build an [`Sql`] query with its parameters.
+///
+/// The parameters are converted with `Into<DruidSqlType>`, and the number
+/// of `?` placeholders, outside of string literals, quoted identifiers and
+/// comments, is checked against the number of parameters at compile time.
+///
+/// ```
+/// use query_druid::sql;
+///
+/// let page = "Rust";
+/// let query = sql!(
+///     "SELECT COUNT(*) FROM wikipedia WHERE page = ? AND added > ? -- why?",
+///     page,
+///     10,
+/// );
+/// assert_eq!(query.parameters.unwrap().len(), 2);
+/// ```
+///
+/// A missing parameter fails to compile:
+///
+/// ```compile_fail
+/// use query_druid::sql;
+///
+/// let query = sql!("SELECT * FROM wikipedia WHERE page = ? AND added > ?", "Rust");
+/// ```
+#[macro_export]
+macro_rules! sql {
+    ($query:literal $(, $parameter:expr)* $(,)?) => {{
+        const _: () = assert!(
+            $crate::queries::sql::placeholder_count($query) == <[&str]>::len(&[$(stringify!($parameter)),*]),
+            "the number of `?` placeholders does not match the number of parameters",
+        );
+        let parameters: ::std::vec::Vec<$crate::components::druid_types::DruidSqlType> =
+            ::std::vec![$(::std::convert::Into::into($parameter)),*];
+        let query = $crate::queries::sql::Sql::new($query);
+        if parameters.is_empty() {
+            query
+        } else {
+            query.parameters(&parameters)
+        }
+    }};
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{placeholder_count, Sql};
+    use crate::components::druid_types::DruidSqlType;
+
+    #[test]
+    fn counts_placeholders() {
+        assert_eq!(placeholder_count("SELECT ? + ?"), 2);
+        assert_eq!(
+            placeholder_count(
+                "SELECT 'what?', 'it''s?', \"col?\" /* ? */ FROM t -- ?\nWHERE a = ?"
+            ),
+            1
+        );
+        assert_eq!(placeholder_count("SELECT '?"), 0);
+    }
+
+    #[test]
+    fn binds_parameters() {
+        let query = sql!(
+            "SELECT * FROM wikipedia WHERE page = ? AND added > ?",
+            "Rust",
+            10
+        );
+        assert_eq!(
+            query.parameters,
+            Some(vec![
+                DruidSqlType::Varchar("Rust".into()),
+                DruidSqlType::Integer(10)
+            ])
+        );
+        assert!(sql!("SELECT 1").parameters.is_none());
+
+        let query = Sql::new("SELECT * FROM wikipedia WHERE page = ?");
+        assert!(query.clone().bind(&[]).is_err());
+        assert!(query.bind(&[DruidSqlType::Varchar("Rust".into())]).is_ok());
+    }
 }
@@ -0,0 +1,755 @@
+//! A builder of SQL queries that keeps values out of the query text.
+//!
+//! Identifiers are always quoted and values are written as `?`
+//! placeholders, with the values collected into [`Sql::parameters`] in
+//! order:
+//!
+//! ```
+//! use query_druid::queries::sql_builder::{column, count_star, select, time_floor};
+//!
+//! let sql = select([
+//!     time_floor(column("__time"), "PT1H").alias("hour"),
+//!     column("page").into(),
+//!     count_star().alias("edits"),
+//! ])
+//! .from("wikipedia")
+//! .where_(column("channel").eq("#en") & column("added").gt(10))
+//! .group_by([column("hour"), column("page")])
+//! .order_by([column("edits").desc()])
+//! .limit(5)
+//! .build();
+//!
+//! assert_eq!(
+//!     sql.query,
+//!     "SELECT TIME_FLOOR(\"__time\", 'PT1H') AS \"hour\", \"page\", COUNT(*) AS \"edits\"\n\
+//!      FROM \"wikipedia\"\n\
+//!      WHERE \"channel\" = ? AND \"added\" > ?\n\
+//!      GROUP BY \"hour\", \"page\"\n\
+//!      ORDER BY \"edits\" DESC\n\
+//!      LIMIT 5"
+//! );
+//! assert_eq!(sql.parameters.unwrap().len(), 2);
+//! ```
+
+use std::ops::{Add, BitAnd, BitOr, Div, Mul, Not, Sub};
+
+use crate::components::druid_types::DruidSqlType;
+
+use super::{
+    sql::Sql,
+    translation::{quote_identifier, quote_string},
+};
+
+// Operator precedences, from loosest to tightest binding.
+const OR: u8 = 1;
+const AND: u8 = 2;
+const NOT: u8 = 3;
+const COMPARISON: u8 = 4;
+const SUM: u8 = 5;
+const PRODUCT: u8 = 6;
+const ATOM: u8 = 7;
+
+/// A SQL expression and the values of its placeholders.
+#[derive(Debug, Clone)]
+pub struct SqlExpr {
+    sql: String,
+    parameters: Vec<DruidSqlType>,
+    /// The precedence of the outermost operator, [`ATOM`] for expressions
+    /// that never need parentheses.
+    precedence: u8,
+}
+
+impl SqlExpr {
+    fn atom(sql: String, parameters: Vec<DruidSqlType>) -> Self {
+        Self {
+            sql,
+            parameters,
+            precedence: ATOM,
+        }
+    }
+
+    /// The expression as the operand of an operator binding with
+    /// `precedence`, parenthesized if it binds more loosely.
+    fn operand(self, precedence: u8) -> (String, Vec<DruidSqlType>) {
+        if self.precedence < precedence {
+            (format!("({})", self.sql), self.parameters)
+        } else {
+            (self.sql, self.parameters)
+        }
+    }
+
+    /// Combine the expression with `operands`, all parenthesized as needed
+    /// for `operand_precedence`.
+    fn operation<const N: usize>(
+        self,
+        precedence: u8,
+        operand_precedence: u8,
+        operands: [SqlExpr; N],
+        sql: impl FnOnce(String, [String; N]) -> String,
+    ) -> Self {
+        let (first, mut parameters) = self.operand(operand_precedence);
+        let operands = operands.map(|operand| {
+            let (sql, operand_parameters) = operand.operand(operand_precedence);
+            parameters.extend(operand_parameters);
+            sql
+        });
+        Self {
+            sql: sql(first, operands),
+            parameters,
+            precedence,
+        }
+    }
+
+    fn comparison<E: Into<SqlExpr>>(self, op: &str, other: E) -> Self {
+        self.operation(
+            COMPARISON,
+            COMPARISON + 1,
+            [other.into()],
+            |left, [right]| format!("{left} {op} {right}"),
+        )
+    }
+
+    /// A left associative binary operation.
+    fn binary(self, op: &str, precedence: u8, other: SqlExpr) -> Self {
+        let (left, mut parameters) = self.operand(precedence);
+        let (right, right_parameters) = other.operand(precedence + 1);
+        parameters.extend(right_parameters);
+        Self {
+            sql: format!("{left} {op} {right}"),
+            parameters,
+            precedence,
+        }
+    }
+
+    /// Join `exprs` with `separator`, keeping the parameters in order.
+    fn join<I: IntoIterator<Item = SqlExpr>>(
+        exprs: I,
+        separator: &str,
+    ) -> (String, Vec<DruidSqlType>) {
+        let mut sql = Vec::new();
+        let mut parameters = Vec::new();
+        for expr in exprs {
+            sql.push(expr.sql);
+            parameters.extend(expr.parameters);
+        }
+        (sql.join(separator), parameters)
+    }
+
+    #[allow(clippy::should_implement_trait)]
+    pub fn eq<E: Into<SqlExpr>>(self, other: E) -> Self {
+        self.comparison("=", other)
+    }
+
+    #[allow(clippy::should_implement_trait)]
+    pub fn ne<E: Into<SqlExpr>>(self, other: E) -> Self {
+        self.comparison("<>", other)
+    }
+
+    #[allow(clippy::should_implement_trait)]
+    pub fn lt<E: Into<SqlExpr>>(self, other: E) -> Self {
+        self.comparison("<", other)
+    }
+
+    #[allow(clippy::should_implement_trait)]
+    pub fn le<E: Into<SqlExpr>>(self, other: E) -> Self {
+        self.comparison("<=", other)
+    }
+
+    #[allow(clippy::should_implement_trait)]
+    pub fn gt<E: Into<SqlExpr>>(self, other: E) -> Self {
+        self.comparison(">", other)
+    }
+
+    #[allow(clippy::should_implement_trait)]
+    pub fn ge<E: Into<SqlExpr>>(self, other: E) -> Self {
+        self.comparison(">=", other)
+    }
+
+    pub fn and(self, other: SqlExpr) -> Self {
+        self.operation(AND, AND, [other], |left, [right]| {
+            format!("{left} AND {right}")
+        })
+    }
+
+    pub fn or(self, other: SqlExpr) -> Self {
+        self.operation(OR, OR, [other], |left, [right]| {
+            format!("{left} OR {right}")
+        })
+    }
+
+    pub fn like<E: Into<SqlExpr>>(self, pattern: E) -> Self {
+        self.comparison("LIKE", pattern)
+    }
+
+    pub fn is_in<I, E>(self, values: I) -> Self
+    where
+        I: IntoIterator<Item = E>,
+        E: Into<SqlExpr>,
+    {
+        let (values, value_parameters) = Self::join(values.into_iter().map(Into::into), ", ");
+        let mut expr = self.operation(COMPARISON, COMPARISON + 1, [], |sql, []| {
+            format!("{sql} IN ({values})")
+        });
+        expr.parameters.extend(value_parameters);
+        expr
+    }
+
+    pub fn between<L: Into<SqlExpr>, U: Into<SqlExpr>>(self, lower: L, upper: U) -> Self {
+        self.operation(
+            COMPARISON,
+            COMPARISON + 1,
+            [lower.into(), upper.into()],
+            |sql, [lower, upper]| format!("{sql} BETWEEN {lower} AND {upper}"),
+        )
+    }
+
+    pub fn is_null(self) -> Self {
+        self.operation(COMPARISON, COMPARISON + 1, [], |sql, []| {
+            format!("{sql} IS NULL")
+        })
+    }
+
+    pub fn is_not_null(self) -> Self {
+        self.operation(COMPARISON, COMPARISON + 1, [], |sql, []| {
+            format!("{sql} IS NOT NULL")
+        })
+    }
+
+    /// Name a selected expression, `expr AS "name"`.
+    pub fn alias(self, name: &str) -> SelectItem {
+        SelectItem(SqlExpr {
+            sql: format!("{} AS {}", self.sql, quote_identifier(name)),
+            ..self
+        })
+    }
+
+    /// Order by the expression ascending.
+    pub fn asc(self) -> OrderItem {
+        OrderItem(SqlExpr {
+            sql: format!("{} ASC", self.sql),
+            ..self
+        })
+    }
+
+    /// Order by the expression descending.
+    pub fn desc(self) -> OrderItem {
+        OrderItem(SqlExpr {
+            sql: format!("{} DESC", self.sql),
+            ..self
+        })
+    }
+}
+
+/// A selected expression, maybe named with [`SqlExpr::alias`].
+#[derive(Debug, Clone)]
+pub struct SelectItem(SqlExpr);
+
+impl From<SqlExpr> for SelectItem {
+    fn from(expr: SqlExpr) -> Self {
+        Self(expr)
+    }
+}
+
+/// An expression to order by, maybe with a direction from [`SqlExpr::asc`]
+/// or [`SqlExpr::desc`].
+#[derive(Debug, Clone)]
+pub struct OrderItem(SqlExpr);
+
+impl From<SqlExpr> for OrderItem {
+    fn from(expr: SqlExpr) -> Self {
+        Self(expr)
+    }
+}
+
+/// A value, written as a `?` placeholder.
+impl<T: Into<DruidSqlType>> From<T> for SqlExpr {
+    fn from(literal: T) -> Self {
+        value(literal)
+    }
+}
+
+macro_rules! binary_operator {
+    ($($trait:ident $method:ident $op:literal $precedence:ident),*) => {
+        $(
+            impl<E: Into<SqlExpr>> $trait<E> for SqlExpr {
+                type Output = SqlExpr;
+
+                fn $method(self, rhs: E) -> Self::Output {
+                    self.binary($op, $precedence, rhs.into())
+                }
+            }
+        )*
+    };
+}
+
+binary_operator!(Add add "+" SUM, Sub sub "-" SUM, Mul mul "*" PRODUCT, Div div "/" PRODUCT);
+
+impl BitAnd for SqlExpr {
+    type Output = SqlExpr;
+
+    fn bitand(self, rhs: Self) -> Self::Output {
+        self.and(rhs)
+    }
+}
+
+impl BitOr for SqlExpr {
+    type Output = SqlExpr;
+
+    fn bitor(self, rhs: Self) -> Self::Output {
+        self.or(rhs)
+    }
+}
+
+impl Not for SqlExpr {
+    type Output = SqlExpr;
+
+    fn not(self) -> Self::Output {
+        self.operation(NOT, NOT, [], |sql, []| format!("NOT {sql}"))
+    }
+}
+
+/// A column, as a quoted identifier.
+pub fn column(name: &str) -> SqlExpr {
+    SqlExpr::atom(quote_identifier(name), Vec::new())
+}
+
+/// A column of a table or alias, like `"w"."page"`.
+pub fn qualified(table: &str, name: &str) -> SqlExpr {
+    SqlExpr::atom(
+        format!("{}.{}", quote_identifier(table), quote_identifier(name)),
+        Vec::new(),
+    )
+}
+
+/// All columns, `*`.
+pub fn star() -> SqlExpr {
+    SqlExpr::atom("*".into(), Vec::new())
+}
+
+/// A value, written as a `?` placeholder.
+pub fn value<T: Into<DruidSqlType>>(value: T) -> SqlExpr {
+    SqlExpr::atom("?".into(), vec![value.into()])
+}
+
+/// A call of the SQL function `name`.
+///
+/// The name is written as is, so it must not come from user input.
+pub fn function<I: IntoIterator<Item = SqlExpr>>(name: &str, args: I) -> SqlExpr {
+    let (args, parameters) = SqlExpr::join(args, ", ");
+    SqlExpr::atom(format!("{name}({args})"), parameters)
+}
+
+pub fn count_star() -> SqlExpr {
+    function("COUNT", [star()])
+}
+
+pub fn count(expr: SqlExpr) -> SqlExpr {
+    function("COUNT", [expr])
+}
+
+pub fn count_distinct(expr: SqlExpr) -> SqlExpr {
+    SqlExpr::atom(format!("COUNT(DISTINCT {})", expr.sql), expr.parameters)
+}
+
+pub fn sum(expr: SqlExpr) -> SqlExpr {
+    function("SUM", [expr])
+}
+
+pub fn min(expr: SqlExpr) -> SqlExpr {
+    function("MIN", [expr])
+}
+
+pub fn max(expr: SqlExpr) -> SqlExpr {
+    function("MAX", [expr])
+}
+
+pub fn avg(expr: SqlExpr) -> SqlExpr {
+    function("AVG", [expr])
+}
+
+/// Approximate distinct count with a HyperLogLog sketch from the
+/// DataSketches extension.
+pub fn approx_count_distinct_ds_hll(expr: SqlExpr) -> SqlExpr {
+    function("APPROX_COUNT_DISTINCT_DS_HLL", [expr])
+}
+
+/// Floor a timestamp to an ISO 8601 `period`, like `PT1H`.
+pub fn time_floor(expr: SqlExpr, period: &str) -> SqlExpr {
+    SqlExpr::atom(
+        format!("TIME_FLOOR({}, {})", expr.sql, quote_string(period)),
+        expr.parameters,
+    )
+}
+
+/// A table, lookup or subquery to select from or join.
+#[derive(Debug, Clone)]
+pub struct Table {
+    sql: String,
+    parameters: Vec<DruidSqlType>,
+}
+
+impl Table {
+    /// Refer to the table as `alias`.
+    pub fn alias(self, alias: &str) -> Self {
+        Self {
+            sql: format!("{} AS {}", self.sql, quote_identifier(alias)),
+            ..self
+        }
+    }
+}
+
+impl From<&str> for Table {
+    fn from(name: &str) -> Self {
+        table(name)
+    }
+}
+
+pub fn table(name: &str) -> Table {
+    Table {
+        sql: quote_identifier(name),
+        parameters: Vec::new(),
+    }
+}
+
+/// A lookup, in the `lookup` schema.
+pub fn lookup(name: &str) -> Table {
+    Table {
+        sql: format!("lookup.{}", quote_identifier(name)),
+        parameters: Vec::new(),
+    }
+}
+
+/// A query in parentheses.
+pub fn subquery(query: SelectQuery) -> Table {
+    let (sql, parameters) = query.render();
+    Table {
+        sql: format!("(\n{sql}\n)"),
+        parameters,
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Join {
+    kind: &'static str,
+    table: Table,
+    on: SqlExpr,
+}
+
+/// A `SELECT` query, built up clause by clause and turned into [`Sql`] with
+/// [`build`](Self::build).
+#[derive(Debug, Clone)]
+pub struct SelectQuery {
+    with: Vec<(String, SelectQuery)>,
+    columns: Vec<SelectItem>,
+    from: Option<Table>,
+    joins: Vec<Join>,
+    condition: Option<SqlExpr>,
+    group_by: Vec<SqlExpr>,
+    having: Option<SqlExpr>,
+    order_by: Vec<OrderItem>,
+    limit: Option<usize>,
+    offset: Option<usize>,
+    union_all: Vec<SelectQuery>,
+}
+
+/// Start a query selecting `columns`.
+pub fn select<I, C>(columns: I) -> SelectQuery
+where
+    I: IntoIterator<Item = C>,
+    C: Into<SelectItem>,
+{
+    SelectQuery {
+        with: Vec::new(),
+        columns: columns.into_iter().map(Into::into).collect(),
+        from: None,
+        joins: Vec::new(),
+        condition: None,
+        group_by: Vec::new(),
+        having: None,
+        order_by: Vec::new(),
+        limit: None,
+        offset: None,
+        union_all: Vec::new(),
+    }
+}
+
+impl SelectQuery {
+    /// Add the common table expression `name` to a `WITH` clause.
+    pub fn with(mut self, name: &str, query: SelectQuery) -> Self {
+        self.with.push((name.into(), query));
+        self
+    }
+
+    pub fn from<T: Into<Table>>(mut self, table: T) -> Self {
+        self.from = Some(table.into());
+        self
+    }
+
+    /// An inner join.
+    pub fn join<T: Into<Table>>(mut self, table: T, on: SqlExpr) -> Self {
+        self.joins.push(Join {
+            kind: "INNER JOIN",
+            table: table.into(),
+            on,
+        });
+        self
+    }
+
+    pub fn left_join<T: Into<Table>>(mut self, table: T, on: SqlExpr) -> Self {
+        self.joins.push(Join {
+            kind: "LEFT JOIN",
+            table: table.into(),
+            on,
+        });
+        self
+    }
+
+    /// Set the `WHERE` condition, `AND`ing it with the condition so far.
+    pub fn where_(mut self, condition: SqlExpr) -> Self {
+        self.condition = Some(match self.condition {
+            Some(previous) => previous.and(condition),
+            None => condition,
+        });
+        self
+    }
+
+    pub fn group_by<I: IntoIterator<Item = SqlExpr>>(mut self, exprs: I) -> Self {
+        self.group_by.extend(exprs);
+        self
+    }
+
+    /// Set the `HAVING` condition, `AND`ing it with the condition so far.
+    pub fn having(mut self, condition: SqlExpr) -> Self {
+        self.having = Some(match self.having {
+            Some(previous) => previous.and(condition),
+            None => condition,
+        });
+        self
+    }
+
+    /// Order by the expressions, made descending with [`SqlExpr::desc`].
+    pub fn order_by<I, O>(mut self, exprs: I) -> Self
+    where
+        I: IntoIterator<Item = O>,
+        O: Into<OrderItem>,
+    {
+        self.order_by.extend(exprs.into_iter().map(Into::into));
+        self
+    }
+
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    pub fn offset(mut self, offset: usize) -> Self {
+        self.offset = Some(offset);
+        self
+    }
+
+    /// Append the rows of `query` with `UNION ALL`.
+    ///
+    /// The `ORDER BY`, `LIMIT` and `OFFSET` of this query apply to the whole
+    /// union, the ones of `query` only to its own rows.
+    pub fn union_all(mut self, query: SelectQuery) -> Self {
+        self.union_all.push(query);
+        self
+    }
+
+    /// The query text and its parameters.
+    pub fn build(self) -> Sql {
+        let (query, parameters) = self.render();
+        let sql = Sql::new(&query);
+        if parameters.is_empty() {
+            sql
+        } else {
+            sql.parameters(&parameters)
+        }
+    }
+
+    fn render(self) -> (String, Vec<DruidSqlType>) {
+        let mut clauses = Vec::new();
+        let mut parameters = Vec::new();
+        let mut clause = |keyword: &str, (sql, clause_parameters): (String, Vec<DruidSqlType>)| {
+            clauses.push(format!("{keyword}{sql}"));
+            parameters.extend(clause_parameters);
+        };
+        if !self.with.is_empty() {
+            let mut ctes = Vec::new();
+            let mut cte_parameters = Vec::new();
+            for (name, query) in self.with {
+                let (sql, query_parameters) = query.render();
+                ctes.push(format!("{} AS (\n{sql}\n)", quote_identifier(&name)));
+                cte_parameters.extend(query_parameters);
+            }
+            clause("WITH ", (ctes.join(", "), cte_parameters));
+        }
+        clause(
+            "SELECT ",
+            SqlExpr::join(self.columns.into_iter().map(|item| item.0), ", "),
+        );
+        if let Some(from) = self.from {
+            clause("FROM ", (from.sql, from.parameters));
+        }
+        for join in self.joins {
+            let mut join_parameters = join.table.parameters;
+            join_parameters.extend(join.on.parameters);
+            clause(
+                join.kind,
+                (
+                    format!(" {} ON {}", join.table.sql, join.on.sql),
+                    join_parameters,
+                ),
+            );
+        }
+        if let Some(condition) = self.condition {
+            clause("WHERE ", (condition.sql, condition.parameters));
+        }
+        if !self.group_by.is_empty() {
+            clause("GROUP BY ", SqlExpr::join(self.group_by, ", "));
+        }
+        if let Some(having) = self.having {
+            clause("HAVING ", (having.sql, having.parameters));
+        }
+        for query in self.union_all {
+            // A branch with clauses that would end the union is a subquery.
+            let nested = !query.with.is_empty()
+                || !query.order_by.is_empty()
+                || query.limit.is_some()
+                || query.offset.is_some();
+            let (sql, query_parameters) = query.render();
+            if nested {
+                clause("UNION ALL\n", (format!("(\n{sql}\n)"), query_parameters));
+            } else {
+                clause("UNION ALL\n", (sql, query_parameters));
+            }
+        }
+        if !self.order_by.is_empty() {
+            clause(
+                "ORDER BY ",
+                SqlExpr::join(self.order_by.into_iter().map(|item| item.0), ", "),
+            );
+        }
+        if let Some(limit) = self.limit {
+            clause("LIMIT ", (limit.to_string(), Vec::new()));
+        }
+        if let Some(offset) = self.offset {
+            clause("OFFSET ", (offset.to_string(), Vec::new()));
+        }
+        (clauses.join("\n"), parameters)
+    }
+}
+
+impl From<SelectQuery> for Sql {
+    fn from(query: SelectQuery) -> Self {
+        query.build()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        approx_count_distinct_ds_hll, column, count_star, lookup, qualified, select, star, sum,
+        table,
+    };
+    use crate::components::druid_types::DruidSqlType;
+
+    #[test]
+    fn builds_parameterized_queries() {
+        let sql = select([
+            column("country").into(),
+            approx_count_distinct_ds_hll(column("user")).alias("users"),
+        ])
+        .from("wiki\"pedia")
+        .where_(column("page").like("%Rust%") | !column("isRobot").eq(true))
+        .where_(column("namespace").is_in(["Main", "Talk"]))
+        .group_by([column("country")])
+        .having(sum(column("added")).gt(100))
+        .order_by([column("users").desc()])
+        .limit(10)
+        .offset(20)
+        .build();
+        assert_eq!(
+            sql.query,
+            "SELECT \"country\", APPROX_COUNT_DISTINCT_DS_HLL(\"user\") AS \"users\"\n\
+             FROM \"wiki\"\"pedia\"\n\
+             WHERE (\"page\" LIKE ? OR NOT \"isRobot\" = ?) AND \"namespace\" IN (?, ?)\n\
+             GROUP BY \"country\"\n\
+             HAVING SUM(\"added\") > ?\n\
+             ORDER BY \"users\" DESC\n\
+             LIMIT 10\n\
+             OFFSET 20"
+        );
+        assert_eq!(
+            sql.parameters,
+            Some(vec![
+                DruidSqlType::Varchar("%Rust%".into()),
+                DruidSqlType::Boolean(true),
+                DruidSqlType::Varchar("Main".into()),
+                DruidSqlType::Varchar("Talk".into()),
+                DruidSqlType::Integer(100),
+            ])
+        );
+    }
+
+    #[test]
+    fn builds_joins_ctes_and_unions() {
+        let edits = select([column("page").into(), count_star().alias("edits")])
+            .from("wikipedia")
+            .where_(column("channel").eq("#en"))
+            .group_by([column("page")]);
+        let sql = select([qualified("e", "page"), qualified("c", "v")])
+            .with("edits", edits)
+            .from(table("edits").alias("e"))
+            .left_join(
+                lookup("categories").alias("c"),
+                qualified("e", "page").eq(qualified("c", "k")),
+            )
+            .union_all(
+                select([star()])
+                    .from("archive")
+                    .where_(column("id").eq(7i64)),
+            )
+            .union_all(
+                select([star()])
+                    .from("recent")
+                    .order_by([column("v").desc()])
+                    .limit(3),
+            )
+            .order_by([column("page").asc(), column("v").into()])
+            .limit(10)
+            .build();
+        assert_eq!(
+            sql.query,
+            "WITH \"edits\" AS (\n\
+             SELECT \"page\", COUNT(*) AS \"edits\"\n\
+             FROM \"wikipedia\"\n\
+             WHERE \"channel\" = ?\n\
+             GROUP BY \"page\"\n\
+             )\n\
+             SELECT \"e\".\"page\", \"c\".\"v\"\n\
+             FROM \"edits\" AS \"e\"\n\
+             LEFT JOIN lookup.\"categories\" AS \"c\" ON \"e\".\"page\" = \"c\".\"k\"\n\
+             UNION ALL\n\
+             SELECT *\n\
+             FROM \"archive\"\n\
+             WHERE \"id\" = ?\n\
+             UNION ALL\n\
+             (\n\
+             SELECT *\n\
+             FROM \"recent\"\n\
+             ORDER BY \"v\" DESC\n\
+             LIMIT 3\n\
+             )\n\
+             ORDER BY \"page\" ASC, \"v\"\n\
+             LIMIT 10"
+        );
+        assert_eq!(
+            sql.parameters,
+            Some(vec![
+                DruidSqlType::Varchar("#en".into()),
+                DruidSqlType::Bigint(7),
+            ])
+        );
+    }
+}
@@ -0,0 +1,700 @@
+//! Typed SQL results, decoded with the column types from Druid's
+//! `typesHeader` and `sqlTypesHeader`.
+
+use chrono::{DateTime, NaiveDate, SecondsFormat, Utc};
+use serde::{
+    de::{self, DeserializeOwned, DeserializeSeed, MapAccess, SeqAccess, Visitor},
+    Deserialize, Deserializer, Serialize,
+};
+use serde_json::Value;
+
+use super::{
+    response::SqlResult,
+    sql::{ResultFormat, Sql},
+};
+use crate::components::druid_types::DruidNativeType;
+
+/// The name and types of a column of a SQL result.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ColumnSchema {
+    pub name: String,
+    /// The native type, like `LONG` or `ARRAY<STRING>`, from `typesHeader`.
+    pub druid_type: Option<String>,
+    /// The SQL type, like `TIMESTAMP` or `BIGINT`, from `sqlTypesHeader`.
+    pub sql_type: Option<String>,
+}
+
+/// A value of a SQL result, typed according to its column.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum SqlValue {
+    Null,
+    Boolean(bool),
+    Long(i64),
+    Double(f64),
+    Timestamp(DateTime<Utc>),
+    String(String),
+    Array(Vec<SqlValue>),
+    /// A value of an unknown or complex type, as Druid returned it.
+    Other(Value),
+}
+
+impl SqlValue {
+    pub fn is_null(&self) -> bool {
+        matches!(self, Self::Null)
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        if let Self::Boolean(b) = self {
+            Some(*b)
+        } else {
+            None
+        }
+    }
+
+    pub fn as_i64(&self) -> Option<i64> {
+        if let Self::Long(l) = self {
+            Some(*l)
+        } else {
+            None
+        }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Self::Double(d) => Some(*d),
+            Self::Long(l) => Some(*l as f64),
+            _ => None,
+        }
+    }
+
+    pub fn as_timestamp(&self) -> Option<DateTime<Utc>> {
+        if let Self::Timestamp(t) = self {
+            Some(*t)
+        } else {
+            None
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        if let Self::String(s) = self {
+            Some(s)
+        } else {
+            None
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[SqlValue]> {
+        if let Self::Array(v) = self {
+            Some(v)
+        } else {
+            None
+        }
+    }
+
+    /// The value as a native value, `None` if it is null. Booleans and
+    /// timestamps become strings, other values their JSON text.
+    pub(crate) fn to_native(&self) -> Option<DruidNativeType> {
+        Some(match self {
+            Self::Null => return None,
+            Self::Boolean(b) => DruidNativeType::String(b.to_string()),
+            Self::Long(l) => DruidNativeType::Long(*l),
+            Self::Double(d) => DruidNativeType::Double(*d),
+            Self::Timestamp(t) => {
+                DruidNativeType::String(t.to_rfc3339_opts(SecondsFormat::Millis, true))
+            }
+            Self::String(s) => DruidNativeType::String(s.clone()),
+            Self::Array(values) => DruidNativeType::Array(
+                values
+                    .iter()
+                    .map(|value| match value {
+                        Self::String(s) => s.clone(),
+                        other => serde_json::to_string(other).unwrap_or_default(),
+                    })
+                    .collect(),
+            ),
+            Self::Other(value) => DruidNativeType::String(value.to_string()),
+        })
+    }
+}
+
+/// The result of a SQL query, with the header rows parsed into the column
+/// schema and each row holding one value per column.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SqlResultSet {
+    pub columns: Vec<ColumnSchema>,
+    pub rows: Vec<Vec<SqlValue>>,
+}
+
+impl SqlResultSet {
+    /// The position of the column `name` in the rows.
+    pub fn column_index(&self, name: &str) -> Option<usize> {
+        self.columns.iter().position(|column| column.name == name)
+    }
+
+    /// The values of the column `name`, one per row.
+    pub fn column(&self, name: &str) -> Option<impl Iterator<Item = &SqlValue>> {
+        let index = self.column_index(name)?;
+        Some(self.rows.iter().map(move |row| &row[index]))
+    }
+
+    /// The result as object-format rows after a header row, the shape the
+    /// conversions of `[SqlResult]` take. Null values are left out of the
+    /// rows.
+    pub fn to_sql_results(&self) -> Vec<SqlResult> {
+        let header = SqlResult::Header(
+            self.columns
+                .iter()
+                .map(|column| column.name.clone())
+                .collect(),
+        );
+        let rows = self.rows.iter().map(|row| {
+            SqlResult::Object(
+                self.columns
+                    .iter()
+                    .zip(row)
+                    .filter_map(|(column, value)| Some((column.name.clone(), value.to_native()?)))
+                    .collect(),
+            )
+        });
+        std::iter::once(header).chain(rows).collect()
+    }
+
+    /// Turn on the header rows `query` doesn't set, so that the response
+    /// carries the column schema. Druid rejects the type headers without the
+    /// names, so they are left alone when `header` is off.
+    pub(crate) fn request_schema(query: &mut Sql) {
+        if *query.header.get_or_insert(true) {
+            query.types_header.get_or_insert(true);
+            query.sql_types_header.get_or_insert(true);
+        }
+    }
+
+    /// Parse the response to `query`, following its result format and
+    /// header options.
+    pub(crate) fn parse(text: &str, query: &Sql) -> Result<Self, String> {
+        let headers = Headers::of(query);
+        match query.result_format.unwrap_or(ResultFormat::Object) {
+            ResultFormat::Object => {
+                let mut deserializer = serde_json::Deserializer::from_str(text);
+                let (header, rows) = ObjectRows {
+                    header: headers.names,
+                }
+                .deserialize(&mut deserializer)
+                .map_err(|_| "response is not valid JSON".to_string())?;
+                Self::from_objects(header, rows)
+            }
+            ResultFormat::ObjectLines => {
+                let mut lines = non_empty_lines(text);
+                let header = if headers.names {
+                    let line = lines
+                        .next()
+                        .ok_or_else(|| "the header row is missing".to_string())?;
+                    Some(
+                        serde_json::from_str(line)
+                            .map_err(|e| format!("invalid header row: {e}"))?,
+                    )
+                } else {
+                    None
+                };
+                Self::from_objects(header, json_lines(lines)?)
+            }
+            ResultFormat::Array => {
+                let rows: Vec<Vec<Value>> = serde_json::from_str(text)
+                    .map_err(|_| "response is not valid JSON".to_string())?;
+                Self::from_arrays(rows, headers)
+            }
+            ResultFormat::ArrayLines => {
+                Self::from_arrays(json_lines(non_empty_lines(text))?, headers)
+            }
+            ResultFormat::Csv => Self::from_csv(text, headers),
+        }
+    }
+
+    fn from_objects(header: Option<ObjectHeader>, rows: Vec<Value>) -> Result<Self, String> {
+        let columns = match header {
+            Some(header) => header.0,
+            // Without a header the column order is lost, so use the
+            // (sorted) keys of the first row.
+            None => match rows.first() {
+                Some(Value::Object(row)) => row.keys().map(|name| untyped(name)).collect(),
+                _ => Vec::new(),
+            },
+        };
+        let rows = rows
+            .into_iter()
+            .map(|row| {
+                let Value::Object(mut row) = row else {
+                    return Err("a row is not a JSON object".to_string());
+                };
+                columns
+                    .iter()
+                    .map(|column| decode(row.remove(&column.name).unwrap_or(Value::Null), column))
+                    .collect()
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self { columns, rows })
+    }
+
+    fn from_arrays(rows: Vec<Vec<Value>>, headers: Headers) -> Result<Self, String> {
+        let text_rows = |row: Vec<Value>| {
+            row.into_iter()
+                .map(|value| match value {
+                    Value::String(s) => Ok(s),
+                    _ => Err("header rows must only hold strings".to_string()),
+                })
+                .collect()
+        };
+        Self::from_rows(rows, headers, text_rows, decode)
+    }
+
+    fn from_csv(text: &str, headers: Headers) -> Result<Self, String> {
+        Self::from_rows(csv_records(text), headers, Ok, decode_text)
+    }
+
+    /// Split off the header rows of the array-like formats and decode the
+    /// rest.
+    fn from_rows<T>(
+        rows: Vec<Vec<T>>,
+        headers: Headers,
+        header_row: impl Fn(Vec<T>) -> Result<Vec<String>, String>,
+        decode: impl Fn(T, &ColumnSchema) -> Result<SqlValue, String>,
+    ) -> Result<Self, String> {
+        let mut rows = rows.into_iter();
+        let mut header = |present: bool| -> Result<Option<Vec<String>>, String> {
+            if present {
+                let row = rows
+                    .next()
+                    .ok_or_else(|| "a header row is missing".to_string())?;
+                header_row(row).map(Some)
+            } else {
+                Ok(None)
+            }
+        };
+        let names = header(headers.names)?;
+        let druid_types = header(headers.names && headers.druid_types)?;
+        let sql_types = header(headers.names && headers.sql_types)?;
+        let columns: Vec<ColumnSchema> = match names {
+            Some(names) => names
+                .into_iter()
+                .enumerate()
+                .map(|(i, name)| ColumnSchema {
+                    name,
+                    druid_type: druid_types.as_ref().and_then(|types| types.get(i).cloned()),
+                    sql_type: sql_types.as_ref().and_then(|types| types.get(i).cloned()),
+                })
+                .collect(),
+            // Unnamed columns, as many as the first row has values.
+            None => (0..rows.as_slice().first().map_or(0, Vec::len))
+                .map(|_| untyped(""))
+                .collect(),
+        };
+        let rows = rows
+            .map(|row| {
+                if row.len() != columns.len() {
+                    return Err(format!(
+                        "a row has {} values for {} columns",
+                        row.len(),
+                        columns.len()
+                    ));
+                }
+                row.into_iter()
+                    .zip(&columns)
+                    .map(|(value, column)| decode(value, column))
+                    .collect()
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self { columns, rows })
+    }
+}
+
+/// Which header rows a response starts with.
+#[derive(Debug, Clone, Copy)]
+struct Headers {
+    names: bool,
+    druid_types: bool,
+    sql_types: bool,
+}
+
+impl Headers {
+    fn of(query: &Sql) -> Self {
+        Self {
+            names: query.header.unwrap_or(false),
+            druid_types: query.types_header.unwrap_or(false),
+            sql_types: query.sql_types_header.unwrap_or(false),
+        }
+    }
+}
+
+fn untyped(name: &str) -> ColumnSchema {
+    ColumnSchema {
+        name: name.into(),
+        druid_type: None,
+        sql_type: None,
+    }
+}
+
+/// The header row of the object formats, with the columns in order.
+///
+/// Each column maps to `null`, or to its types when `typesHeader` or
+/// `sqlTypesHeader` is set.
+pub(crate) struct ObjectHeader(pub(crate) Vec<ColumnSchema>);
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ColumnTypes {
+    #[serde(rename = "type")]
+    druid_type: Option<String>,
+    sql_type: Option<String>,
+}
+
+impl<'de> Deserialize<'de> for ObjectHeader {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct HeaderVisitor;
+
+        impl<'de> Visitor<'de> for HeaderVisitor {
+            type Value = ObjectHeader;
+
+            fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+                formatter.write_str("a header object")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+                let mut columns = Vec::new();
+                while let Some((name, types)) = map.next_entry::<String, Option<ColumnTypes>>()? {
+                    columns.push(ColumnSchema {
+                        name,
+                        druid_type: types.as_ref().and_then(|t| t.druid_type.clone()),
+                        sql_type: types.and_then(|t| t.sql_type),
+                    });
+                }
+                Ok(ObjectHeader(columns))
+            }
+        }
+
+        deserializer.deserialize_map(HeaderVisitor)
+    }
+}
+
+/// The rows of the object format, where the first one is the header if
+/// `header` is set.
+pub(crate) struct ObjectRows {
+    pub(crate) header: bool,
+}
+
+impl<'de> DeserializeSeed<'de> for ObjectRows {
+    type Value = (Option<ObjectHeader>, Vec<Value>);
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'de> Visitor<'de> for ObjectRows {
+    type Value = (Option<ObjectHeader>, Vec<Value>);
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("an array of rows")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let header = if self.header {
+            let header = seq.next_element()?;
+            Some(header.ok_or_else(|| de::Error::custom("the header row is missing"))?)
+        } else {
+            None
+        };
+        let mut rows = Vec::new();
+        while let Some(row) = seq.next_element()? {
+            rows.push(row);
+        }
+        Ok((header, rows))
+    }
+}
+
+/// The type a value is decoded as.
+#[derive(Debug, Clone, PartialEq)]
+enum Kind {
+    Boolean,
+    Long,
+    Double,
+    Timestamp,
+    String,
+    Array(Box<Kind>),
+    Unknown,
+}
+
+impl Kind {
+    /// The kind of a column, from its SQL type if known, else from its
+    /// native type.
+    fn of(column: &ColumnSchema) -> Self {
+        match (&column.sql_type, &column.druid_type) {
+            (Some(sql_type), _) => Self::from_sql_type(sql_type),
+            (None, Some(druid_type)) => Self::from_druid_type(druid_type),
+            (None, None) => Self::Unknown,
+        }
+    }
+
+    fn from_sql_type(sql_type: &str) -> Self {
+        let sql_type = sql_type.trim().to_ascii_uppercase();
+        if let Some(element) = sql_type.strip_suffix(" ARRAY") {
+            return Self::Array(Box::new(Self::from_sql_type(element)));
+        }
+        // Drop any precision, as in `TIMESTAMP(3)` or `DECIMAL(10, 2)`.
+        let base = sql_type.split('(').next().unwrap_or_default().trim();
+        match base {
+            "BOOLEAN" => Self::Boolean,
+            "TINYINT" | "SMALLINT" | "INTEGER" | "BIGINT" => Self::Long,
+            "REAL" | "FLOAT" | "DOUBLE" | "DECIMAL" => Self::Double,
+            "TIMESTAMP" | "DATE" => Self::Timestamp,
+            "CHAR" | "VARCHAR" => Self::String,
+            _ => Self::Unknown,
+        }
+    }
+
+    fn from_druid_type(druid_type: &str) -> Self {
+        let druid_type = druid_type.trim().to_ascii_uppercase();
+        if let Some(element) = druid_type
+            .strip_prefix("ARRAY<")
+            .and_then(|t| t.strip_suffix('>'))
+        {
+            return Self::Array(Box::new(Self::from_druid_type(element)));
+        }
+        match druid_type.as_str() {
+            "LONG" => Self::Long,
+            "FLOAT" | "DOUBLE" => Self::Double,
+            "STRING" => Self::String,
+            _ => Self::Unknown,
+        }
+    }
+}
+
+fn decode(value: Value, column: &ColumnSchema) -> Result<SqlValue, String> {
+    decode_kind(value, &Kind::of(column))
+        .map_err(|value| format!("invalid value {value} in column {}", column.name))
+}
+
+/// Decode a CSV field, where everything is text and an empty field is null
+/// unless the column holds strings.
+fn decode_text(field: String, column: &ColumnSchema) -> Result<SqlValue, String> {
+    match Kind::of(column) {
+        Kind::String | Kind::Unknown => Ok(SqlValue::String(field)),
+        _ if field.is_empty() => Ok(SqlValue::Null),
+        // Arrays are written as JSON.
+        Kind::Array(_) => decode(
+            serde_json::from_str(&field).unwrap_or(Value::String(field)),
+            column,
+        ),
+        _ => decode(Value::String(field), column),
+    }
+}
+
+/// Decode `value` as `kind`, returning the value back if it doesn't fit.
+fn decode_kind(value: Value, kind: &Kind) -> Result<SqlValue, Value> {
+    let decoded = match (kind, &value) {
+        (_, Value::Null) => Some(SqlValue::Null),
+        (Kind::Boolean, Value::Bool(b)) => Some(SqlValue::Boolean(*b)),
+        (Kind::Boolean, Value::Number(n)) => n.as_i64().map(|n| SqlValue::Boolean(n != 0)),
+        (Kind::Boolean, Value::String(s)) => s.parse().ok().map(SqlValue::Boolean),
+        (Kind::Long, Value::Number(n)) => n.as_i64().map(SqlValue::Long),
+        (Kind::Long, Value::String(s)) => s.parse().ok().map(SqlValue::Long),
+        (Kind::Double, Value::Number(n)) => n.as_f64().map(SqlValue::Double),
+        (Kind::Double, Value::String(s)) => s.parse().ok().map(SqlValue::Double),
+        (Kind::Timestamp, Value::Number(n)) => n
+            .as_i64()
+            .and_then(DateTime::from_timestamp_millis)
+            .map(SqlValue::Timestamp),
+        (Kind::Timestamp, Value::String(s)) => parse_timestamp(s).map(SqlValue::Timestamp),
+        (Kind::String, Value::String(s)) => Some(SqlValue::String(s.clone())),
+        (Kind::String, Value::Number(_) | Value::Bool(_)) => {
+            Some(SqlValue::String(value.to_string()))
+        }
+        (Kind::Array(element), Value::Array(items)) => {
+            return items
+                .iter()
+                .map(|item| decode_kind(item.clone(), element))
+                .collect::<Result<_, _>>()
+                .map(SqlValue::Array);
+        }
+        (Kind::Unknown, _) => Some(guess(value.clone())),
+        _ => None,
+    };
+    decoded.ok_or(value)
+}
+
+/// Decode a value of an unknown type by its JSON type.
+fn guess(value: Value) -> SqlValue {
+    match value {
+        Value::Null => SqlValue::Null,
+        Value::Bool(b) => SqlValue::Boolean(b),
+        Value::Number(n) => match n.as_i64() {
+            Some(l) => SqlValue::Long(l),
+            None => n
+                .as_f64()
+                .map_or(SqlValue::Other(Value::Number(n)), SqlValue::Double),
+        },
+        Value::String(s) => SqlValue::String(s),
+        Value::Array(items) => SqlValue::Array(items.into_iter().map(guess).collect()),
+        Value::Object(_) => SqlValue::Other(value),
+    }
+}
+
+/// Parse an ISO 8601 timestamp, or a date at midnight UTC.
+fn parse_timestamp(s: &str) -> Option<DateTime<Utc>> {
+    s.parse().ok().or_else(|| {
+        let date: NaiveDate = s.parse().ok()?;
+        Some(date.and_hms_opt(0, 0, 0)?.and_utc())
+    })
+}
+
+/// The lines of the line based formats, which end with an empty line.
+fn non_empty_lines(text: &str) -> impl Iterator<Item = &str> {
+    text.lines().filter(|line| !line.trim().is_empty())
+}
+
+/// Parse a JSON value from each line.
+fn json_lines<'a, T: DeserializeOwned>(
+    lines: impl Iterator<Item = &'a str>,
+) -> Result<Vec<T>, String> {
+    lines
+        .map(serde_json::from_str)
+        .collect::<Result<_, _>>()
+        .map_err(|_| "part of the response is not valid JSON".to_string())
+}
+
+/// Split CSV text into records, unquoting quoted fields, which may hold
+/// commas, doubled quotes and line breaks. Empty lines are skipped.
+fn csv_records(text: &str) -> Vec<Vec<String>> {
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted => {
+                if chars.peek() == Some(&'"') {
+                    chars.next();
+                    field.push('"');
+                } else {
+                    quoted = false;
+                }
+            }
+            '"' if field.is_empty() => quoted = true,
+            ',' if !quoted => record.push(std::mem::take(&mut field)),
+            '\n' if !quoted => {
+                if !field.is_empty() || !record.is_empty() {
+                    record.push(std::mem::take(&mut field));
+                    records.push(std::mem::take(&mut record));
+                }
+            }
+            '\r' if !quoted && chars.peek() == Some(&'\n') => {}
+            c => field.push(c),
+        }
+    }
+    if !field.is_empty() || !record.is_empty() {
+        record.push(field);
+        records.push(record);
+    }
+    records
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+
+    use super::{SqlResultSet, SqlValue};
+    use crate::queries::sql::{ResultFormat, Sql};
+
+    fn typed(format: ResultFormat) -> Sql {
+        Sql::new("SELECT __time, page, added FROM wikipedia")
+            .result_format(format)
+            .header(true)
+            .types_header(true)
+            .sql_types_header(true)
+    }
+
+    #[test]
+    fn parses_typed_json_results() {
+        let expected = vec![
+            SqlValue::Timestamp(Utc.with_ymd_and_hms(2015, 9, 12, 0, 46, 58).unwrap()),
+            SqlValue::String("Rust".into()),
+            SqlValue::Long(42),
+        ];
+
+        let body = r#"[
+            {"__time": {"type": "LONG", "sqlType": "TIMESTAMP"},
+             "page": {"type": "STRING", "sqlType": "VARCHAR"},
+             "added": {"type": "LONG", "sqlType": "BIGINT"}},
+            {"__time": "2015-09-12T00:46:58.000Z", "page": "Rust", "added": 42}
+        ]"#;
+        let result = SqlResultSet::parse(body, &typed(ResultFormat::Object)).unwrap();
+        let names: Vec<_> = result.columns.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, ["__time", "page", "added"]);
+        assert_eq!(result.columns[0].druid_type.as_deref(), Some("LONG"));
+        assert_eq!(result.columns[0].sql_type.as_deref(), Some("TIMESTAMP"));
+        assert_eq!(result.rows, vec![expected.clone()]);
+
+        let body = "[\"__time\",\"page\",\"added\"]\n\
+            [\"LONG\",\"STRING\",\"LONG\"]\n\
+            [\"TIMESTAMP\",\"VARCHAR\",\"BIGINT\"]\n\
+            [\"2015-09-12T00:46:58.000Z\",\"Rust\",42]\n\n";
+        let result = SqlResultSet::parse(body, &typed(ResultFormat::ArrayLines)).unwrap();
+        assert_eq!(result.rows, vec![expected]);
+
+        let body = r#"[["added"], ["LONG"], ["BIGINT"], ["many"]]"#;
+        assert!(SqlResultSet::parse(body, &typed(ResultFormat::Array)).is_err());
+    }
+
+    #[test]
+    fn requests_the_schema_unless_the_header_is_off() {
+        let mut sql = Sql::new("SELECT page FROM wikipedia");
+        SqlResultSet::request_schema(&mut sql);
+        assert_eq!(
+            (sql.header, sql.types_header, sql.sql_types_header),
+            (Some(true), Some(true), Some(true))
+        );
+
+        let mut sql = Sql::new("SELECT page FROM wikipedia").header(false);
+        SqlResultSet::request_schema(&mut sql);
+        assert_eq!(
+            (sql.header, sql.types_header, sql.sql_types_header),
+            (Some(false), None, None)
+        );
+        let result = SqlResultSet::parse(r#"[{"page": "Rust"}]"#, &sql).unwrap();
+        assert_eq!(result.columns[0].name, "page");
+        assert_eq!(result.rows, vec![vec![SqlValue::String("Rust".into())]]);
+    }
+
+    #[test]
+    fn parses_typed_csv_results() {
+        let body = "__time,page,added,tags\n\
+            LONG,STRING,LONG,ARRAY<STRING>\n\
+            TIMESTAMP,VARCHAR,BIGINT,VARCHAR ARRAY\n\
+            2015-09-12T00:46:58.000Z,\"Rust, \"\"the\"\" language\",42,\"[\"\"a\"\",\"\"b\"\"]\"\n\
+            2015-09-12T01:00:00.000Z,,,\n\
+            \n";
+        let result = SqlResultSet::parse(body, &typed(ResultFormat::Csv)).unwrap();
+        assert_eq!(result.columns[3].sql_type.as_deref(), Some("VARCHAR ARRAY"));
+        assert_eq!(
+            result.rows[0],
+            vec![
+                SqlValue::Timestamp(Utc.with_ymd_and_hms(2015, 9, 12, 0, 46, 58).unwrap()),
+                SqlValue::String("Rust, \"the\" language".into()),
+                SqlValue::Long(42),
+                SqlValue::Array(vec![
+                    SqlValue::String("a".into()),
+                    SqlValue::String("b".into())
+                ]),
+            ]
+        );
+        assert_eq!(result.rows[1][2], SqlValue::Null);
+        let added: Vec<_> = result
+            .column("added")
+            .unwrap()
+            .map(SqlValue::as_i64)
+            .collect();
+        assert_eq!(added, [Some(42), None]);
+    }
+}
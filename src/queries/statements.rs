@@ -0,0 +1,102 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Status of a query submitted to `/druid/v2/sql/statements`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StatementInfo {
+    pub query_id: String,
+    pub state: StatementState,
+    pub created_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub schema: Vec<StatementColumn>,
+    pub duration_ms: Option<i64>,
+    pub result: Option<StatementResultInfo>,
+    pub error_details: Option<StatementError>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum StatementState {
+    Accepted,
+    Running,
+    Success,
+    Failed,
+    /// A state added in a newer Druid version.
+    #[serde(other)]
+    Unknown,
+}
+
+impl StatementState {
+    pub fn is_complete(&self) -> bool {
+        matches!(self, Self::Success | Self::Failed)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StatementColumn {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub sql_type: String,
+    pub native_type: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StatementResultInfo {
+    pub num_total_rows: Option<u64>,
+    pub total_size_in_bytes: Option<u64>,
+    pub data_source: Option<String>,
+    #[serde(default)]
+    pub pages: Vec<PageInfo>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PageInfo {
+    pub id: u64,
+    pub num_rows: Option<u64>,
+    pub size_in_bytes: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StatementError {
+    pub error: Option<String>,
+    pub error_code: Option<String>,
+    pub category: Option<String>,
+    pub error_message: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{StatementInfo, StatementState};
+
+    #[test]
+    fn parses_statement() {
+        let body = r#"{
+            "queryId": "query-9b93f6f7",
+            "state": "SUCCESS",
+            "createdAt": "2023-07-26T21:16:25.324Z",
+            "schema": [
+                {"name": "__time", "type": "TIMESTAMP", "nativeType": "LONG"},
+                {"name": "channel", "type": "VARCHAR", "nativeType": "STRING"}
+            ],
+            "durationMs": 25591,
+            "result": {
+                "numTotalRows": 2,
+                "totalSizeInBytes": 375,
+                "dataSource": "__query_select",
+                "sampleRecords": [],
+                "pages": [{"id": 0, "numRows": 2, "sizeInBytes": 375}]
+            }
+        }"#;
+        let info: StatementInfo = serde_json::from_str(body).unwrap();
+        assert_eq!(info.state, StatementState::Success);
+        assert_eq!(info.schema[1].native_type.as_deref(), Some("STRING"));
+        assert_eq!(info.result.unwrap().pages[0].num_rows, Some(2));
+        let state: StatementState = serde_json::from_str(r#""CANCELED""#).unwrap();
+        assert_eq!(state, StatementState::Unknown);
+    }
+}
@@ -4,6 +4,8 @@ use crate::components::{
     context::Context, data_sources::DataSource, filters::Filter, virtual_columns::VirtaulColumn,
 };
 
+use super::translation::{Select, TranslationError, Translator};
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct TimeBoundary {
@@ -46,4 +48,31 @@ impl TimeBoundary {
         self.context = Some(context);
         self
     }
+
+    /// Translate the query into Druid SQL, as `MIN` and `MAX` of `__time`.
+    pub fn to_sql(&self) -> Result<String, TranslationError> {
+        let translator = Translator::new(self.virtual_columns.as_deref())?;
+        let (min, max) = match self.bound.as_deref() {
+            None => (true, true),
+            Some("minTime") => (true, false),
+            Some("maxTime") => (false, true),
+            Some(bound) => {
+                return Err(TranslationError::Unsupported(format!(
+                    "time boundaries like {bound}"
+                )))
+            }
+        };
+        let mut select = Select {
+            from: translator.data_source(&self.data_source)?,
+            conditions: translator.conditions(&[], self.filter.as_ref())?,
+            ..Select::default()
+        };
+        if min {
+            select.columns.push("MIN(\"__time\") AS \"minTime\"".into());
+        }
+        if max {
+            select.columns.push("MAX(\"__time\") AS \"maxTime\"".into());
+        }
+        Ok(select.to_string())
+    }
 }
@@ -1,16 +1,27 @@
 use serde::{Deserialize, Serialize};
 
 use crate::components::{
-    aggregations::Aggregator, context::Context, data_sources::DataSource, filters::Filter,
-    granularities::Granularity, intervals::Interval, post_aggregations::PostAggregator,
+    aggregations::Aggregator,
+    context::Context,
+    data_sources::DataSource,
+    filters::Filter,
+    granularities::Granularity,
+    intervals::{deserialize_intervals, Interval},
+    post_aggregations::PostAggregator,
     virtual_columns::VirtaulColumn,
 };
 
+use super::{
+    translation::{Select, TranslationError, Translator},
+    validation::{ValidationError, Validator},
+};
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Timeseries {
     query_type: String,
     data_source: DataSource,
+    #[serde(deserialize_with = "deserialize_intervals")]
     intervals: Vec<Interval>,
     granularity: Granularity,
     descending: Option<bool>,
@@ -73,4 +84,54 @@ impl Timeseries {
         self.context = Some(context);
         self
     }
+
+    /// Check the query for problems Druid would reject it for, see
+    /// [`Query::validate`](super::Query::validate).
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        let mut validator = Validator::default();
+        validator.intervals(&self.intervals);
+        validator.aggregations(self.aggregations.as_deref());
+        validator.post_aggregations(self.post_aggregations.as_deref());
+        validator.finish()
+    }
+
+    /// Translate the query into Druid SQL, grouping by the time bucket of
+    /// the granularity.
+    ///
+    /// SQL doesn't return empty time buckets, so bucketed queries need
+    /// `skipEmptyBuckets` set in the context to be translated.
+    pub fn to_sql(&self) -> Result<String, TranslationError> {
+        let mut translator = Translator::new(self.virtual_columns.as_deref())?;
+        let mut select = Select {
+            from: translator.data_source(&self.data_source)?,
+            conditions: translator.conditions(&self.intervals, self.filter.as_ref())?,
+            ..Select::default()
+        };
+        if translator
+            .group_by_time(&self.granularity, &mut select)?
+            .is_some()
+        {
+            // SQL has no rows for empty buckets, which native queries fill
+            // with zeros unless told to skip them.
+            let skip_empty_buckets = self
+                .context
+                .as_ref()
+                .and_then(|context| context.skip_empty_buckets);
+            if skip_empty_buckets != Some(true) {
+                return Err(TranslationError::Unsupported(
+                    "zero-filled empty buckets".into(),
+                ));
+            }
+            let direction = if self.descending == Some(true) {
+                "DESC"
+            } else {
+                "ASC"
+            };
+            select.order_by.push(format!("\"__time\" {direction}"));
+        }
+        translator.aggregations(self.aggregations.as_deref(), &mut select)?;
+        translator.post_aggregations(self.post_aggregations.as_deref(), &mut select)?;
+        select.limit = self.limit;
+        Ok(select.to_string())
+    }
 }
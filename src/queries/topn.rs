@@ -1,17 +1,29 @@
 use serde::{Deserialize, Serialize};
 
 use crate::components::{
-    aggregations::Aggregator, context::Context, data_sources::DataSource,
-    dimension_specs::DimensionSpec, filters::Filter, granularities::Granularity,
-    intervals::Interval, post_aggregations::PostAggregator, topn_metric_specs::TopNMetricSpec,
+    aggregations::Aggregator,
+    context::Context,
+    data_sources::DataSource,
+    dimension_specs::DimensionSpec,
+    filters::Filter,
+    granularities::Granularity,
+    intervals::{deserialize_intervals, Interval},
+    post_aggregations::PostAggregator,
+    topn_metric_specs::TopNMetricSpec,
     virtual_columns::VirtaulColumn,
 };
 
+use super::{
+    translation::{Select, TranslationError, Translator},
+    validation::{ValidationError, Validator},
+};
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct TopN {
     query_type: String,
     data_source: DataSource,
+    #[serde(deserialize_with = "deserialize_intervals")]
     intervals: Vec<Interval>,
     granularity: Granularity,
     dimension: DimensionSpec,
@@ -73,4 +85,44 @@ impl TopN {
         self.context = Some(context);
         self
     }
+
+    /// Check the query for problems Druid would reject it for, see
+    /// [`Query::validate`](super::Query::validate).
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        let mut validator = Validator::default();
+        validator.intervals(&self.intervals);
+        validator.dimensions("dimension", [&self.dimension]);
+        validator.aggregations(self.aggregations.as_deref());
+        validator.post_aggregations(self.post_aggregations.as_deref());
+        validator.topn_metric("metric", &self.metric);
+        validator.finish()
+    }
+
+    /// Translate the query into Druid SQL, grouping by the dimension and
+    /// ordering by the metric.
+    ///
+    /// Only the `all` granularity can be translated, as SQL has no limit per
+    /// time bucket.
+    pub fn to_sql(&self) -> Result<String, TranslationError> {
+        let mut translator = Translator::new(self.virtual_columns.as_deref())?;
+        if translator
+            .time_floor("\"__time\"", &self.granularity)?
+            .is_some()
+        {
+            return Err(TranslationError::Unsupported(
+                "topN queries with a granularity other than all".into(),
+            ));
+        }
+        let mut select = Select {
+            from: translator.data_source(&self.data_source)?,
+            conditions: translator.conditions(&self.intervals, self.filter.as_ref())?,
+            limit: Some(self.threshold),
+            ..Select::default()
+        };
+        let dimension = translator.group_by_dimension(&self.dimension, &mut select)?;
+        translator.aggregations(self.aggregations.as_deref(), &mut select)?;
+        translator.post_aggregations(self.post_aggregations.as_deref(), &mut select)?;
+        translator.topn_metric(&self.metric, &dimension, false, &mut select)?;
+        Ok(select.to_string())
+    }
 }
@@ -0,0 +1,1548 @@
+//! Translation of native queries into equivalent Druid SQL.
+//!
+//! Columns are written as quoted identifiers, native expressions are
+//! re-rendered with the SQL operators and functions, and `__time` is kept a
+//! `TIMESTAMP` except inside expressions, where it is converted to the
+//! milliseconds native expressions work with. Native constructs without an
+//! exact SQL equivalent, like JavaScript aggregators and most extraction
+//! functions, are reported as [`TranslationError::Unsupported`] rather than
+//! approximated.
+
+use std::{collections::HashMap, fmt};
+
+use crate::{
+    components::{
+        aggregations::Aggregator, data_sources::DataSource, dimension_specs::DimensionSpec,
+        druid_types::DruidNativeType, extraction_functions::ExtractionFunction, filters::Filter,
+        granularities::Granularity, having_specs::HavingSpec, intervals::Interval,
+        limit_specs::LimitSpec, ordering::Ordering, post_aggregations::PostAggregator,
+        search_query_specs::SearchQuerySpec, topn_metric_specs::TopNMetricSpec,
+        virtual_columns::VirtaulColumn,
+    },
+    expr::{BinaryOp, Expr, ExprType, ParseError, UnaryOp},
+};
+
+/// Why a query couldn't be translated.
+#[derive(Debug, Clone, thiserror::Error)]
+pub enum TranslationError {
+    #[error("{0} have no SQL equivalent")]
+    Unsupported(String),
+    #[error(transparent)]
+    Expression(#[from] ParseError),
+}
+
+fn unsupported(what: &str) -> TranslationError {
+    TranslationError::Unsupported(what.into())
+}
+
+/// Quote `name` as a SQL identifier.
+pub(crate) fn quote_identifier(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+/// Quote `value` as a SQL string literal.
+pub(crate) fn quote_string(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "''"))
+}
+
+/// `expression AS "name"`, or just the expression if it is the column
+/// `name` already.
+fn alias(expression: &str, name: &str) -> String {
+    let name = quote_identifier(name);
+    if expression == name {
+        name
+    } else {
+        format!("{expression} AS {name}")
+    }
+}
+
+fn literal(value: &DruidNativeType) -> String {
+    match value {
+        DruidNativeType::String(s) => quote_string(s),
+        DruidNativeType::Array(items) => format!(
+            "ARRAY[{}]",
+            items
+                .iter()
+                .map(|item| quote_string(item))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+        DruidNativeType::Long(l) => l.to_string(),
+        DruidNativeType::Float(f) => format!("{f:?}"),
+        DruidNativeType::Double(d) => format!("{d:?}"),
+    }
+}
+
+fn ordering_name(ordering: &Ordering) -> &'static str {
+    match ordering {
+        Ordering::Alphanumeric => "alphanumeric",
+        Ordering::Lexicographic => "lexicographic",
+        Ordering::Numeric => "numeric",
+        Ordering::Strlen => "strlen",
+        Ordering::Version => "version",
+    }
+}
+
+/// How a condition is joined at its top level, to know when it needs
+/// parentheses.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Junction {
+    None,
+    And,
+    Or,
+}
+
+/// The clauses of a `SELECT` statement.
+#[derive(Default)]
+pub(crate) struct Select {
+    pub(crate) columns: Vec<String>,
+    pub(crate) from: String,
+    pub(crate) conditions: Vec<String>,
+    pub(crate) group_by: Vec<String>,
+    pub(crate) having: Option<String>,
+    pub(crate) order_by: Vec<String>,
+    pub(crate) limit: Option<usize>,
+    pub(crate) offset: Option<usize>,
+}
+
+impl fmt::Display for Select {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "SELECT {}\nFROM {}", self.columns.join(", "), self.from)?;
+        if !self.conditions.is_empty() {
+            write!(f, "\nWHERE {}", self.conditions.join(" AND "))?;
+        }
+        if !self.group_by.is_empty() {
+            write!(f, "\nGROUP BY {}", self.group_by.join(", "))?;
+        }
+        if let Some(having) = &self.having {
+            write!(f, "\nHAVING {having}")?;
+        }
+        if !self.order_by.is_empty() {
+            write!(f, "\nORDER BY {}", self.order_by.join(", "))?;
+        }
+        if let Some(limit) = self.limit {
+            write!(f, "\nLIMIT {limit}")?;
+        }
+        if let Some(offset) = self.offset {
+            write!(f, "\nOFFSET {offset}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Translates the parts of a native query, keeping the SQL of the virtual
+/// columns, dimensions and aggregators defined so far.
+#[derive(Default)]
+pub(crate) struct Translator {
+    columns: HashMap<String, String>,
+    /// The known types of `columns` and datasource columns.
+    types: HashMap<String, ExprType>,
+    dimensions: HashMap<String, String>,
+    outputs: HashMap<String, String>,
+    output_types: HashMap<String, ExprType>,
+}
+
+impl Translator {
+    pub(crate) fn new(virtual_columns: Option<&[VirtaulColumn]>) -> Result<Self, TranslationError> {
+        let mut translator = Self::default();
+        translator.types.insert("__time".into(), ExprType::Long);
+        for column in virtual_columns.unwrap_or_default() {
+            let VirtaulColumn::Expression {
+                name,
+                expression,
+                output_type,
+            } = column;
+            let expr = Expr::parse(expression)?;
+            let sql = translator.expression(&expr)?;
+            let expr_type = match output_type {
+                Some(output_type) => ExprType::from_druid(output_type),
+                None => expr.output_type_with(&translator.types),
+            };
+            match expr_type {
+                Some(expr_type) => translator.types.insert(name.clone(), expr_type),
+                None => translator.types.remove(name),
+            };
+            translator.columns.insert(name.clone(), sql);
+        }
+        Ok(translator)
+    }
+
+    /// A translator whose columns are the dimensions and aggregators of this
+    /// one, for post-aggregators and having specs.
+    fn output_scope(&self) -> Self {
+        let mut columns = self.dimensions.clone();
+        columns.extend(self.outputs.clone());
+        Self {
+            columns,
+            types: self.output_types.clone(),
+            ..Self::default()
+        }
+    }
+
+    fn column(&self, name: &str) -> String {
+        match self.columns.get(name) {
+            Some(sql) => sql.clone(),
+            None => quote_identifier(name),
+        }
+    }
+
+    /// The virtual columns, as `expression AS "name"`.
+    pub(crate) fn virtual_columns(&self, virtual_columns: Option<&[VirtaulColumn]>) -> Vec<String> {
+        virtual_columns
+            .unwrap_or_default()
+            .iter()
+            .map(|VirtaulColumn::Expression { name, .. }| alias(&self.columns[name], name))
+            .collect()
+    }
+
+    /// A column of a scan query, with virtual columns aliased.
+    pub(crate) fn select_column(&self, name: &str) -> String {
+        alias(&self.column(name), name)
+    }
+
+    pub(crate) fn data_source(&self, data_source: &DataSource) -> Result<String, TranslationError> {
+        match data_source {
+            DataSource::Table { name } => Ok(quote_identifier(name)),
+            DataSource::Lookup { lookup } => Ok(format!("lookup.{}", quote_identifier(lookup))),
+            DataSource::Union { data_sources } => Ok(format!(
+                "TABLE(APPEND({}))",
+                data_sources
+                    .iter()
+                    .map(|name| quote_string(name))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )),
+            DataSource::Query(query) => Ok(format!("(\n{}\n)", query.to_sql()?)),
+            DataSource::Inline { .. } => Err(unsupported("inline data sources")),
+            DataSource::Join { .. } => Err(unsupported("join data sources")),
+        }
+    }
+
+    /// The `WHERE` conditions for the query intervals and filter.
+    pub(crate) fn conditions(
+        &self,
+        intervals: &[Interval],
+        filter: Option<&Filter>,
+    ) -> Result<Vec<String>, TranslationError> {
+        let mut conditions = Vec::new();
+        if !intervals.is_empty() {
+            let (sql, junction) = self.intervals("\"__time\"", intervals, false);
+            if junction == Junction::Or {
+                conditions.push(format!("({sql})"));
+            } else {
+                conditions.push(sql);
+            }
+        }
+        if let Some(filter) = filter {
+            conditions.extend(self.conjuncts(filter)?);
+        }
+        Ok(conditions)
+    }
+
+    /// The fields of a filter that are joined by `AND`, each parenthesized
+    /// as needed.
+    fn conjuncts(&self, filter: &Filter) -> Result<Vec<String>, TranslationError> {
+        match filter {
+            Filter::True => Ok(Vec::new()),
+            Filter::And { fields } => fields.iter().try_fold(Vec::new(), |mut conjuncts, field| {
+                conjuncts.extend(self.conjuncts(field)?);
+                Ok(conjuncts)
+            }),
+            filter => Ok(vec![self.filter_operand(filter, Junction::And)?]),
+        }
+    }
+
+    fn filter_operand(
+        &self,
+        filter: &Filter,
+        parent: Junction,
+    ) -> Result<String, TranslationError> {
+        let (sql, junction) = self.filter(filter)?;
+        if junction == Junction::None || junction == parent {
+            Ok(sql)
+        } else {
+            Ok(format!("({sql})"))
+        }
+    }
+
+    fn junction(
+        &self,
+        fields: &[Filter],
+        junction: Junction,
+    ) -> Result<(String, Junction), TranslationError> {
+        let (separator, empty) = match junction {
+            Junction::Or => (" OR ", "FALSE"),
+            _ => (" AND ", "TRUE"),
+        };
+        match fields {
+            [] => Ok((empty.into(), Junction::None)),
+            [field] => self.filter(field),
+            fields => Ok((
+                fields
+                    .iter()
+                    .map(|field| self.filter_operand(field, junction))
+                    .collect::<Result<Vec<_>, _>>()?
+                    .join(separator),
+                junction,
+            )),
+        }
+    }
+
+    fn filter(&self, filter: &Filter) -> Result<(String, Junction), TranslationError> {
+        let atom = |sql: String| Ok((sql, Junction::None));
+        match filter {
+            Filter::Selector {
+                dimension,
+                value,
+                extraction_fn,
+            } => {
+                let column = self.filtered_dimension(dimension, extraction_fn.as_ref())?;
+                atom(format!("{column} = {}", quote_string(value)))
+            }
+            Filter::ColumnComparison { dimensions } => {
+                let columns = dimensions
+                    .iter()
+                    .map(|dimension| self.dimension(dimension))
+                    .collect::<Result<Vec<_>, _>>()?;
+                let comparisons: Vec<_> = columns
+                    .windows(2)
+                    .map(|pair| format!("{} = {}", pair[0], pair[1]))
+                    .collect();
+                match comparisons.len() {
+                    0 => atom("TRUE".into()),
+                    1 => atom(comparisons.join("")),
+                    _ => Ok((comparisons.join(" AND "), Junction::And)),
+                }
+            }
+            Filter::Regex {
+                dimension,
+                pattern,
+                extraction_fn,
+            } => {
+                let column = self.filtered_dimension(dimension, extraction_fn.as_ref())?;
+                atom(format!("REGEXP_LIKE({column}, {})", quote_string(pattern)))
+            }
+            Filter::And { fields } => self.junction(fields, Junction::And),
+            Filter::Or { fields } => self.junction(fields, Junction::Or),
+            Filter::Not { field } => atom(format!(
+                "NOT {}",
+                self.filter_operand(field, Junction::None)?
+            )),
+            Filter::Javascript { .. } => Err(unsupported("JavaScript filters")),
+            Filter::Extraction {
+                dimension,
+                value,
+                extraction_fn,
+            } => {
+                let column = self.filtered_dimension(dimension, Some(extraction_fn))?;
+                atom(format!("{column} = {}", quote_string(value)))
+            }
+            Filter::Search {
+                dimension,
+                query,
+                extraction_fn,
+            } => {
+                let column = self.filtered_dimension(dimension, extraction_fn.as_ref())?;
+                let contains = |value: &DruidNativeType, case_sensitive: bool| {
+                    let value = match value {
+                        DruidNativeType::String(s) => quote_string(s),
+                        other => quote_string(&literal(other)),
+                    };
+                    if case_sensitive {
+                        format!("CONTAINS_STRING({column}, {value})")
+                    } else {
+                        format!("ICONTAINS_STRING({column}, {value})")
+                    }
+                };
+                match query {
+                    SearchQuerySpec::InsensitiveContains { value } => atom(contains(value, false)),
+                    SearchQuerySpec::Contains {
+                        case_sensitive,
+                        value,
+                    } => atom(contains(value, *case_sensitive)),
+                    SearchQuerySpec::Fragment {
+                        case_sensitive,
+                        values,
+                    } => match values.as_slice() {
+                        [] => atom("TRUE".into()),
+                        [value] => atom(contains(value, *case_sensitive)),
+                        values => Ok((
+                            values
+                                .iter()
+                                .map(|value| contains(value, *case_sensitive))
+                                .collect::<Vec<_>>()
+                                .join(" AND "),
+                            Junction::And,
+                        )),
+                    },
+                    SearchQuerySpec::Regex { pattern } => {
+                        atom(format!("REGEXP_LIKE({column}, {})", quote_string(pattern)))
+                    }
+                }
+            }
+            Filter::In { dimension, values } => {
+                if values.is_empty() {
+                    return atom("FALSE".into());
+                }
+                let column = self.dimension(dimension)?;
+                let values: Vec<_> = values.iter().map(|value| quote_string(value)).collect();
+                atom(format!("{column} IN ({})", values.join(", ")))
+            }
+            Filter::Like {
+                dimension,
+                pattern,
+                escape,
+                extraction_fn,
+            } => {
+                let column = self.extracted(self.column(dimension), extraction_fn.as_ref())?;
+                let mut sql = format!("{column} LIKE {}", quote_string(pattern));
+                if let Some(escape) = escape {
+                    sql.push_str(&format!(" ESCAPE {}", quote_string(escape)));
+                }
+                atom(sql)
+            }
+            Filter::Bound {
+                dimension,
+                lower,
+                upper,
+                lower_strict,
+                upper_strict,
+                ordering,
+                extraction_fn,
+            } => {
+                let column = self.extracted(self.column(dimension), extraction_fn.as_ref())?;
+                let numeric = match ordering {
+                    None | Some(Ordering::Lexicographic) => false,
+                    Some(Ordering::Numeric) => true,
+                    Some(ordering) => {
+                        return Err(TranslationError::Unsupported(format!(
+                            "bound filters with the {} ordering",
+                            ordering_name(ordering)
+                        )))
+                    }
+                };
+                let column = if numeric {
+                    format!("CAST({column} AS DOUBLE)")
+                } else {
+                    column
+                };
+                let value = |value: &String| {
+                    if !numeric {
+                        Ok(quote_string(value))
+                    } else if value.parse::<f64>().is_ok() {
+                        Ok(value.clone())
+                    } else {
+                        Err(TranslationError::Unsupported(format!(
+                            "numeric bounds on non-numbers like {value}"
+                        )))
+                    }
+                };
+                let mut comparisons = Vec::new();
+                if let Some(lower) = lower {
+                    let op = if lower_strict.unwrap_or(false) {
+                        ">"
+                    } else {
+                        ">="
+                    };
+                    comparisons.push(format!("{column} {op} {}", value(lower)?));
+                }
+                if let Some(upper) = upper {
+                    let op = if upper_strict.unwrap_or(false) {
+                        "<"
+                    } else {
+                        "<="
+                    };
+                    comparisons.push(format!("{column} {op} {}", value(upper)?));
+                }
+                match comparisons.len() {
+                    0 => atom("TRUE".into()),
+                    1 => atom(comparisons.join("")),
+                    _ => Ok((comparisons.join(" AND "), Junction::And)),
+                }
+            }
+            Filter::Interval {
+                dimension,
+                intervals,
+                extraction_fn,
+            } => {
+                if extraction_fn.is_some() {
+                    return Err(unsupported("interval filters with extraction functions"));
+                }
+                let millis = dimension != "__time";
+                Ok(self.intervals(&self.column(dimension), intervals, millis))
+            }
+            Filter::Expression { expression } => atom(self.expression(&Expr::parse(expression)?)?),
+            Filter::True => atom("TRUE".into()),
+            Filter::Spatial { .. } => Err(unsupported("spatial filters")),
+        }
+    }
+
+    /// Conditions matching `column` to any of `intervals`, comparing with
+    /// epoch milliseconds if `millis` is true and timestamps otherwise.
+    fn intervals(&self, column: &str, intervals: &[Interval], millis: bool) -> (String, Junction) {
+        let instant = |instant: String| {
+            if millis {
+                format!("TIMESTAMP_TO_MILLIS({instant})")
+            } else {
+                instant
+            }
+        };
+        let ranges: Vec<_> = intervals
+            .iter()
+            .map(|interval| {
+                let (start, end) = interval_bounds(interval);
+                format!(
+                    "{column} >= {} AND {column} < {}",
+                    instant(start),
+                    instant(end)
+                )
+            })
+            .collect();
+        match ranges.as_slice() {
+            [] => ("FALSE".into(), Junction::None),
+            [range] => (range.clone(), Junction::And),
+            ranges => (
+                ranges
+                    .iter()
+                    .map(|range| format!("({range})"))
+                    .collect::<Vec<_>>()
+                    .join(" OR "),
+                Junction::Or,
+            ),
+        }
+    }
+
+    /// The column a dimension spec reads, with its transformations applied.
+    pub(crate) fn dimension(&self, dimension: &DimensionSpec) -> Result<String, TranslationError> {
+        match dimension {
+            DimensionSpec::Default { dimension, .. } => Ok(self.column(dimension)),
+            DimensionSpec::Extraction {
+                dimension,
+                extraction_fn,
+                ..
+            } => self.extracted(self.column(dimension), Some(extraction_fn)),
+            DimensionSpec::ListFiltered {
+                delegate,
+                values,
+                is_whitelist,
+            } => {
+                let function = if is_whitelist.unwrap_or(true) {
+                    "MV_FILTER_ONLY"
+                } else {
+                    "MV_FILTER_NONE"
+                };
+                let values: Vec<_> = values.iter().map(|value| quote_string(value)).collect();
+                Ok(format!(
+                    "{function}({}, ARRAY[{}])",
+                    self.dimension(delegate)?,
+                    values.join(", ")
+                ))
+            }
+            DimensionSpec::RegexFiltered { .. } | DimensionSpec::PrefixFiltered { .. } => {
+                Err(unsupported("regex and prefix filtered dimensions"))
+            }
+            DimensionSpec::Lookup {
+                dimension,
+                retain_missing_value,
+                replace_missing_value_with,
+                lookup: None,
+                name: Some(name),
+                ..
+            } => Ok(lookup(
+                self.column(dimension),
+                name,
+                *retain_missing_value,
+                replace_missing_value_with.as_ref(),
+            )),
+            DimensionSpec::Lookup { .. } => Err(unsupported("inline lookups")),
+        }
+    }
+
+    fn filtered_dimension(
+        &self,
+        dimension: &DimensionSpec,
+        extraction_fn: Option<&ExtractionFunction>,
+    ) -> Result<String, TranslationError> {
+        self.extracted(self.dimension(dimension)?, extraction_fn)
+    }
+
+    /// Add a dimension to the selected and grouped by columns.
+    pub(crate) fn group_by_dimension(
+        &mut self,
+        dimension: &DimensionSpec,
+        select: &mut Select,
+    ) -> Result<String, TranslationError> {
+        let sql = self.dimension(dimension)?;
+        select.columns.push(alias(&sql, dimension.output_name()));
+        select.group_by.push(sql.clone());
+        self.dimensions
+            .insert(dimension.output_name().into(), sql.clone());
+        Ok(sql)
+    }
+
+    fn extracted(
+        &self,
+        input: String,
+        extraction_fn: Option<&ExtractionFunction>,
+    ) -> Result<String, TranslationError> {
+        let Some(extraction_fn) = extraction_fn else {
+            return Ok(input);
+        };
+        match extraction_fn {
+            ExtractionFunction::Regex {
+                expr,
+                index,
+                replace_missing_value,
+                replace_missing_value_with,
+            } => {
+                let extracted = format!(
+                    "REGEXP_EXTRACT({input}, {}, {})",
+                    quote_string(expr),
+                    index.unwrap_or(1)
+                );
+                match (replace_missing_value, replace_missing_value_with) {
+                    (Some(true), None) => Ok(extracted),
+                    (Some(true), Some(with)) => {
+                        Ok(format!("COALESCE({extracted}, {})", literal(with)))
+                    }
+                    _ => Ok(format!("COALESCE({extracted}, {input})")),
+                }
+            }
+            ExtractionFunction::Partial { expr } => Ok(format!(
+                "CASE WHEN REGEXP_LIKE({input}, {}) THEN {input} END",
+                quote_string(expr)
+            )),
+            ExtractionFunction::Substring { index, length } => match length {
+                Some(length) => Ok(format!("SUBSTRING({input}, {}, {length})", index + 1)),
+                None => Ok(format!("SUBSTRING({input}, {})", index + 1)),
+            },
+            ExtractionFunction::Strlen => Ok(format!("CHAR_LENGTH({input})")),
+            ExtractionFunction::Upper { locale: None } => Ok(format!("UPPER({input})")),
+            ExtractionFunction::Lower { locale: None } => Ok(format!("LOWER({input})")),
+            ExtractionFunction::Upper { .. } | ExtractionFunction::Lower { .. } => {
+                Err(unsupported("locale specific case conversions"))
+            }
+            ExtractionFunction::TimeFormat {
+                format,
+                time_zone,
+                locale: None,
+                granularity,
+                as_millis: None | Some(false),
+            } => {
+                let mut timestamp = if input == "\"__time\"" {
+                    input
+                } else {
+                    format!("MILLIS_TO_TIMESTAMP({input})")
+                };
+                if let Some(granularity) = granularity {
+                    timestamp = self
+                        .time_floor(&timestamp, granularity)?
+                        .ok_or_else(|| unsupported("time formats with the all granularity"))?;
+                }
+                let mut args = vec![timestamp];
+                if format.is_some() || time_zone.is_some() {
+                    // Druid's default, ISO 8601 with milliseconds.
+                    let format = format.as_deref().unwrap_or("yyyy-MM-dd'T'HH:mm:ss.SSSZZ");
+                    args.push(quote_string(format));
+                }
+                args.extend(time_zone.as_deref().map(quote_string));
+                Ok(format!("TIME_FORMAT({})", args.join(", ")))
+            }
+            ExtractionFunction::TimeFormat { .. } => {
+                Err(unsupported("time formats with a locale or as milliseconds"))
+            }
+            ExtractionFunction::RegisteredLookup {
+                lookup: name,
+                retain_missing_value,
+                replace_missing_value_with,
+                ..
+            } => Ok(lookup(
+                input,
+                name,
+                *retain_missing_value,
+                replace_missing_value_with.as_ref(),
+            )),
+            ExtractionFunction::Cascade { extraction_fns } => extraction_fns
+                .iter()
+                .try_fold(input, |input, extraction_fn| {
+                    self.extracted(input, Some(extraction_fn))
+                }),
+            ExtractionFunction::SearchQuery { .. } => {
+                Err(unsupported("search query extraction functions"))
+            }
+            ExtractionFunction::Time { .. } => {
+                Err(unsupported("time parsing extraction functions"))
+            }
+            ExtractionFunction::Javascript { .. } => {
+                Err(unsupported("JavaScript extraction functions"))
+            }
+            ExtractionFunction::Lookup { .. } => Err(unsupported("inline lookups")),
+            ExtractionFunction::StringFormat { .. } => {
+                Err(unsupported("string format extraction functions"))
+            }
+            ExtractionFunction::Bucket { .. } => Err(unsupported("bucket extraction functions")),
+        }
+    }
+
+    /// `TIME_FLOOR` of `timestamp` to the granularity, `None` for `all`.
+    pub(crate) fn time_floor(
+        &self,
+        timestamp: &str,
+        granularity: &Granularity,
+    ) -> Result<Option<String>, TranslationError> {
+        let (period, origin, time_zone) = match granularity {
+            Granularity::Simple(simple) => {
+                let period = match simple.to_lowercase().as_str() {
+                    "all" => return Ok(None),
+                    "none" => return Ok(Some(timestamp.into())),
+                    "second" => "PT1S",
+                    "minute" => "PT1M",
+                    "fifteen_minute" => "PT15M",
+                    "thirty_minute" => "PT30M",
+                    "hour" => "PT1H",
+                    "day" => "P1D",
+                    "week" => "P1W",
+                    "month" => "P1M",
+                    "quarter" => "P3M",
+                    "year" => "P1Y",
+                    _ => {
+                        return Err(TranslationError::Unsupported(format!(
+                            "granularities like {simple}"
+                        )))
+                    }
+                };
+                (period.to_string(), None, None)
+            }
+            Granularity::Duration { duration, origin } => {
+                let period = if duration % 1000 == 0 {
+                    format!("PT{}S", duration / 1000)
+                } else {
+                    format!("PT{}.{:03}S", duration / 1000, duration % 1000)
+                };
+                (period, origin.as_deref(), None)
+            }
+            Granularity::Period {
+                period,
+                origin,
+                time_zone,
+            } => (period.clone(), origin.as_deref(), time_zone.as_deref()),
+        };
+        let mut args = vec![timestamp.to_string(), quote_string(&period)];
+        match (origin, time_zone) {
+            (None, None) => {}
+            (origin, time_zone) => {
+                args.push(origin.map_or("NULL".into(), |origin| {
+                    format!("TIME_PARSE({})", quote_string(origin))
+                }));
+                args.extend(time_zone.map(quote_string));
+            }
+        }
+        Ok(Some(format!("TIME_FLOOR({})", args.join(", "))))
+    }
+
+    /// Select and group by the time bucket of the granularity, if there is
+    /// one.
+    pub(crate) fn group_by_time(
+        &self,
+        granularity: &Granularity,
+        select: &mut Select,
+    ) -> Result<Option<String>, TranslationError> {
+        let time = self.time_floor("\"__time\"", granularity)?;
+        if let Some(time) = &time {
+            select.columns.push(alias(time, "__time"));
+            select.group_by.push(time.clone());
+        }
+        Ok(time)
+    }
+
+    /// The `GROUPING SETS` of a groupBy subtotals spec, each including the
+    /// time bucket if there is one.
+    pub(crate) fn grouping_sets(
+        &self,
+        subtotals_spec: &[Vec<String>],
+        time: Option<&str>,
+    ) -> String {
+        let sets: Vec<_> = subtotals_spec
+            .iter()
+            .map(|dimensions| {
+                let columns: Vec<_> = time
+                    .map(str::to_string)
+                    .into_iter()
+                    .chain(
+                        dimensions
+                            .iter()
+                            .map(|name| match self.dimensions.get(name) {
+                                Some(sql) => sql.clone(),
+                                None => quote_identifier(name),
+                            }),
+                    )
+                    .collect();
+                format!("({})", columns.join(", "))
+            })
+            .collect();
+        format!("GROUPING SETS ({})", sets.join(", "))
+    }
+
+    fn aggregator(&self, aggregator: &Aggregator) -> Result<String, TranslationError> {
+        let call = |function: &str, field_name: &str| {
+            Ok(format!("{function}({})", self.column(field_name)))
+        };
+        let sized = |function: &str, field_name: &str, max_string_bytes: &Option<u64>| {
+            Ok(format!(
+                "{function}({}, {})",
+                self.column(field_name),
+                max_string_bytes.unwrap_or(1024)
+            ))
+        };
+        match aggregator {
+            Aggregator::Count { .. } => Ok("COUNT(*)".into()),
+            Aggregator::LongSum { field_name, .. }
+            | Aggregator::DoubleSum { field_name, .. }
+            | Aggregator::FloatSum { field_name, .. } => call("SUM", field_name),
+            Aggregator::LongMin { field_name, .. }
+            | Aggregator::DoubleMin { field_name, .. }
+            | Aggregator::FloatMin { field_name, .. } => call("MIN", field_name),
+            Aggregator::LongMax { field_name, .. }
+            | Aggregator::DoubleMax { field_name, .. }
+            | Aggregator::FloatMax { field_name, .. } => call("MAX", field_name),
+            Aggregator::DoubleMean { field_name, .. } => call("AVG", field_name),
+            Aggregator::LongFirst { field_name, .. }
+            | Aggregator::DoubleFirst { field_name, .. }
+            | Aggregator::FloatFirst { field_name, .. } => call("EARLIEST", field_name),
+            Aggregator::StringFirst {
+                field_name,
+                max_string_bytes,
+                ..
+            } => sized("EARLIEST", field_name, max_string_bytes),
+            Aggregator::LongLast { field_name, .. }
+            | Aggregator::DoubleLast { field_name, .. }
+            | Aggregator::FloatLast { field_name, .. } => call("LATEST", field_name),
+            Aggregator::StringLast {
+                field_name,
+                max_string_bytes,
+                ..
+            } => sized("LATEST", field_name, max_string_bytes),
+            Aggregator::LongAny { field_name, .. }
+            | Aggregator::DoubleAny { field_name, .. }
+            | Aggregator::FloatAny { field_name, .. } => call("ANY_VALUE", field_name),
+            Aggregator::StringAny {
+                field_name,
+                max_string_bytes,
+                ..
+            } => sized("ANY_VALUE", field_name, max_string_bytes),
+            Aggregator::Javascript { .. } => Err(unsupported("JavaScript aggregators")),
+            Aggregator::Filtered { .. } => {
+                // Nested filtered aggregators share a single FILTER clause.
+                let mut filters = Vec::new();
+                let mut aggregator = aggregator;
+                while let Aggregator::Filtered {
+                    filter,
+                    aggregator: inner,
+                } = aggregator
+                {
+                    filters.push(filter.as_ref().clone());
+                    aggregator = inner;
+                }
+                let conditions = self.conjuncts(&Filter::and(filters))?;
+                let aggregator = self.aggregator(aggregator)?;
+                if conditions.is_empty() {
+                    Ok(aggregator)
+                } else {
+                    Ok(format!(
+                        "{aggregator} FILTER(WHERE {})",
+                        conditions.join(" AND ")
+                    ))
+                }
+            }
+            Aggregator::Grouping { groupings, .. } => Ok(format!(
+                "GROUPING({})",
+                groupings
+                    .iter()
+                    .map(|name| match self.dimensions.get(name) {
+                        Some(sql) => sql.clone(),
+                        None => quote_identifier(name),
+                    })
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )),
+            Aggregator::Cardinality { fields, .. } => match fields.as_slice() {
+                [field] => Ok(format!("APPROX_COUNT_DISTINCT({})", self.dimension(field)?)),
+                _ => Err(unsupported("cardinality aggregators over several fields")),
+            },
+            Aggregator::HyperUnique { field_name, .. } => call("APPROX_COUNT_DISTINCT", field_name),
+        }
+    }
+
+    pub(crate) fn aggregations(
+        &mut self,
+        aggregations: Option<&[Aggregator]>,
+        select: &mut Select,
+    ) -> Result<(), TranslationError> {
+        for aggregator in aggregations.unwrap_or_default() {
+            let sql = self.aggregator(aggregator)?;
+            select.columns.push(alias(&sql, aggregator.name()));
+            if let Some(output_type) = aggregator_type(aggregator) {
+                self.output_types
+                    .insert(aggregator.name().into(), output_type);
+            }
+            self.outputs.insert(aggregator.name().into(), sql);
+        }
+        Ok(())
+    }
+
+    fn post_aggregator(
+        &self,
+        post_aggregator: &PostAggregator,
+    ) -> Result<String, TranslationError> {
+        let fields = |fields: &[PostAggregator]| {
+            fields
+                .iter()
+                .map(|field| self.post_aggregator(field))
+                .collect::<Result<Vec<_>, _>>()
+        };
+        match post_aggregator {
+            PostAggregator::Arithmetic {
+                func, fields: f, ..
+            } => {
+                let mut operands = fields(f)?.into_iter();
+                let first = operands
+                    .next()
+                    .ok_or_else(|| unsupported("arithmetic post-aggregators without fields"))?;
+                operands.try_fold(first, |left, right| match func.as_str() {
+                    "+" | "-" | "*" => Ok(format!("({left} {func} {right})")),
+                    // Native division is zero when dividing by zero.
+                    "/" => Ok(format!(
+                        "CASE WHEN {right} = 0 THEN 0 ELSE CAST({left} AS DOUBLE) / {right} END"
+                    )),
+                    "quotient" => Ok(format!("(CAST({left} AS DOUBLE) / {right})")),
+                    "pow" => Ok(format!("POWER({left}, {right})")),
+                    func => Err(TranslationError::Unsupported(format!(
+                        "arithmetic post-aggregators like {func}"
+                    ))),
+                })
+            }
+            PostAggregator::FieldAccess { field_name, .. }
+            | PostAggregator::FinalizingFieldAccess { field_name, .. }
+            | PostAggregator::HyperUniqueCardinality { field_name, .. } => {
+                Ok(self.output_scope().column(field_name))
+            }
+            PostAggregator::Constant { value, .. } => Ok(literal(value)),
+            PostAggregator::LongGreatest { fields: f, .. }
+            | PostAggregator::DoubleGreatest { fields: f, .. } => {
+                Ok(format!("GREATEST({})", fields(f)?.join(", ")))
+            }
+            PostAggregator::LongLeast { fields: f, .. }
+            | PostAggregator::DoubleLeast { fields: f, .. } => {
+                Ok(format!("LEAST({})", fields(f)?.join(", ")))
+            }
+            PostAggregator::Javascript { .. } => Err(unsupported("JavaScript post-aggregators")),
+            PostAggregator::Expression { expression, .. } => {
+                self.output_scope().expression(&Expr::parse(expression)?)
+            }
+        }
+    }
+
+    pub(crate) fn post_aggregations(
+        &mut self,
+        post_aggregations: Option<&[PostAggregator]>,
+        select: &mut Select,
+    ) -> Result<(), TranslationError> {
+        for post_aggregator in post_aggregations.unwrap_or_default() {
+            let sql = self.post_aggregator(post_aggregator)?;
+            select.columns.push(alias(&sql, post_aggregator.name()));
+            match self.post_aggregator_type(post_aggregator) {
+                Some(output_type) => self
+                    .output_types
+                    .insert(post_aggregator.name().into(), output_type),
+                None => self.output_types.remove(post_aggregator.name()),
+            };
+            self.outputs.insert(post_aggregator.name().into(), sql);
+        }
+        Ok(())
+    }
+
+    fn post_aggregator_type(&self, post_aggregator: &PostAggregator) -> Option<ExprType> {
+        match post_aggregator {
+            PostAggregator::Arithmetic { .. }
+            | PostAggregator::DoubleGreatest { .. }
+            | PostAggregator::DoubleLeast { .. }
+            | PostAggregator::HyperUniqueCardinality { .. } => Some(ExprType::Double),
+            PostAggregator::LongGreatest { .. } | PostAggregator::LongLeast { .. } => {
+                Some(ExprType::Long)
+            }
+            PostAggregator::FieldAccess { field_name, .. }
+            | PostAggregator::FinalizingFieldAccess { field_name, .. } => {
+                self.output_types.get(field_name).copied()
+            }
+            PostAggregator::Expression { expression, .. } => Expr::parse(expression)
+                .ok()?
+                .output_type_with(&self.output_types),
+            PostAggregator::Constant { .. } | PostAggregator::Javascript { .. } => None,
+        }
+    }
+
+    pub(crate) fn having(&self, having: &HavingSpec) -> Result<String, TranslationError> {
+        Ok(self.output_scope().having_condition(having)?.0)
+    }
+
+    fn having_condition(
+        &self,
+        having: &HavingSpec,
+    ) -> Result<(String, Junction), TranslationError> {
+        let comparison = |column: &str, op: &str, value: &DruidNativeType| {
+            Ok((
+                format!("{} {op} {}", self.column(column), literal(value)),
+                Junction::None,
+            ))
+        };
+        let junction = |specs: &[HavingSpec], junction: Junction| {
+            let separator = if junction == Junction::And {
+                " AND "
+            } else {
+                " OR "
+            };
+            let conditions = specs
+                .iter()
+                .map(|spec| self.having_operand(spec, junction))
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok((conditions.join(separator), junction))
+        };
+        match having {
+            HavingSpec::Filter { filter } => self.filter(filter),
+            HavingSpec::EqualTo { aggregation, value } => comparison(aggregation, "=", value),
+            HavingSpec::GreaterThan { aggregation, value } => comparison(aggregation, ">", value),
+            HavingSpec::LessThan { aggregation, value } => comparison(aggregation, "<", value),
+            HavingSpec::DimSelector { dimension, value } => comparison(dimension, "=", value),
+            HavingSpec::And { having_specs } => junction(having_specs, Junction::And),
+            HavingSpec::Or { having_specs } => junction(having_specs, Junction::Or),
+            HavingSpec::Not { having_spec } => Ok((
+                format!("NOT {}", self.having_operand(having_spec, Junction::None)?),
+                Junction::None,
+            )),
+        }
+    }
+
+    fn having_operand(
+        &self,
+        having: &HavingSpec,
+        parent: Junction,
+    ) -> Result<String, TranslationError> {
+        let (sql, junction) = self.having_condition(having)?;
+        if junction == Junction::None || junction == parent {
+            Ok(sql)
+        } else {
+            Ok(format!("({sql})"))
+        }
+    }
+
+    /// Set the `ORDER BY`, `LIMIT` and `OFFSET` of a groupBy query.
+    pub(crate) fn limit_spec(
+        &self,
+        limit_spec: &LimitSpec,
+        select: &mut Select,
+    ) -> Result<(), TranslationError> {
+        match limit_spec {
+            LimitSpec::Noop => Ok(()),
+            LimitSpec::Default {
+                limit,
+                offset,
+                columns,
+            } => {
+                for column in columns.as_deref().unwrap_or_default() {
+                    self.limit_spec(column, select)?;
+                }
+                select.limit = *limit;
+                select.offset = *offset;
+                Ok(())
+            }
+            LimitSpec::OrderByColumnSpec {
+                dimension,
+                direction,
+                dimension_order,
+            } => {
+                let column = match (dimension_order, self.dimensions.get(dimension)) {
+                    (Some(Ordering::Numeric), Some(sql)) => format!("CAST({sql} AS DOUBLE)"),
+                    (None | Some(Ordering::Lexicographic | Ordering::Numeric), _) => {
+                        quote_identifier(dimension)
+                    }
+                    (Some(ordering), _) => {
+                        return Err(TranslationError::Unsupported(format!(
+                            "orderings like {}",
+                            ordering_name(ordering)
+                        )))
+                    }
+                };
+                let direction = if direction.eq_ignore_ascii_case("descending") {
+                    "DESC"
+                } else {
+                    "ASC"
+                };
+                select.order_by.push(format!("{column} {direction}"));
+                Ok(())
+            }
+        }
+    }
+
+    /// Set the `ORDER BY` of a topN query, and the condition for its
+    /// `previous_stop`.
+    pub(crate) fn topn_metric(
+        &self,
+        metric: &TopNMetricSpec,
+        dimension: &str,
+        inverted: bool,
+        select: &mut Select,
+    ) -> Result<(), TranslationError> {
+        let (ascending, descending) = if inverted {
+            ("DESC", "ASC")
+        } else {
+            ("ASC", "DESC")
+        };
+        match metric {
+            TopNMetricSpec::Numeric { metric } => {
+                select
+                    .order_by
+                    .push(format!("{} {descending}", quote_identifier(metric)));
+            }
+            TopNMetricSpec::Dimension {
+                ordering,
+                previous_stop,
+            } => {
+                let (column, stop) = match ordering {
+                    None | Some(Ordering::Lexicographic) => (
+                        dimension.to_string(),
+                        previous_stop.as_deref().map(quote_string),
+                    ),
+                    Some(Ordering::Numeric) => (
+                        format!("CAST({dimension} AS DOUBLE)"),
+                        previous_stop
+                            .as_deref()
+                            .map(|stop| format!("CAST({} AS DOUBLE)", quote_string(stop))),
+                    ),
+                    Some(ordering) => {
+                        return Err(TranslationError::Unsupported(format!(
+                            "orderings like {}",
+                            ordering_name(ordering)
+                        )))
+                    }
+                };
+                if let Some(stop) = stop {
+                    let op = if inverted { "<" } else { ">" };
+                    select.conditions.push(format!("{column} {op} {stop}"));
+                }
+                select.order_by.push(format!("{column} {ascending}"));
+            }
+            TopNMetricSpec::Inverted { metric } => {
+                self.topn_metric(metric, dimension, !inverted, select)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn expressions(&self, exprs: &[Expr]) -> Result<Vec<String>, TranslationError> {
+        exprs.iter().map(|expr| self.expression(expr)).collect()
+    }
+
+    /// Render a native expression as SQL, with binary operations always
+    /// parenthesized so that the result can be embedded anywhere.
+    fn expression(&self, expr: &Expr) -> Result<String, TranslationError> {
+        match expr {
+            Expr::Null => Ok("NULL".into()),
+            Expr::Long(l) => Ok(l.to_string()),
+            Expr::Double(d) if d.is_finite() => Ok(format!("{d:?}")),
+            Expr::Double(_) => Err(unsupported("NaN and infinite literals")),
+            Expr::String(s) => Ok(quote_string(s)),
+            Expr::Array(items) => Ok(format!("ARRAY[{}]", self.expressions(items)?.join(", "))),
+            Expr::Identifier(name) if name == "__time" && !self.columns.contains_key(name) => {
+                Ok("TIMESTAMP_TO_MILLIS(\"__time\")".into())
+            }
+            Expr::Identifier(name) => Ok(self.column(name)),
+            Expr::Unary {
+                op: UnaryOp::Neg,
+                expr,
+            } => Ok(format!("-{}", self.expression(expr)?)),
+            Expr::Unary {
+                op: UnaryOp::Not,
+                expr,
+            } => Ok(format!("(NOT {})", self.expression(expr)?)),
+            Expr::Binary { op, left, right } => {
+                let types = (
+                    left.output_type_with(&self.types),
+                    right.output_type_with(&self.types),
+                );
+                let (left, right) = (self.expression(left)?, self.expression(right)?);
+                Ok(match op {
+                    BinaryOp::Pow => format!("POWER({left}, {right})"),
+                    BinaryOp::Mod => format!("MOD({left}, {right})"),
+                    // Native `+` concatenates as soon as one operand is a
+                    // string, so it is only `+` in SQL for numbers.
+                    BinaryOp::Add => match types {
+                        (Some(ExprType::String), _) | (_, Some(ExprType::String)) => {
+                            format!("CONCAT({left}, {right})")
+                        }
+                        (
+                            Some(ExprType::Long | ExprType::Double),
+                            Some(ExprType::Long | ExprType::Double),
+                        ) => format!("({left} + {right})"),
+                        _ => return Err(unsupported("additions of operands of unknown types")),
+                    },
+                    BinaryOp::Eq => format!("({left} = {right})"),
+                    BinaryOp::Ne => format!("({left} <> {right})"),
+                    BinaryOp::And => format!("({left} AND {right})"),
+                    BinaryOp::Or => format!("({left} OR {right})"),
+                    op => format!("({left} {} {right})", op.symbol()),
+                })
+            }
+            Expr::Function { name, args } => self.function(name, args),
+            Expr::Lambda { .. } => Err(unsupported("lambdas")),
+        }
+    }
+
+    fn function(&self, name: &str, args: &[Expr]) -> Result<String, TranslationError> {
+        let sql = self.expressions(args)?;
+        let call = |function: &str| format!("{function}({})", sql.join(", "));
+        let cases = |pairs: &[String]| {
+            let mut case = String::new();
+            for pair in pairs.chunks(2) {
+                match pair {
+                    [condition, result] => {
+                        case.push_str(&format!(" WHEN {condition} THEN {result}"))
+                    }
+                    [otherwise] => case.push_str(&format!(" ELSE {otherwise}")),
+                    _ => unreachable!(),
+                }
+            }
+            case
+        };
+        let invalid =
+            || TranslationError::Unsupported(format!("calls of {name} with these arguments"));
+        match (name.to_ascii_lowercase().as_str(), args) {
+            (
+                "abs" | "ceil" | "floor" | "sqrt" | "exp" | "log10" | "round" | "lower" | "upper"
+                | "concat" | "nvl" | "greatest" | "least" | "replace" | "left" | "right"
+                | "reverse" | "repeat" | "lpad" | "rpad" | "strpos" | "regexp_extract"
+                | "regexp_like" | "lookup" | "json_query" | "safe_divide" | "array_length"
+                | "array_offset" | "array_ordinal" | "array_contains" | "array_overlap"
+                | "array_offset_of" | "array_ordinal_of" | "array_append" | "array_prepend"
+                | "array_concat" | "array_slice" | "array_to_string" | "string_to_array",
+                _,
+            ) => Ok(call(&name.to_uppercase())),
+            ("trim" | "ltrim" | "rtrim", [_]) => Ok(call(&name.to_uppercase())),
+            ("strlen", _) => Ok(call("CHAR_LENGTH")),
+            ("pow", _) => Ok(call("POWER")),
+            ("log", _) => Ok(call("LN")),
+            ("isnull", [_]) => Ok(format!("({} IS NULL)", sql[0])),
+            ("notnull", [_]) => Ok(format!("({} IS NOT NULL)", sql[0])),
+            ("if", [_, _, _]) => Ok(format!(
+                "CASE WHEN {} THEN {} ELSE {} END",
+                sql[0], sql[1], sql[2]
+            )),
+            ("case_searched", _) => Ok(format!("CASE{} END", cases(&sql))),
+            ("case_simple", [_, ..]) => Ok(format!("CASE {}{} END", sql[0], cases(&sql[1..]))),
+            ("substring", [_, index, length]) => {
+                let start = match index {
+                    Expr::Long(index) => (index + 1).to_string(),
+                    _ => format!("({} + 1)", sql[1]),
+                };
+                match length {
+                    // A negative length takes the rest of the string.
+                    Expr::Long(length) if *length < 0 => {
+                        Ok(format!("SUBSTRING({}, {start})", sql[0]))
+                    }
+                    _ => Ok(format!("SUBSTRING({}, {start}, {})", sql[0], sql[2])),
+                }
+            }
+            ("cast", [_, Expr::String(to)]) => Ok(format!(
+                "CAST({} AS {})",
+                sql[0],
+                sql_type(to).ok_or_else(invalid)?
+            )),
+            ("json_value", [_, _]) => Ok(call("JSON_VALUE")),
+            ("json_value", [_, _, Expr::String(returning)]) => Ok(format!(
+                "JSON_VALUE({}, {} RETURNING {})",
+                sql[0],
+                sql[1],
+                sql_type(returning).ok_or_else(invalid)?
+            )),
+            ("timestamp_floor", [timestamp, _, rest @ ..]) if rest.len() <= 2 => {
+                let mut time_args = vec![
+                    match timestamp {
+                        Expr::Identifier(name) if name == "__time" => "\"__time\"".into(),
+                        _ => format!("MILLIS_TO_TIMESTAMP({})", sql[0]),
+                    },
+                    sql[1].clone(),
+                ];
+                match rest {
+                    [] => {}
+                    [Expr::Null, ..] => time_args.push("NULL".into()),
+                    [_, ..] => time_args.push(format!("MILLIS_TO_TIMESTAMP({})", sql[2])),
+                }
+                time_args.extend(sql.get(3).cloned());
+                Ok(format!(
+                    "TIMESTAMP_TO_MILLIS(TIME_FLOOR({}))",
+                    time_args.join(", ")
+                ))
+            }
+            _ => Err(TranslationError::Unsupported(format!(
+                "expression functions like {name}"
+            ))),
+        }
+    }
+}
+
+/// The SQL type of a native expression type.
+fn sql_type(native: &str) -> Option<&'static str> {
+    match native.to_uppercase().as_str() {
+        "LONG" => Some("BIGINT"),
+        "FLOAT" => Some("FLOAT"),
+        "DOUBLE" => Some("DOUBLE"),
+        "STRING" => Some("VARCHAR"),
+        _ => None,
+    }
+}
+
+/// A registered lookup applied to `input`.
+fn lookup(
+    input: String,
+    name: &str,
+    retain_missing_value: Option<bool>,
+    replace_missing_value_with: Option<&DruidNativeType>,
+) -> String {
+    match (retain_missing_value, replace_missing_value_with) {
+        (Some(true), _) => format!("COALESCE(LOOKUP({input}, {}), {input})", quote_string(name)),
+        (_, Some(with)) => format!("LOOKUP({input}, {}, {})", quote_string(name), literal(with)),
+        _ => format!("LOOKUP({input}, {})", quote_string(name)),
+    }
+}
+
+/// The start and end of an interval as SQL timestamps, resolving periods
+/// against the other end.
+fn interval_bounds(interval: &Interval) -> (String, String) {
+    let parse = |instant: &str| format!("TIME_PARSE({})", quote_string(instant));
+    if interval.start.starts_with('P') {
+        let end = parse(&interval.end);
+        (
+            format!("TIME_SHIFT({end}, {}, -1)", quote_string(&interval.start)),
+            end,
+        )
+    } else if interval.end.starts_with('P') {
+        let start = parse(&interval.start);
+        (
+            start.clone(),
+            format!("TIME_SHIFT({start}, {}, 1)", quote_string(&interval.end)),
+        )
+    } else {
+        (parse(&interval.start), parse(&interval.end))
+    }
+}
+
+/// The type of the values an aggregator outputs, if it is a simple one.
+fn aggregator_type(aggregator: &Aggregator) -> Option<ExprType> {
+    match aggregator {
+        Aggregator::Filtered { aggregator, .. } => aggregator_type(aggregator),
+        Aggregator::Count { .. }
+        | Aggregator::LongSum { .. }
+        | Aggregator::LongMin { .. }
+        | Aggregator::LongMax { .. }
+        | Aggregator::LongFirst { .. }
+        | Aggregator::LongLast { .. }
+        | Aggregator::LongAny { .. }
+        | Aggregator::Grouping { .. } => Some(ExprType::Long),
+        Aggregator::DoubleSum { .. }
+        | Aggregator::FloatSum { .. }
+        | Aggregator::DoubleMin { .. }
+        | Aggregator::FloatMin { .. }
+        | Aggregator::DoubleMax { .. }
+        | Aggregator::FloatMax { .. }
+        | Aggregator::DoubleMean { .. }
+        | Aggregator::DoubleFirst { .. }
+        | Aggregator::FloatFirst { .. }
+        | Aggregator::DoubleLast { .. }
+        | Aggregator::FloatLast { .. }
+        | Aggregator::DoubleAny { .. }
+        | Aggregator::FloatAny { .. }
+        | Aggregator::Cardinality { .. }
+        | Aggregator::HyperUnique { .. } => Some(ExprType::Double),
+        Aggregator::StringFirst { .. }
+        | Aggregator::StringLast { .. }
+        | Aggregator::StringAny { .. } => Some(ExprType::String),
+        Aggregator::Javascript { .. } => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        components::{
+            aggregations::Aggregator, context::Context, dimension_specs::DimensionSpec,
+            filter_dsl::col, filters::Filter, granularities::Granularity, having_specs::HavingSpec,
+            limit_specs::LimitSpec, post_aggregations::PostAggregator,
+            topn_metric_specs::TopNMetricSpec, virtual_columns::VirtaulColumn,
+        },
+        queries::{groupby::GroupBy, timeseries::Timeseries, topn::TopN, Query},
+    };
+
+    use super::TranslationError;
+
+    #[test]
+    fn translates_queries() {
+        let query = GroupBy::new(
+            "wikipedia".into(),
+            &["2015-09-12/P1D".parse().unwrap()],
+            "hour".parse().unwrap(),
+            &[
+                DimensionSpec::default("page".into(), "page".into(), None),
+                DimensionSpec::default("net".into(), "net added".into(), None),
+            ],
+        )
+        .virtual_columns(&[VirtaulColumn::expression(
+            "net".into(),
+            "added - deleted".into(),
+            Some("LONG".into()),
+        )])
+        .filter(col("channel").eq("#en") & (col("added").gt(10) | !col("user").like("bot%")))
+        .aggregations(&[
+            Aggregator::count("edits".into()),
+            Aggregator::Filtered {
+                filter: Box::new(Filter::expression("isRobot == 'true'".into())),
+                aggregator: Box::new(Aggregator::long_sum("bot_added".into(), "added".into())),
+            },
+        ])
+        .post_aggregations(&[PostAggregator::arithmetic(
+            "bot_share".into(),
+            "/".into(),
+            vec![
+                PostAggregator::field_access("bot_added".into(), "bot_added".into()),
+                PostAggregator::field_access("edits".into(), "edits".into()),
+            ],
+            None,
+        )])
+        .having(HavingSpec::GreaterThan {
+            aggregation: "edits".into(),
+            value: crate::components::druid_types::DruidNativeType::long(5),
+        })
+        .limit_spec(LimitSpec::default(
+            Some(10),
+            None,
+            Some(vec![LimitSpec::order_by_columns_spec(
+                "edits".into(),
+                "descending".into(),
+                None,
+            )]),
+        ));
+        assert_eq!(
+            Query::from(query).to_sql().unwrap(),
+            "SELECT TIME_FLOOR(\"__time\", 'PT1H') AS \"__time\", \"page\", (\"added\" - \"deleted\") AS \"net added\", \
+             COUNT(*) AS \"edits\", SUM(\"added\") FILTER(WHERE (\"isRobot\" = 'true')) AS \"bot_added\", \
+             CASE WHEN COUNT(*) = 0 THEN 0 ELSE CAST(SUM(\"added\") FILTER(WHERE (\"isRobot\" = 'true')) AS DOUBLE) / COUNT(*) END AS \"bot_share\"\n\
+             FROM \"wikipedia\"\n\
+             WHERE \"__time\" >= TIME_PARSE('2015-09-12') AND \"__time\" < TIME_SHIFT(TIME_PARSE('2015-09-12'), 'P1D', 1) \
+             AND \"channel\" = '#en' AND (CAST(\"added\" AS DOUBLE) > 10 OR NOT \"user\" LIKE 'bot%')\n\
+             GROUP BY TIME_FLOOR(\"__time\", 'PT1H'), \"page\", (\"added\" - \"deleted\")\n\
+             HAVING COUNT(*) > 5\n\
+             ORDER BY \"edits\" DESC\n\
+             LIMIT 10"
+        );
+
+        let query = TopN::new(
+            "wikipedia".into(),
+            &["2015-09-12/2015-09-13".parse().unwrap()],
+            Granularity::simple("all".into()),
+            DimensionSpec::default("page".into(), "page".into(), None),
+            5,
+            TopNMetricSpec::numeric("added".into()),
+        )
+        .aggregations(&[Aggregator::long_sum("added".into(), "added".into())]);
+        assert_eq!(
+            query.to_sql().unwrap(),
+            "SELECT \"page\", SUM(\"added\") AS \"added\"\n\
+             FROM \"wikipedia\"\n\
+             WHERE \"__time\" >= TIME_PARSE('2015-09-12') AND \"__time\" < TIME_PARSE('2015-09-13')\n\
+             GROUP BY \"page\"\n\
+             ORDER BY \"added\" DESC\n\
+             LIMIT 5"
+        );
+    }
+
+    #[test]
+    fn rejects_unsupported_constructs() {
+        let query = Timeseries::new(
+            "wikipedia".into(),
+            &["2015-09-12/P1D".parse().unwrap()],
+            "all".parse().unwrap(),
+        )
+        .aggregations(&[Aggregator::Javascript {
+            name: "sum".into(),
+            field_names: vec!["added".into()],
+            fn_aggregate: "function(current, a) { return current + a; }".into(),
+            fn_combine: "function(a, b) { return a + b; }".into(),
+            fn_reset: "function() { return 0; }".into(),
+        }]);
+        assert!(matches!(
+            query.to_sql(),
+            Err(TranslationError::Unsupported(what)) if what == "JavaScript aggregators"
+        ));
+        let query = TopN::new(
+            "wikipedia".into(),
+            &["2015-09-12/P1D".parse().unwrap()],
+            "hour".parse().unwrap(),
+            DimensionSpec::default("page".into(), "page".into(), None),
+            5,
+            TopNMetricSpec::numeric("count".into()),
+        );
+        assert!(query.to_sql().is_err());
+        let query = Timeseries::new(
+            "wikipedia".into(),
+            &["2015-09-12/P1D".parse().unwrap()],
+            "hour".parse().unwrap(),
+        )
+        .aggregations(&[Aggregator::count("edits".into())]);
+        assert!(matches!(
+            query.to_sql(),
+            Err(TranslationError::Unsupported(what)) if what == "zero-filled empty buckets"
+        ));
+        assert!(query
+            .context(Context::new().skip_empty_buckets(true))
+            .to_sql()
+            .is_ok());
+        let query = Timeseries::new(
+            "wikipedia".into(),
+            &["2015-09-12/P1D".parse().unwrap()],
+            "all".parse().unwrap(),
+        )
+        .virtual_columns(&[VirtaulColumn::expression(
+            "net".into(),
+            "added - deleted".into(),
+            Some("LONG".into()),
+        )])
+        .filter(Filter::expression(
+            "net + 1 > 10 && UPPER(page + '!') == 'A!'".into(),
+        ));
+        assert!(query.to_sql().unwrap().contains(
+            "(((\"added\" - \"deleted\") + 1) > 10) AND (UPPER(CONCAT(\"page\", '!')) = 'A!')"
+        ));
+        // `added` could be a string column, making `+` a concatenation.
+        let filter = Filter::expression("added + 1 > 10".into());
+        let query = Timeseries::new(
+            "wikipedia".into(),
+            &["2015-09-12/P1D".parse().unwrap()],
+            "all".parse().unwrap(),
+        )
+        .filter(filter);
+        assert!(matches!(
+            query.to_sql(),
+            Err(TranslationError::Unsupported(what))
+                if what == "additions of operands of unknown types"
+        ));
+        let filter = Filter::expression("map((x) -> x + 1, tags)".into());
+        let query = Timeseries::new(
+            "wikipedia".into(),
+            &["2015-09-12/P1D".parse().unwrap()],
+            "all".parse().unwrap(),
+        )
+        .filter(filter);
+        assert!(matches!(
+            query.to_sql(),
+            Err(TranslationError::Unsupported(_))
+        ));
+    }
+}
@@ -0,0 +1,333 @@
+//! Checks of native queries that Druid would otherwise only reject after a
+//! round trip.
+//!
+//! Problems are reported with the path of the offending part of the query,
+//! using the Rust field names, like `aggregations[2].name`.
+
+use std::{collections::HashSet, fmt};
+
+use chrono::{DateTime, NaiveDate, NaiveDateTime};
+
+use crate::components::{
+    aggregations::Aggregator, dimension_specs::DimensionSpec, having_specs::HavingSpec,
+    intervals::Interval, limit_specs::LimitSpec, post_aggregations::PostAggregator,
+    topn_metric_specs::TopNMetricSpec,
+};
+
+/// A single problem found in a query.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Problem {
+    pub path: String,
+    pub message: String,
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.path, self.message)
+    }
+}
+
+/// All problems found in a query.
+#[derive(Debug, Clone, thiserror::Error)]
+pub struct ValidationError {
+    pub problems: Vec<Problem>,
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid query")?;
+        for (i, problem) in self.problems.iter().enumerate() {
+            write!(f, "{} {problem}", if i == 0 { ":" } else { ";" })?;
+        }
+        Ok(())
+    }
+}
+
+/// Collects problems and the output names defined so far.
+#[derive(Default)]
+pub(crate) struct Validator {
+    problems: Vec<Problem>,
+    dimensions: HashSet<String>,
+    metrics: HashSet<String>,
+}
+
+impl Validator {
+    fn problem(&mut self, path: String, message: String) {
+        self.problems.push(Problem { path, message });
+    }
+
+    pub(crate) fn finish(self) -> Result<(), ValidationError> {
+        if self.problems.is_empty() {
+            Ok(())
+        } else {
+            Err(ValidationError {
+                problems: self.problems,
+            })
+        }
+    }
+
+    pub(crate) fn intervals(&mut self, intervals: &[Interval]) {
+        if intervals.is_empty() {
+            self.problem("intervals".into(), "at least one interval is needed".into());
+        }
+        for (i, interval) in intervals.iter().enumerate() {
+            if let (Some(start), Some(end)) = (instant(&interval.start), instant(&interval.end)) {
+                if start >= end {
+                    self.problem(
+                        format!("intervals[{i}]"),
+                        format!("interval {interval} is empty"),
+                    );
+                }
+            }
+        }
+    }
+
+    pub(crate) fn dimensions<'d, I>(&mut self, path: &str, dimensions: I)
+    where
+        I: IntoIterator<Item = &'d DimensionSpec>,
+    {
+        for (i, dimension) in dimensions.into_iter().enumerate() {
+            let name = dimension.output_name();
+            if !self.dimensions.insert(name.to_string()) {
+                self.problem(
+                    format!("{path}[{i}].output_name"),
+                    format!("duplicate output name {name:?}"),
+                );
+            }
+        }
+    }
+
+    pub(crate) fn aggregations(&mut self, aggregations: Option<&[Aggregator]>) {
+        for (i, aggregator) in aggregations.unwrap_or_default().iter().enumerate() {
+            self.define(format!("aggregations[{i}].name"), aggregator.name());
+        }
+    }
+
+    /// Post-aggregators may use aggregators and the post-aggregators before
+    /// them.
+    pub(crate) fn post_aggregations(&mut self, post_aggregations: Option<&[PostAggregator]>) {
+        for (i, post_aggregator) in post_aggregations.unwrap_or_default().iter().enumerate() {
+            self.post_aggregator_fields(&format!("post_aggregations[{i}]"), post_aggregator);
+            self.define(
+                format!("post_aggregations[{i}].name"),
+                post_aggregator.name(),
+            );
+        }
+    }
+
+    fn define(&mut self, path: String, name: &str) {
+        if self.dimensions.contains(name) || !self.metrics.insert(name.to_string()) {
+            self.problem(path, format!("duplicate output name {name:?}"));
+        }
+    }
+
+    fn post_aggregator_fields(&mut self, path: &str, post_aggregator: &PostAggregator) {
+        match post_aggregator {
+            PostAggregator::FieldAccess { field_name, .. }
+            | PostAggregator::FinalizingFieldAccess { field_name, .. }
+            | PostAggregator::HyperUniqueCardinality { field_name, .. } => {
+                self.metric(format!("{path}.field_name"), field_name)
+            }
+            PostAggregator::Javascript { field_names, .. } => {
+                for (i, field_name) in field_names.iter().enumerate() {
+                    self.metric(format!("{path}.field_names[{i}]"), field_name);
+                }
+            }
+            PostAggregator::Arithmetic { fields, .. }
+            | PostAggregator::LongGreatest { fields, .. }
+            | PostAggregator::DoubleGreatest { fields, .. }
+            | PostAggregator::LongLeast { fields, .. }
+            | PostAggregator::DoubleLeast { fields, .. } => {
+                for (i, field) in fields.iter().enumerate() {
+                    self.post_aggregator_fields(&format!("{path}.fields[{i}]"), field);
+                }
+            }
+            PostAggregator::Constant { .. } | PostAggregator::Expression { .. } => {}
+        }
+    }
+
+    fn metric(&mut self, path: String, name: &str) {
+        if !self.metrics.contains(name) {
+            self.problem(path, format!("no aggregator named {name:?}"));
+        }
+    }
+
+    fn output(&mut self, path: String, name: &str) {
+        if !self.metrics.contains(name) && !self.dimensions.contains(name) {
+            self.problem(path, format!("no output named {name:?}"));
+        }
+    }
+
+    pub(crate) fn having(&mut self, path: &str, having: &HavingSpec) {
+        match having {
+            HavingSpec::EqualTo { aggregation, .. }
+            | HavingSpec::GreaterThan { aggregation, .. }
+            | HavingSpec::LessThan { aggregation, .. } => {
+                self.output(format!("{path}.aggregation"), aggregation)
+            }
+            HavingSpec::DimSelector { dimension, .. } => {
+                self.output(format!("{path}.dimension"), dimension)
+            }
+            HavingSpec::And { having_specs } | HavingSpec::Or { having_specs } => {
+                for (i, spec) in having_specs.iter().enumerate() {
+                    self.having(&format!("{path}.having_specs[{i}]"), spec);
+                }
+            }
+            HavingSpec::Not { having_spec } => {
+                self.having(&format!("{path}.having_spec"), having_spec)
+            }
+            HavingSpec::Filter { .. } => {}
+        }
+    }
+
+    pub(crate) fn limit_spec(&mut self, path: &str, limit_spec: &LimitSpec) {
+        match limit_spec {
+            LimitSpec::Default {
+                columns: Some(columns),
+                ..
+            } => {
+                for (i, column) in columns.iter().enumerate() {
+                    self.limit_spec(&format!("{path}.columns[{i}]"), column);
+                }
+            }
+            LimitSpec::OrderByColumnSpec { dimension, .. } => {
+                self.output(format!("{path}.dimension"), dimension)
+            }
+            _ => {}
+        }
+    }
+
+    pub(crate) fn subtotals_spec(&mut self, subtotals_spec: &[Vec<String>]) {
+        for (i, subtotal) in subtotals_spec.iter().enumerate() {
+            for (j, dimension) in subtotal.iter().enumerate() {
+                if !self.dimensions.contains(dimension) {
+                    self.problem(
+                        format!("subtotals_spec[{i}][{j}]"),
+                        format!("no dimension named {dimension:?}"),
+                    );
+                }
+            }
+        }
+    }
+
+    pub(crate) fn topn_metric(&mut self, path: &str, metric: &TopNMetricSpec) {
+        match metric {
+            TopNMetricSpec::Numeric { metric } => self.metric(format!("{path}.metric"), metric),
+            TopNMetricSpec::Inverted { metric } => {
+                self.topn_metric(&format!("{path}.metric"), metric)
+            }
+            TopNMetricSpec::Dimension { .. } => {}
+        }
+    }
+}
+
+/// An interval boundary as a local time, `None` for periods and anything else
+/// that isn't a plain timestamp.
+fn instant(value: &str) -> Option<NaiveDateTime> {
+    if let Ok(time) = DateTime::parse_from_rfc3339(value) {
+        return Some(time.naive_utc());
+    }
+    ["%Y-%m-%dT%H:%M:%S%.f", "%Y-%m-%dT%H:%M"]
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(value, format).ok())
+        .or_else(|| {
+            NaiveDate::parse_from_str(value, "%Y-%m-%d")
+                .ok()
+                .and_then(|date| date.and_hms_opt(0, 0, 0))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        components::{
+            aggregations::Aggregator, data_sources::DataSource, dimension_specs::DimensionSpec,
+            druid_types::DruidNativeType, granularities::Granularity, having_specs::HavingSpec,
+            intervals::Interval, limit_specs::LimitSpec, post_aggregations::PostAggregator,
+            topn_metric_specs::TopNMetricSpec,
+        },
+        queries::{groupby::GroupBy, topn::TopN, Query},
+    };
+
+    fn interval() -> Interval {
+        "2015-09-12/2015-09-13".parse().unwrap()
+    }
+
+    #[test]
+    fn reports_all_groupby_problems() {
+        let query: Query = GroupBy::new(
+            DataSource::table("wikipedia".into()),
+            &["2015-09-13/2015-09-12".parse().unwrap()],
+            Granularity::simple("all".into()),
+            &[DimensionSpec::default("page".into(), "page".into(), None)],
+        )
+        .aggregations(&[
+            Aggregator::count("edits".into()),
+            Aggregator::long_sum("added".into(), "added".into()),
+            Aggregator::long_sum("edits".into(), "deleted".into()),
+        ])
+        .post_aggregations(&[
+            PostAggregator::field_access("ratio".into(), "removed".into()),
+            PostAggregator::arithmetic(
+                "net".into(),
+                "-".into(),
+                vec![
+                    PostAggregator::field_access("a".into(), "added".into()),
+                    PostAggregator::field_access("b".into(), "ratio".into()),
+                ],
+                None,
+            ),
+        ])
+        .having(HavingSpec::and(vec![
+            HavingSpec::greater_than("net".into(), DruidNativeType::long(0)),
+            HavingSpec::dim_selector("user".into(), DruidNativeType::string("bob")),
+        ]))
+        .limit_spec(LimitSpec::default(
+            Some(10),
+            None,
+            Some(vec![LimitSpec::order_by_columns_spec(
+                "total".into(),
+                "descending".into(),
+                None,
+            )]),
+        ))
+        .subtotals_spec(&[vec!["page".into()], vec!["user".into()]])
+        .into();
+        let paths: Vec<_> = query
+            .validate()
+            .unwrap_err()
+            .problems
+            .into_iter()
+            .map(|problem| problem.path)
+            .collect();
+        assert_eq!(
+            paths,
+            [
+                "intervals[0]",
+                "aggregations[2].name",
+                "post_aggregations[0].field_name",
+                "having.having_specs[1].dimension",
+                "limit_spec.columns[0].dimension",
+                "subtotals_spec[1][0]",
+            ]
+        );
+    }
+
+    #[test]
+    fn checks_topn_metric() {
+        let query = TopN::new(
+            DataSource::table("wikipedia".into()),
+            &[interval()],
+            Granularity::simple("all".into()),
+            DimensionSpec::default("page".into(), "page".into(), None),
+            10,
+            TopNMetricSpec::inverted(TopNMetricSpec::numeric("edits".into())),
+        );
+        let error = query.clone().validate().unwrap_err();
+        assert_eq!(error.problems[0].path, "metric.metric.metric");
+        assert!(query
+            .aggregations(&[Aggregator::count("edits".into())])
+            .validate()
+            .is_ok());
+    }
+}
@@ -0,0 +1,362 @@
+//! Conversion of query results into Arrow [`RecordBatch`]es, behind the
+//! `arrow` feature.
+//!
+//! Column types are inferred from the values, or taken from the type hints
+//! given to [`ToRecordBatch::to_record_batch_with_types`], like the ones from
+//! [`column_types_from_segment_metadata`]. Multi-value dimensions become
+//! lists of strings, and the result timestamp becomes a `timestamp` column in
+//! milliseconds. `__time` is converted to a timestamp column as well.
+
+use std::{collections::HashMap, sync::Arc};
+
+use arrow::{
+    array::{
+        ArrayRef, BooleanArray, Float32Array, Float64Array, Int64Array, ListBuilder, StringArray,
+        StringBuilder, TimestampMillisecondArray,
+    },
+    datatypes::{DataType, Field, Schema, TimeUnit},
+    error::ArrowError,
+    record_batch::RecordBatch,
+};
+
+use crate::{
+    columnar::{
+        as_bool, as_f64, as_i64, as_millis, as_strings, to_string, Column, ColumnKind, ToColumns,
+        TIMESTAMP,
+    },
+    queries::{
+        response::{
+            GroupByResult, ScanResult, SegmentMetadataResult, SqlResult, TimeseriesResult,
+            TopNResult,
+        },
+        sql_result::SqlResultSet,
+    },
+};
+
+/// Conversion of a set of results into a single [`RecordBatch`].
+pub trait ToRecordBatch {
+    fn to_record_batch(&self) -> Result<RecordBatch, ArrowError> {
+        self.to_record_batch_with_types(&HashMap::new())
+    }
+
+    /// Convert with the type of some columns given instead of inferred.
+    fn to_record_batch_with_types(
+        &self,
+        types: &HashMap<String, DataType>,
+    ) -> Result<RecordBatch, ArrowError>;
+}
+
+macro_rules! impl_to_record_batch {
+    ($($result:ty),*) => {
+        $(
+            impl ToRecordBatch for [$result] {
+                fn to_record_batch_with_types(
+                    &self,
+                    types: &HashMap<String, DataType>,
+                ) -> Result<RecordBatch, ArrowError> {
+                    record_batch(self, types)
+                }
+            }
+        )*
+    };
+}
+
+impl_to_record_batch!(
+    ScanResult,
+    GroupByResult,
+    TimeseriesResult,
+    TopNResult,
+    SqlResult
+);
+
+/// Columns keep their order, and take their types from the SQL or native
+/// types in the schema where known. Types given here take precedence.
+impl ToRecordBatch for SqlResultSet {
+    fn to_record_batch_with_types(
+        &self,
+        types: &HashMap<String, DataType>,
+    ) -> Result<RecordBatch, ArrowError> {
+        let mut column_types: HashMap<String, DataType> = self
+            .columns
+            .iter()
+            .filter_map(|column| {
+                let data_type = column
+                    .sql_type
+                    .as_deref()
+                    .and_then(arrow_type)
+                    .or_else(|| column.druid_type.as_deref().and_then(arrow_type))?;
+                Some((column.name.clone(), data_type))
+            })
+            .collect();
+        column_types.extend(types.iter().map(|(name, t)| (name.clone(), t.clone())));
+        record_batch(self.to_sql_results().as_slice(), &column_types)
+    }
+}
+
+/// The Arrow type of a Druid native type like `LONG` or `ARRAY<STRING>`, or a
+/// Druid SQL type like `BIGINT` or `TIMESTAMP`.
+pub fn arrow_type(druid_type: &str) -> Option<DataType> {
+    match druid_type {
+        "LONG" | "BIGINT" | "INTEGER" | "SMALLINT" | "TINYINT" => Some(DataType::Int64),
+        "FLOAT" => Some(DataType::Float32),
+        "DOUBLE" | "REAL" | "DECIMAL" => Some(DataType::Float64),
+        "STRING" | "VARCHAR" | "CHAR" => Some(DataType::Utf8),
+        "BOOLEAN" => Some(DataType::Boolean),
+        "TIMESTAMP" | "DATE" => Some(timestamp_type()),
+        "ARRAY<STRING>" | "VARCHAR ARRAY" => Some(string_list_type()),
+        _ => None,
+    }
+}
+
+/// Column types from a `segmentMetadata` analysis, skipping complex columns.
+pub fn column_types_from_segment_metadata(
+    metadata: &SegmentMetadataResult,
+) -> HashMap<String, DataType> {
+    metadata
+        .columns
+        .iter()
+        .filter_map(|(name, analysis)| {
+            let data_type = if analysis.has_multiple_values {
+                Some(string_list_type())
+            } else {
+                arrow_type(&analysis.column_type)
+            };
+            data_type.map(|t| (name.clone(), t))
+        })
+        .collect()
+}
+
+fn timestamp_type() -> DataType {
+    DataType::Timestamp(TimeUnit::Millisecond, Some("UTC".into()))
+}
+
+fn string_list_type() -> DataType {
+    DataType::List(Arc::new(Field::new("item", DataType::Utf8, true)))
+}
+
+fn record_batch<R: ToColumns + ?Sized>(
+    results: &R,
+    types: &HashMap<String, DataType>,
+) -> Result<RecordBatch, ArrowError> {
+    let columns = results
+        .to_columns()
+        .map_err(|e| ArrowError::InvalidArgumentError(e.into()))?;
+    let (timestamps, columns) = columns.finish();
+    let mut fields = Vec::new();
+    let mut arrays = Vec::new();
+    if let Some(timestamps) = timestamps {
+        let array: ArrayRef =
+            Arc::new(TimestampMillisecondArray::from(timestamps).with_timezone("UTC"));
+        fields.push(Field::new(TIMESTAMP, array.data_type().clone(), true));
+        arrays.push(array);
+    }
+    for column in columns {
+        let data_type = match types.get(column.name) {
+            Some(data_type) => data_type.clone(),
+            None => match column.kind() {
+                ColumnKind::Int64 => DataType::Int64,
+                ColumnKind::Float64 => DataType::Float64,
+                ColumnKind::Utf8 => DataType::Utf8,
+                ColumnKind::StringList => string_list_type(),
+                ColumnKind::Timestamp => timestamp_type(),
+                #[cfg(feature = "polars")]
+                ColumnKind::Boolean => DataType::Boolean,
+            },
+        };
+        let array = build(&column, &data_type)?;
+        fields.push(Field::new(column.name, array.data_type().clone(), true));
+        arrays.push(array);
+    }
+    RecordBatch::try_new(Arc::new(Schema::new(fields)), arrays)
+}
+
+fn build(column: &Column, data_type: &DataType) -> Result<ArrayRef, ArrowError> {
+    let values = column.values.iter();
+    let array: ArrayRef = match data_type {
+        DataType::Int64 => Arc::new(values.map(|v| v.and_then(as_i64)).collect::<Int64Array>()),
+        DataType::Float64 => Arc::new(values.map(|v| v.and_then(as_f64)).collect::<Float64Array>()),
+        DataType::Float32 => Arc::new(
+            values
+                .map(|v| v.and_then(as_f64).map(|d| d as f32))
+                .collect::<Float32Array>(),
+        ),
+        DataType::Boolean => Arc::new(
+            values
+                .map(|v| v.and_then(as_bool))
+                .collect::<BooleanArray>(),
+        ),
+        DataType::Timestamp(TimeUnit::Millisecond, tz) => Arc::new(
+            values
+                .map(|v| v.and_then(as_millis))
+                .collect::<TimestampMillisecondArray>()
+                .with_timezone_opt(tz.clone()),
+        ),
+        DataType::List(item) if item.data_type() == &DataType::Utf8 => {
+            let mut builder = ListBuilder::new(StringBuilder::new());
+            for value in values {
+                match value.and_then(as_strings) {
+                    Some(items) => {
+                        for item in items {
+                            builder.values().append_value(item);
+                        }
+                        builder.append(true);
+                    }
+                    None => builder.append(false),
+                }
+            }
+            Arc::new(builder.finish())
+        }
+        DataType::Utf8 => Arc::new(values.map(|v| v.map(to_string)).collect::<StringArray>()),
+        other => {
+            return Err(ArrowError::NotYetImplemented(format!(
+                "converting Druid values to {other}"
+            )))
+        }
+    };
+    Ok(array)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use arrow::{
+        array::{Array, Int64Array, ListArray, StringArray},
+        datatypes::DataType,
+    };
+
+    use super::{timestamp_type, ToRecordBatch};
+    use crate::{
+        components::druid_types::DruidNativeType,
+        queries::{
+            response::{GroupByResult, ScanResult, TopNResult},
+            sql::Sql,
+            sql_result::SqlResultSet,
+        },
+    };
+
+    #[test]
+    fn converts_scan_results() {
+        let results = [ScanResult::CompactedList {
+            segment_id: "s".into(),
+            columns: vec![
+                "__time".into(),
+                "page".into(),
+                "tags".into(),
+                "added".into(),
+            ],
+            events: vec![
+                vec![
+                    DruidNativeType::long(1_442_016_000_000i64),
+                    DruidNativeType::string("Rust"),
+                    DruidNativeType::Array(vec!["a".into(), "b".into()]),
+                    DruidNativeType::long(3),
+                ],
+                vec![
+                    DruidNativeType::long(1_442_016_001_000i64),
+                    DruidNativeType::string("Go"),
+                    DruidNativeType::string("c"),
+                    DruidNativeType::long(5),
+                ],
+            ],
+        }];
+        let batch = results.to_record_batch().unwrap();
+        let schema = batch.schema();
+        let names: Vec<_> = schema.fields().iter().map(|f| f.name().as_str()).collect();
+        assert_eq!(names, ["__time", "page", "tags", "added"]);
+        assert_eq!(schema.field(0).data_type(), &timestamp_type());
+        let tags = batch
+            .column(2)
+            .as_any()
+            .downcast_ref::<ListArray>()
+            .unwrap();
+        assert_eq!(tags.value(0).len(), 2);
+        let added = batch
+            .column(3)
+            .as_any()
+            .downcast_ref::<Int64Array>()
+            .unwrap();
+        assert_eq!(added.value(1), 5);
+    }
+
+    #[test]
+    fn flattens_topn_with_types() {
+        let results: Vec<TopNResult> = serde_json::from_str(
+            r#"[{"timestamp": "2015-09-12T00:00:00.000Z", "result": [
+                {"page": "Rust", "edits": 3}, {"page": "Go", "edits": 2}]}]"#,
+        )
+        .unwrap();
+        let types = HashMap::from([("edits".to_string(), DataType::Float64)]);
+        let batch = results.to_record_batch_with_types(&types).unwrap();
+        assert_eq!(batch.num_rows(), 2);
+        assert_eq!(batch.schema().field(0).name(), "timestamp");
+        assert_eq!(batch.schema().field(1).data_type(), &DataType::Float64);
+        let page = batch
+            .column(2)
+            .as_any()
+            .downcast_ref::<StringArray>()
+            .unwrap();
+        assert_eq!(page.value(1), "Go");
+
+        let results: Vec<GroupByResult> = serde_json::from_str(
+            r#"[{"version": "v1", "timestamp": "2015-09-12T00:00:00.000Z", "event": {"page": "Rust"}},
+                {"version": "v1", "timestamp": "2015-09-12T00:00:00.000Z", "event": {"page": "Go", "user": "bob"}}]"#,
+        )
+        .unwrap();
+        let batch = results.to_record_batch().unwrap();
+        assert!(batch.column(2).is_null(0));
+
+        let results: Vec<TopNResult> = serde_json::from_str(
+            r#"[{"timestamp": "2015-09-12T00:00:00.000Z", "result": [
+                {"edits": 3.0}, {"edits": 1.5}, {"edits": 1e30}]}]"#,
+        )
+        .unwrap();
+        let types = HashMap::from([("edits".to_string(), DataType::Int64)]);
+        let batch = results.to_record_batch_with_types(&types).unwrap();
+        let edits = batch
+            .column(1)
+            .as_any()
+            .downcast_ref::<Int64Array>()
+            .unwrap();
+        assert_eq!(edits.iter().collect::<Vec<_>>(), [Some(3), None, None]);
+    }
+
+    #[test]
+    fn converts_sql_result_sets_with_their_types() {
+        let query = Sql::new("SELECT __time, page, added, robot FROM wikipedia")
+            .header(true)
+            .types_header(true)
+            .sql_types_header(true);
+        let body = r#"[
+            {"page": {"type": "STRING", "sqlType": "VARCHAR"},
+             "__time": {"type": "LONG", "sqlType": "TIMESTAMP"},
+             "added": {"type": "LONG", "sqlType": "BIGINT"},
+             "robot": {"type": "LONG", "sqlType": "BOOLEAN"}},
+            {"page": "Rust", "__time": "2015-09-12T00:46:58.000Z", "added": null, "robot": true},
+            {"page": null, "__time": "2015-09-12T01:00:00.000Z", "added": null, "robot": false}]"#;
+        let result = SqlResultSet::parse(body, &query).unwrap();
+        let batch = result.to_record_batch().unwrap();
+        let schema = batch.schema();
+        let fields: Vec<_> = schema
+            .fields()
+            .iter()
+            .map(|field| (field.name().as_str(), field.data_type().clone()))
+            .collect();
+        assert_eq!(
+            fields,
+            [
+                ("page", DataType::Utf8),
+                ("__time", timestamp_type()),
+                ("added", DataType::Int64),
+                ("robot", DataType::Boolean),
+            ]
+        );
+        assert_eq!(batch.num_rows(), 2);
+        assert!(batch.column(0).is_null(1));
+        assert_eq!(batch.column(2).null_count(), 2);
+
+        let types = HashMap::from([("added".to_string(), DataType::Float64)]);
+        let batch = result.to_record_batch_with_types(&types).unwrap();
+        assert_eq!(batch.schema().field(2).data_type(), &DataType::Float64);
+    }
+}
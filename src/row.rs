@@ -0,0 +1,255 @@
+//! Conversion of result rows into user structs.
+//!
+//! [`DruidRow`] ties a Rust type to the columns of a query, so the query
+//! definition and the type it is decoded into can't drift apart. With the
+//! `derive` feature it can be derived:
+//!
+//! ```ignore
+//! use query_druid::row::DruidRow;
+//!
+//! #[derive(DruidRow)]
+//! struct PageEdits {
+//!     page: String,
+//!     #[druid(rename = "user", dimension = "user_name")]
+//!     user: String,
+//!     #[druid(long_sum = "added")]
+//!     added: i64,
+//!     #[druid(count)]
+//!     edits: i64,
+//! }
+//!
+//! let query = GroupBy::new(datasource, &intervals, granularity, &PageEdits::dimensions())
+//!     .aggregations(&PageEdits::aggregations());
+//! let rows = client
+//!     .groupby(query)
+//!     .await?
+//!     .iter()
+//!     .map(PageEdits::from_groupby)
+//!     .collect::<Result<Vec<_>, _>>()?;
+//! ```
+//!
+//! Fields are dimensions unless they name an aggregator. The attributes are
+//! `rename = "output"`, `dimension = "source"`, `skip` (filled with
+//! `Default::default()`) and one of `count`, `long_sum`, `double_sum`,
+//! `float_sum`, the `_min`, `_max`, `_first`, `_last` and `_any` variants of
+//! those, `double_mean`, `string_first`, `string_last` and `string_any`, each
+//! given the source column.
+
+use std::collections::HashMap;
+
+use crate::{
+    components::{
+        aggregations::Aggregator, dimension_specs::DimensionSpec, druid_types::DruidNativeType,
+    },
+    queries::response::{GroupByResult, ScanResult},
+};
+
+#[cfg(feature = "derive")]
+pub use query_druid_derive::DruidRow;
+
+/// A row of a query result with a fixed set of columns.
+pub trait DruidRow: Sized {
+    /// Output names of all columns.
+    fn columns() -> Vec<String>;
+
+    /// Output name and source column of each column a scan can read: the
+    /// source of a dimension or the field of an aggregator. Count columns
+    /// have no source and are left out.
+    fn scan_sources() -> Vec<(String, String)> {
+        Self::columns()
+            .into_iter()
+            .map(|c| (c.clone(), c))
+            .collect()
+    }
+
+    /// Source names of the columns, for `Scan::columns`.
+    fn scan_columns() -> Vec<String> {
+        Self::scan_sources()
+            .into_iter()
+            .map(|(_, source)| source)
+            .collect()
+    }
+
+    /// The dimensions to group on.
+    fn dimensions() -> Vec<DimensionSpec>;
+
+    /// The aggregators computing the metric columns.
+    fn aggregations() -> Vec<Aggregator>;
+
+    fn from_row(row: &HashMap<String, DruidNativeType>) -> Result<Self, RowError>;
+
+    fn from_groupby(result: &GroupByResult) -> Result<Self, RowError> {
+        Self::from_row(&result.event)
+    }
+
+    /// Convert all events of a scan result batch, in either result format.
+    ///
+    /// The events are keyed by source column, see [`Self::scan_columns`].
+    fn from_scan(result: &ScanResult) -> Result<Vec<Self>, RowError> {
+        let sources = Self::scan_sources();
+        let from_source = |event: &HashMap<String, DruidNativeType>| {
+            let row = sources
+                .iter()
+                .filter_map(|(name, source)| Some((name.clone(), event.get(source)?.clone())))
+                .collect();
+            Self::from_row(&row)
+        };
+        match result {
+            ScanResult::List { events, .. } => events.iter().map(from_source).collect(),
+            ScanResult::CompactedList {
+                columns, events, ..
+            } => events
+                .iter()
+                .map(|event| {
+                    let event = columns.iter().cloned().zip(event.iter().cloned()).collect();
+                    from_source(&event)
+                })
+                .collect(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, thiserror::Error)]
+pub enum RowError {
+    #[error("column {0} is missing from the row")]
+    Missing(String),
+    #[error("column {column} can't be converted to {expected}")]
+    Type {
+        column: String,
+        expected: &'static str,
+    },
+}
+
+/// Types a single result value can be converted into.
+pub trait FromDruidNative: Sized {
+    fn from_druid(value: &DruidNativeType) -> Option<Self>;
+
+    /// The value of a column missing from the row, `None` if it is required.
+    fn missing() -> Option<Self> {
+        None
+    }
+}
+
+/// Convert the value of `column` in `row`, used by the derived
+/// [`DruidRow::from_row`].
+pub fn column<T: FromDruidNative>(
+    row: &HashMap<String, DruidNativeType>,
+    column: &str,
+) -> Result<T, RowError> {
+    match row.get(column) {
+        Some(value) => T::from_druid(value).ok_or_else(|| RowError::Type {
+            column: column.into(),
+            expected: std::any::type_name::<T>(),
+        }),
+        None => T::missing().ok_or_else(|| RowError::Missing(column.into())),
+    }
+}
+
+impl FromDruidNative for DruidNativeType {
+    fn from_druid(value: &DruidNativeType) -> Option<Self> {
+        Some(value.clone())
+    }
+}
+
+impl FromDruidNative for String {
+    fn from_druid(value: &DruidNativeType) -> Option<Self> {
+        value.as_str().map(String::from)
+    }
+}
+
+impl FromDruidNative for Vec<String> {
+    fn from_druid(value: &DruidNativeType) -> Option<Self> {
+        match value {
+            DruidNativeType::Array(values) => Some(values.clone()),
+            DruidNativeType::String(value) => Some(vec![value.clone()]),
+            _ => None,
+        }
+    }
+}
+
+impl FromDruidNative for i64 {
+    fn from_druid(value: &DruidNativeType) -> Option<Self> {
+        value.as_i64()
+    }
+}
+
+macro_rules! from_long {
+    ($($t:ty),*) => {
+        $(
+            impl FromDruidNative for $t {
+                fn from_druid(value: &DruidNativeType) -> Option<Self> {
+                    value.as_i64().and_then(|l| <$t>::try_from(l).ok())
+                }
+            }
+        )*
+    };
+}
+
+from_long!(i32, u32, u64, usize);
+
+impl FromDruidNative for f64 {
+    fn from_druid(value: &DruidNativeType) -> Option<Self> {
+        value.as_f64().or_else(|| value.as_i64().map(|l| l as f64))
+    }
+}
+
+impl FromDruidNative for f32 {
+    fn from_druid(value: &DruidNativeType) -> Option<Self> {
+        match value {
+            DruidNativeType::Float(f) => Some(*f),
+            DruidNativeType::Double(d) => Some(*d as f32),
+            DruidNativeType::Long(l) => Some(*l as f32),
+            _ => None,
+        }
+    }
+}
+
+impl FromDruidNative for bool {
+    fn from_druid(value: &DruidNativeType) -> Option<Self> {
+        match value {
+            DruidNativeType::Long(l) => Some(*l != 0),
+            DruidNativeType::String(s) => s.parse().ok(),
+            _ => None,
+        }
+    }
+}
+
+impl<T: FromDruidNative> FromDruidNative for Option<T> {
+    fn from_druid(value: &DruidNativeType) -> Option<Self> {
+        T::from_druid(value).map(Some)
+    }
+
+    fn missing() -> Option<Self> {
+        Some(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::{column, RowError};
+    use crate::components::druid_types::DruidNativeType;
+
+    #[test]
+    fn converts_columns() {
+        let row = HashMap::from([
+            ("page".to_string(), DruidNativeType::string("Rust")),
+            ("added".to_string(), DruidNativeType::long(42)),
+            ("ratio".to_string(), DruidNativeType::float(0.5)),
+        ]);
+        assert_eq!(column::<String>(&row, "page").unwrap(), "Rust");
+        assert_eq!(column::<u64>(&row, "added").unwrap(), 42);
+        assert_eq!(column::<f64>(&row, "added").unwrap(), 42.0);
+        assert_eq!(column::<f64>(&row, "ratio").unwrap(), 0.5);
+        assert_eq!(column::<Option<i64>>(&row, "deleted").unwrap(), None);
+        assert!(matches!(
+            column::<i64>(&row, "deleted"),
+            Err(RowError::Missing(c)) if c == "deleted"
+        ));
+        assert!(matches!(
+            column::<i64>(&row, "page"),
+            Err(RowError::Type { column, .. }) if column == "page"
+        ));
+    }
+}
@@ -0,0 +1,568 @@
+//! Typed access to Druid's `sys` schema.
+//!
+//! Each table has a row struct and a query builder whose filters are bound as
+//! SQL parameters. Run them with
+//! [`Client::system_table`](crate::prelude::Client::system_table).
+//!
+//! ```
+//! use query_druid::system_tables::{segments, SystemTableQuery};
+//!
+//! let sql = segments().datasource("wikipedia").is_published().to_sql();
+//! assert!(sql.query.ends_with("FROM sys.segments WHERE datasource = ? AND is_published = 1"));
+//! ```
+
+use chrono::{DateTime, Utc};
+use serde::{de::DeserializeOwned, Deserialize, Deserializer, Serialize};
+
+use crate::{
+    components::druid_types::DruidSqlType, ingestion::tasks::TaskState, queries::sql::Sql,
+};
+
+/// A query on one of the system tables, decoding into `Row`.
+pub trait SystemTableQuery {
+    type Row: DeserializeOwned;
+
+    fn to_sql(&self) -> Sql;
+}
+
+#[derive(Debug, Clone)]
+struct TableQuery {
+    table: &'static str,
+    columns: &'static [&'static str],
+    conditions: Vec<String>,
+    parameters: Vec<DruidSqlType>,
+    limit: Option<usize>,
+}
+
+impl TableQuery {
+    fn new(table: &'static str, columns: &'static [&'static str]) -> Self {
+        Self {
+            table,
+            columns,
+            conditions: Vec::new(),
+            parameters: Vec::new(),
+            limit: None,
+        }
+    }
+
+    fn equals(&mut self, column: &str, value: &str) {
+        self.conditions.push(format!("{column} = ?"));
+        self.parameters.push(DruidSqlType::varchar(value));
+    }
+
+    fn flag(&mut self, column: &str, value: bool) {
+        self.conditions
+            .push(format!("{column} = {}", if value { 1 } else { 0 }));
+    }
+
+    fn to_sql(&self) -> Sql {
+        let mut query = format!("SELECT {} FROM sys.{}", self.columns.join(", "), self.table);
+        if !self.conditions.is_empty() {
+            query.push_str(" WHERE ");
+            query.push_str(&self.conditions.join(" AND "));
+        }
+        if let Some(limit) = self.limit {
+            query.push_str(&format!(" LIMIT {limit}"));
+        }
+        let sql = Sql::new(&query);
+        if self.parameters.is_empty() {
+            sql
+        } else {
+            sql.parameters(&self.parameters)
+        }
+    }
+}
+
+// Druid reports boolean system table columns as 0 or 1.
+fn flag<'de, D: Deserializer<'de>>(deserializer: D) -> Result<bool, D::Error> {
+    optional_flag(deserializer).map(|flag| flag.unwrap_or(false))
+}
+
+fn optional_flag<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<bool>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Flag {
+        Bool(bool),
+        Long(i64),
+    }
+    Ok(
+        Option::<Flag>::deserialize(deserializer)?.map(|flag| match flag {
+            Flag::Bool(b) => b,
+            Flag::Long(l) => l != 0,
+        }),
+    )
+}
+
+/// A row of `sys.segments`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SegmentRow {
+    pub segment_id: String,
+    pub datasource: String,
+    pub start: String,
+    pub end: String,
+    pub size: i64,
+    pub version: String,
+    pub partition_num: Option<i64>,
+    pub num_replicas: i64,
+    pub num_rows: Option<i64>,
+    #[serde(deserialize_with = "flag")]
+    pub is_active: bool,
+    #[serde(deserialize_with = "flag")]
+    pub is_published: bool,
+    #[serde(deserialize_with = "flag")]
+    pub is_available: bool,
+    #[serde(deserialize_with = "flag")]
+    pub is_realtime: bool,
+    #[serde(deserialize_with = "flag")]
+    pub is_overshadowed: bool,
+    pub shard_spec: Option<String>,            // JSON
+    pub dimensions: Option<String>,            // JSON
+    pub metrics: Option<String>,               // JSON
+    pub last_compaction_state: Option<String>, // JSON
+}
+
+#[derive(Debug, Clone)]
+pub struct SegmentsQuery(TableQuery);
+
+/// Query `sys.segments`.
+pub fn segments() -> SegmentsQuery {
+    SegmentsQuery(TableQuery::new(
+        "segments",
+        &[
+            "segment_id",
+            "datasource",
+            "\"start\"",
+            "\"end\"",
+            "size",
+            "version",
+            "partition_num",
+            "num_replicas",
+            "num_rows",
+            "is_active",
+            "is_published",
+            "is_available",
+            "is_realtime",
+            "is_overshadowed",
+            "shard_spec",
+            "dimensions",
+            "metrics",
+            "last_compaction_state",
+        ],
+    ))
+}
+
+impl SegmentsQuery {
+    pub fn datasource(mut self, datasource: &str) -> Self {
+        self.0.equals("datasource", datasource);
+        self
+    }
+
+    pub fn segment_id(mut self, segment_id: &str) -> Self {
+        self.0.equals("segment_id", segment_id);
+        self
+    }
+
+    pub fn is_active(mut self) -> Self {
+        self.0.flag("is_active", true);
+        self
+    }
+
+    pub fn is_published(mut self) -> Self {
+        self.0.flag("is_published", true);
+        self
+    }
+
+    pub fn is_available(mut self) -> Self {
+        self.0.flag("is_available", true);
+        self
+    }
+
+    /// Segments published to deep storage but not loaded on any historical.
+    pub fn is_unavailable(mut self) -> Self {
+        self.0.flag("is_available", false);
+        self
+    }
+
+    pub fn is_realtime(mut self) -> Self {
+        self.0.flag("is_realtime", true);
+        self
+    }
+
+    pub fn is_overshadowed(mut self) -> Self {
+        self.0.flag("is_overshadowed", true);
+        self
+    }
+
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.0.limit = Some(limit);
+        self
+    }
+}
+
+impl SystemTableQuery for SegmentsQuery {
+    type Row = SegmentRow;
+
+    fn to_sql(&self) -> Sql {
+        self.0.to_sql()
+    }
+}
+
+/// A row of `sys.server_segments`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServerSegmentRow {
+    pub server: String,
+    pub segment_id: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct ServerSegmentsQuery(TableQuery);
+
+/// Query `sys.server_segments`.
+pub fn server_segments() -> ServerSegmentsQuery {
+    ServerSegmentsQuery(TableQuery::new(
+        "server_segments",
+        &["server", "segment_id"],
+    ))
+}
+
+impl ServerSegmentsQuery {
+    pub fn server(mut self, server: &str) -> Self {
+        self.0.equals("server", server);
+        self
+    }
+
+    pub fn segment_id(mut self, segment_id: &str) -> Self {
+        self.0.equals("segment_id", segment_id);
+        self
+    }
+
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.0.limit = Some(limit);
+        self
+    }
+}
+
+impl SystemTableQuery for ServerSegmentsQuery {
+    type Row = ServerSegmentRow;
+
+    fn to_sql(&self) -> Sql {
+        self.0.to_sql()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ServerType {
+    Coordinator,
+    Overlord,
+    Broker,
+    Router,
+    Historical,
+    MiddleManager,
+    Indexer,
+    Peon,
+}
+
+impl ServerType {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Coordinator => "coordinator",
+            Self::Overlord => "overlord",
+            Self::Broker => "broker",
+            Self::Router => "router",
+            Self::Historical => "historical",
+            Self::MiddleManager => "middle_manager",
+            Self::Indexer => "indexer",
+            Self::Peon => "peon",
+        }
+    }
+}
+
+/// A row of `sys.servers`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServerRow {
+    pub server: String,
+    pub host: String,
+    pub plaintext_port: i64, // -1 if disabled
+    pub tls_port: i64,       // -1 if disabled
+    pub server_type: ServerType,
+    pub tier: Option<String>,
+    pub curr_size: i64,
+    pub max_size: i64,
+    #[serde(deserialize_with = "optional_flag")]
+    pub is_leader: Option<bool>, // only set for coordinators and overlords
+}
+
+#[derive(Debug, Clone)]
+pub struct ServersQuery(TableQuery);
+
+/// Query `sys.servers`.
+pub fn servers() -> ServersQuery {
+    ServersQuery(TableQuery::new(
+        "servers",
+        &[
+            "server",
+            "host",
+            "plaintext_port",
+            "tls_port",
+            "server_type",
+            "tier",
+            "curr_size",
+            "max_size",
+            "is_leader",
+        ],
+    ))
+}
+
+impl ServersQuery {
+    pub fn server_type(mut self, server_type: ServerType) -> Self {
+        self.0.equals("server_type", server_type.as_str());
+        self
+    }
+
+    pub fn tier(mut self, tier: &str) -> Self {
+        self.0.equals("tier", tier);
+        self
+    }
+
+    pub fn is_leader(mut self) -> Self {
+        self.0.flag("is_leader", true);
+        self
+    }
+
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.0.limit = Some(limit);
+        self
+    }
+}
+
+impl SystemTableQuery for ServersQuery {
+    type Row = ServerRow;
+
+    fn to_sql(&self) -> Sql {
+        self.0.to_sql()
+    }
+}
+
+/// A row of `sys.tasks`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskRow {
+    pub task_id: String,
+    pub group_id: Option<String>,
+    #[serde(rename = "type")]
+    pub task_type: String,
+    pub datasource: String,
+    pub created_time: DateTime<Utc>,
+    pub queue_insertion_time: Option<DateTime<Utc>>,
+    pub status: TaskState,
+    pub runner_status: Option<String>, // "NONE" once complete
+    pub duration: Option<i64>,         // milliseconds, -1 while running
+    pub location: Option<String>,
+    pub host: Option<String>,
+    pub plaintext_port: Option<i64>,
+    pub tls_port: Option<i64>,
+    pub error_msg: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct TasksQuery(TableQuery);
+
+/// Returned by [`TasksQuery::status`] for [`TaskState::Unknown`].
+#[derive(Debug, Clone, thiserror::Error)]
+#[error("tasks can't be filtered on an unknown state")]
+pub struct UnknownTaskState;
+
+/// Query `sys.tasks`.
+pub fn tasks() -> TasksQuery {
+    TasksQuery(TableQuery::new(
+        "tasks",
+        &[
+            "task_id",
+            "group_id",
+            "\"type\"",
+            "datasource",
+            "created_time",
+            "queue_insertion_time",
+            "status",
+            "runner_status",
+            "duration",
+            "location",
+            "host",
+            "plaintext_port",
+            "tls_port",
+            "error_msg",
+        ],
+    ))
+}
+
+impl TasksQuery {
+    pub fn datasource(mut self, datasource: &str) -> Self {
+        self.0.equals("datasource", datasource);
+        self
+    }
+
+    pub fn task_type(mut self, task_type: &str) -> Self {
+        self.0.equals("\"type\"", task_type);
+        self
+    }
+
+    /// Fails on [`TaskState::Unknown`], which no row has.
+    pub fn status(mut self, status: TaskState) -> Result<Self, UnknownTaskState> {
+        let status = match status {
+            TaskState::Waiting => "WAITING",
+            TaskState::Pending => "PENDING",
+            TaskState::Running => "RUNNING",
+            TaskState::Success => "SUCCESS",
+            TaskState::Failed => "FAILED",
+            TaskState::Unknown => return Err(UnknownTaskState),
+        };
+        self.0.equals("status", status);
+        Ok(self)
+    }
+
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.0.limit = Some(limit);
+        self
+    }
+}
+
+impl SystemTableQuery for TasksQuery {
+    type Row = TaskRow;
+
+    fn to_sql(&self) -> Sql {
+        self.0.to_sql()
+    }
+}
+
+/// A row of `sys.supervisors`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SupervisorRow {
+    pub supervisor_id: String,
+    pub state: String,
+    pub detailed_state: String,
+    #[serde(deserialize_with = "flag")]
+    pub healthy: bool,
+    #[serde(rename = "type")]
+    pub supervisor_type: String,
+    pub source: Option<String>, // topic or stream
+    #[serde(deserialize_with = "flag")]
+    pub suspended: bool,
+    pub spec: Option<String>, // JSON
+}
+
+#[derive(Debug, Clone)]
+pub struct SupervisorsQuery(TableQuery);
+
+/// Query `sys.supervisors`.
+pub fn supervisors() -> SupervisorsQuery {
+    SupervisorsQuery(TableQuery::new(
+        "supervisors",
+        &[
+            "supervisor_id",
+            "state",
+            "detailed_state",
+            "healthy",
+            "\"type\"",
+            "source",
+            "suspended",
+            "spec",
+        ],
+    ))
+}
+
+impl SupervisorsQuery {
+    pub fn supervisor_id(mut self, supervisor_id: &str) -> Self {
+        self.0.equals("supervisor_id", supervisor_id);
+        self
+    }
+
+    pub fn supervisor_type(mut self, supervisor_type: &str) -> Self {
+        self.0.equals("\"type\"", supervisor_type);
+        self
+    }
+
+    pub fn is_healthy(mut self) -> Self {
+        self.0.flag("healthy", true);
+        self
+    }
+
+    pub fn is_unhealthy(mut self) -> Self {
+        self.0.flag("healthy", false);
+        self
+    }
+
+    pub fn is_suspended(mut self) -> Self {
+        self.0.flag("suspended", true);
+        self
+    }
+
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.0.limit = Some(limit);
+        self
+    }
+}
+
+impl SystemTableQuery for SupervisorsQuery {
+    type Row = SupervisorRow;
+
+    fn to_sql(&self) -> Sql {
+        self.0.to_sql()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{segments, servers, tasks, ServerRow, ServerType, SystemTableQuery, TaskRow};
+    use crate::{components::druid_types::DruidSqlType, ingestion::tasks::TaskState};
+
+    #[test]
+    fn builds_parameterized_query() {
+        let sql = segments()
+            .datasource("wikipedia")
+            .is_published()
+            .limit(10)
+            .to_sql();
+        assert!(sql
+            .query
+            .starts_with("SELECT segment_id, datasource, \"start\""));
+        assert!(sql
+            .query
+            .ends_with("FROM sys.segments WHERE datasource = ? AND is_published = 1 LIMIT 10"));
+        assert!(matches!(
+            sql.parameters.as_deref(),
+            Some([DruidSqlType::Varchar(d)]) if d == "wikipedia"
+        ));
+
+        let sql = servers().server_type(ServerType::MiddleManager).to_sql();
+        assert!(matches!(
+            sql.parameters.as_deref(),
+            Some([DruidSqlType::Varchar(t)]) if t == "middle_manager"
+        ));
+
+        let sql = tasks().status(TaskState::Running).unwrap().to_sql();
+        assert!(matches!(
+            sql.parameters.as_deref(),
+            Some([DruidSqlType::Varchar(s)]) if s == "RUNNING"
+        ));
+        assert!(tasks().status(TaskState::Unknown).is_err());
+    }
+
+    #[test]
+    fn decodes_rows() {
+        let server: ServerRow = serde_json::from_str(
+            r#"{"server": "localhost:8081", "host": "localhost", "plaintext_port": 8081, "tls_port": -1,
+                "server_type": "coordinator", "tier": null, "curr_size": 0, "max_size": 0, "is_leader": 1}"#,
+        )
+        .unwrap();
+        assert_eq!(server.is_leader, Some(true));
+
+        let task: TaskRow = serde_json::from_str(
+            r#"{"task_id": "index_1", "group_id": "index_1", "type": "index_parallel", "datasource": "wikipedia",
+                "created_time": "2023-05-01T10:00:00.000Z", "queue_insertion_time": "1970-01-01T00:00:00.000Z",
+                "status": "SUCCESS", "runner_status": "NONE", "duration": 12000, "location": null,
+                "host": null, "plaintext_port": -1, "tls_port": -1, "error_msg": null}"#,
+        )
+        .unwrap();
+        assert_eq!(task.duration, Some(12000));
+    }
+}